@@ -0,0 +1,809 @@
+//! `gunmetal`, the command-line interface. It runs the same code as the app, for use over SSH,
+//! in scripts and when provisioning machines without a display.
+//!
+//! Exits with 0 on success, 1 when a command fails (or `path check` finds a problem), and 2 for invalid arguments.
+//! Read commands take `--format`, see `output`.
+
+// explicit `return` is the style of this codebase
+#![allow(clippy::needless_return)]
+
+mod output;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::json;
+use std::io::Write;
+use std::process::ExitCode;
+
+use gunmetal_core::backups::{list_all_backups, list_backups, restore_backup};
+use gunmetal_core::dotenv::{export_dotenv, import_dotenv};
+use gunmetal_core::env_backend::Position;
+use gunmetal_core::env_export::{export_env, save_export, ExportFormat};
+use gunmetal_core::fs_root::{rooted, set_root_override};
+use gunmetal_core::path_check::{check_path, which};
+use gunmetal_core::project_env::{add_project_var, open_project};
+use gunmetal_core::provenance::{resolve, Origin, SCHEMA_VERSION};
+use gunmetal_core::search::{search_vars, Field, Health, MatchMode, SearchQuery};
+use gunmetal_core::secrets::{
+    list_secrets, mark_secret, reveal_secret, secret_exports, unmark_secret, vault_path,
+};
+use gunmetal_core::settings::load_settings;
+use gunmetal_core::settings_utils::{get_config_path, get_shell_profile_path};
+use gunmetal_core::shell_quote::ShellDialect;
+use gunmetal_core::snapshots::{diff_snapshots, list_snapshots, save_snapshot};
+use gunmetal_core::system_scope::{Scope, SystemTarget};
+use gunmetal_core::{add_vars, i18n, logging, remove_vars, ErrorInfo, GunmetalError};
+use output::{
+    print_diff, print_items, print_json, print_line, print_raw, print_search, print_vars, Format,
+};
+
+#[derive(Parser)]
+#[command(
+    name = "gunmetal",
+    version,
+    about = "View and edit environment variables"
+)]
+struct Cli {
+    /// read and write the files under DIR instead of /, ex: a mounted disk image. Overrides the "root" setting
+    #[arg(long, global = true, value_name = "DIR")]
+    root: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print every variable with where it's set
+    List {
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+    /// Print the value of one variable
+    Get {
+        key: String,
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+    /// Add a value to a variable, in front of its existing values unless --append is given
+    Add {
+        key: String,
+        value: String,
+        /// add the value before the existing ones (the default)
+        #[arg(long, conflicts_with = "append")]
+        prepend: bool,
+        /// add the value after the existing ones
+        #[arg(long)]
+        append: bool,
+        /// add it for every user instead of only yours
+        #[arg(long)]
+        system: bool,
+        /// the file written with --system
+        #[arg(long, value_enum, requires = "system", default_value = "profile-d")]
+        system_file: SystemFileArg,
+        /// print the change as a diff instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Remove one value from a variable
+    Remove {
+        key: String,
+        value: String,
+        /// print the change as a diff instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Commands about PATH
+    Path {
+        #[command(subcommand)]
+        command: PathCommand,
+    },
+    /// Print where a command is found in PATH
+    Which {
+        name: String,
+        /// print every match, not only the one that's used
+        #[arg(short, long)]
+        all: bool,
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+    /// Commands about the backups taken before each change
+    Backup {
+        #[command(subcommand)]
+        command: BackupCommand,
+    },
+    /// Save every variable to compare against later
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommand,
+    },
+    /// Write every variable, with where it's set, whether it's a list and the problems found, for inventories
+    Export {
+        /// format to write, guessed from --output's extension if not given (JSON for stdout)
+        #[arg(long, value_enum)]
+        format: Option<ExportAs>,
+        /// hide the values of variables holding secrets
+        #[arg(long)]
+        redact: bool,
+        /// file to write instead of printing it
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+    /// Commands about a project directory's .envrc and .env files
+    Project {
+        #[command(subcommand)]
+        command: ProjectCommand,
+    },
+    /// Copy variables between .env files and the shell profile
+    Dotenv {
+        #[command(subcommand)]
+        command: DotenvCommand,
+    },
+    /// Find variables by name, value or the file they're set in, best matches first
+    Search {
+        /// text to look for, every variable passing the filters if not given
+        #[arg(default_value = "")]
+        text: String,
+        /// take the text as a regular expression
+        #[arg(long, conflicts_with = "fuzzy")]
+        regex: bool,
+        /// match the text's characters in order, with anything in between
+        #[arg(long)]
+        fuzzy: bool,
+        /// tell upper and lower case letters apart
+        #[arg(long)]
+        case_sensitive: bool,
+        /// only look in these fields, all of them if not given
+        #[arg(long = "in", value_enum, value_delimiter = ',', value_name = "FIELD")]
+        fields: Vec<FieldArg>,
+        /// only variables set in one of these places
+        #[arg(
+            long = "origin",
+            value_enum,
+            value_delimiter = ',',
+            value_name = "ORIGIN"
+        )]
+        origins: Vec<OriginArg>,
+        /// only variables with (unhealthy) or without (healthy) problems
+        #[arg(long, value_enum)]
+        health: Option<HealthArg>,
+        /// print at most N variables
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+    /// Commands about secret variables, kept in an encrypted vault instead of the profile.
+    /// The passphrase is read from GUNMETAL_VAULT_PASSPHRASE, or asked for on the terminal
+    Secret {
+        #[command(subcommand)]
+        command: SecretCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum PathCommand {
+    /// Report entries of PATH that are missing, relative or repeated
+    Check {
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+}
+
+#[derive(Subcommand)]
+enum BackupCommand {
+    /// List the backups of a file, or of every file
+    List {
+        file: Option<String>,
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+    /// Put a file back the way it was in a backup
+    Restore {
+        /// file to restore, the shell profile if not given
+        file: Option<String>,
+        /// name of the backup to restore, the newest if not given
+        #[arg(long)]
+        backup: Option<String>,
+        /// print the change as a diff instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotCommand {
+    /// Save every variable as it is now, with where it's set
+    Save {
+        /// name to save it under, the time it's taken if not given
+        name: Option<String>,
+    },
+    /// List the saved snapshots
+    List {
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+    /// Show what changed between two snapshots, or since a snapshot
+    Diff {
+        /// the older snapshot
+        from: String,
+        /// the newer snapshot, the variables as they are now if not given
+        to: Option<String>,
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProjectCommand {
+    /// Print the variables in a directory: the global ones with the .envrc and .env files up the tree applied
+    Show {
+        /// the project directory, the current one if not given
+        #[arg(default_value = ".")]
+        dir: String,
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+    /// Add a value to a variable in the directory's .envrc, in front of its existing values unless --append is given
+    Add {
+        key: String,
+        value: String,
+        /// the project directory, the current one if not given
+        #[arg(long, default_value = ".")]
+        dir: String,
+        /// add the value after the existing ones
+        #[arg(long)]
+        append: bool,
+        /// print the change as a diff instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportAs {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl From<ExportAs> for ExportFormat {
+    fn from(format: ExportAs) -> ExportFormat {
+        return match format {
+            ExportAs::Json => ExportFormat::Json,
+            ExportAs::Yaml => ExportFormat::Yaml,
+            ExportAs::Toml => ExportFormat::Toml,
+        };
+    }
+}
+
+#[derive(Subcommand)]
+enum DotenvCommand {
+    /// Set every variable of a .env file in the shell profile
+    Import {
+        file: String,
+        /// print the change as a diff instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write variables as they are now to a .env file
+    Export {
+        #[arg(required = true)]
+        keys: Vec<String>,
+        /// file to write, replaced if it exists
+        #[arg(short, long, value_name = "FILE", default_value = ".env")]
+        output: String,
+        /// print the change as a diff instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FieldArg {
+    Key,
+    Value,
+    File,
+}
+
+impl From<FieldArg> for Field {
+    fn from(field: FieldArg) -> Field {
+        return match field {
+            FieldArg::Key => Field::Key,
+            FieldArg::Value => Field::Value,
+            FieldArg::File => Field::File,
+        };
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SystemFileArg {
+    /// /etc/profile.d/gunmetal.sh, read by login shells
+    ProfileD,
+    /// /etc/environment, read at login
+    Environment,
+    /// /etc/environment.d/60-gunmetal.conf, read by the systemd user manager
+    EnvironmentD,
+}
+
+impl From<SystemFileArg> for SystemTarget {
+    fn from(file: SystemFileArg) -> SystemTarget {
+        return match file {
+            SystemFileArg::ProfileD => SystemTarget::ProfileD,
+            SystemFileArg::Environment => SystemTarget::Environment,
+            SystemFileArg::EnvironmentD => SystemTarget::EnvironmentD,
+        };
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OriginArg {
+    Process,
+    Profile,
+    System,
+    Project,
+}
+
+impl From<OriginArg> for Origin {
+    fn from(origin: OriginArg) -> Origin {
+        return match origin {
+            OriginArg::Process => Origin::Process,
+            OriginArg::Profile => Origin::Profile,
+            OriginArg::System => Origin::System,
+            OriginArg::Project => Origin::Project,
+        };
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum HealthArg {
+    Healthy,
+    Unhealthy,
+}
+
+impl From<HealthArg> for Health {
+    fn from(health: HealthArg) -> Health {
+        return match health {
+            HealthArg::Healthy => Health::Healthy,
+            HealthArg::Unhealthy => Health::Unhealthy,
+        };
+    }
+}
+
+#[derive(Subcommand)]
+enum SecretCommand {
+    /// Move a variable's value to the vault. The profile gets a line that loads it at startup instead
+    Add {
+        key: String,
+        /// the secret value, the variable's current value if not given
+        value: Option<String>,
+        /// print the change as a diff instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Put a secret's value back in the profile and take it out of the vault
+    Remove {
+        key: String,
+        /// print the change as a diff instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List the secret variables, without their values
+    List {
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+    /// Print the value of a secret variable
+    Reveal { key: String },
+    /// Print the commands that set every secret, for the line in the profile to run
+    Env {
+        #[arg(long, value_enum, default_value_t = ShellArg::Posix)]
+        shell: ShellArg,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ShellArg {
+    /// sh, bash, zsh and friends
+    Posix,
+    Fish,
+}
+
+impl From<ShellArg> for ShellDialect {
+    fn from(shell: ShellArg) -> ShellDialect {
+        return match shell {
+            ShellArg::Posix => ShellDialect::Posix,
+            ShellArg::Fish => ShellDialect::Fish,
+        };
+    }
+}
+
+impl Command {
+    /// Output format asked for, tables for the commands that don't take one
+    fn format(&self) -> Format {
+        return match self {
+            Command::List { format }
+            | Command::Get { format, .. }
+            | Command::Which { format, .. }
+            | Command::Path {
+                command: PathCommand::Check { format },
+            }
+            | Command::Backup {
+                command: BackupCommand::List { format, .. },
+            }
+            | Command::Snapshot {
+                command: SnapshotCommand::List { format } | SnapshotCommand::Diff { format, .. },
+            }
+            | Command::Project {
+                command: ProjectCommand::Show { format, .. },
+            }
+            | Command::Search { format, .. }
+            | Command::Secret {
+                command: SecretCommand::List { format },
+            } => *format,
+            _ => Format::Table,
+        };
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if cli.root.is_some() {
+        set_root_override(cli.root);
+    }
+    // the command still runs without a log file
+    let _ = logging::init_logging();
+    if let Ok(settings) =
+        get_config_path().and_then(|path| load_settings(&format!("{}/settings.json", path)))
+    {
+        i18n::set_locale(&settings.locale);
+    }
+
+    let format = cli.command.format();
+    match run(cli.command) {
+        Ok(code) => return code,
+        // scripts asking for JSON get the error as JSON too, with its code
+        Err(err) if format == Format::Json => {
+            eprintln!("{}", json!(err));
+            return ExitCode::FAILURE;
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!("{}", err.suggestion());
+            return ExitCode::FAILURE;
+        }
+    }
+}
+
+/// Runs a command and prints its output
+/// ### Returns
+/// The exit code, or the error to print
+fn run(command: Command) -> Result<ExitCode, GunmetalError> {
+    match command {
+        Command::List { format } => {
+            written(print_vars(&resolve()?, format))?;
+        }
+        Command::Get { key, format } => {
+            written(print_vars(&resolve()?.only(&key)?, format))?;
+        }
+        Command::Add {
+            key,
+            value,
+            prepend: _,
+            append,
+            system,
+            system_file,
+            dry_run,
+        } => {
+            let position = match append {
+                true => Position::Append,
+                false => Position::Prepend,
+            };
+            let scope = match system {
+                true => Some(Scope::System),
+                false => None,
+            };
+            let output = add_vars::add_var(
+                key,
+                value,
+                Some(dry_run),
+                scope,
+                Some(position),
+                Some(system_file.into()),
+            )?;
+            written(print_line(&output))?;
+        }
+        Command::Remove {
+            key,
+            value,
+            dry_run,
+        } => {
+            let output = remove_vars::remove_var(key, value, Some(dry_run))?;
+            written(print_line(&output))?;
+        }
+        Command::Path {
+            command: PathCommand::Check { format },
+        } => {
+            let issues = check_path()?;
+            let entries: Vec<String> = issues.iter().map(|issue| issue.entry.to_owned()).collect();
+            let rows = issues
+                .iter()
+                .map(|issue| vec![(issue.index + 1).to_string(), issue.message.to_owned()])
+                .collect();
+            let json = json!({ "schema_version": SCHEMA_VERSION, "issues": issues });
+            written(print_items(
+                format,
+                &entries,
+                json,
+                vec!["ENTRY", "PROBLEM"],
+                rows,
+            ))?;
+            if !issues.is_empty() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Which { name, all, format } => {
+            let mut found = which(&name)?;
+            if !all {
+                found.truncate(1);
+            }
+            let rows = found.iter().map(|path| vec![path.to_owned()]).collect();
+            let json = json!({ "schema_version": SCHEMA_VERSION, "name": name, "matches": found });
+            written(print_items(format, &found, json, vec!["PATH"], rows))?;
+        }
+        Command::Backup {
+            command: BackupCommand::List { file, format },
+        } => {
+            let backups = match file {
+                // files are named as seen from inside the root
+                Some(file) => list_backups(&rooted(&file)?),
+                None => list_all_backups(),
+            };
+            let paths: Vec<String> = backups
+                .iter()
+                .map(|backup| backup.path.to_owned())
+                .collect();
+            let rows = backups
+                .iter()
+                .map(|backup| vec![backup.name.to_owned(), backup.source.to_owned()])
+                .collect();
+            let json = json!({ "schema_version": SCHEMA_VERSION, "backups": backups });
+            written(print_items(
+                format,
+                &paths,
+                json,
+                vec!["NAME", "FILE"],
+                rows,
+            ))?;
+        }
+        Command::Backup {
+            command:
+                BackupCommand::Restore {
+                    file,
+                    backup,
+                    dry_run,
+                },
+        } => {
+            let file = match file {
+                Some(file) => rooted(&file)?,
+                None => get_shell_profile_path()?,
+            };
+            let output = restore_backup(&file, backup.as_deref(), Some(dry_run))?;
+            written(print_line(&output))?;
+        }
+        Command::Snapshot {
+            command: SnapshotCommand::Save { name },
+        } => {
+            let saved = save_snapshot(name)?;
+            written(print_line(&saved.path))?;
+        }
+        Command::Snapshot {
+            command: SnapshotCommand::List { format },
+        } => {
+            let snapshots = list_snapshots();
+            let names: Vec<String> = snapshots.iter().map(|info| info.name.to_owned()).collect();
+            let rows = snapshots
+                .iter()
+                .map(|info| vec![info.name.to_owned(), info.taken_at.to_owned()])
+                .collect();
+            let json = json!({ "schema_version": SCHEMA_VERSION, "snapshots": snapshots });
+            written(print_items(
+                format,
+                &names,
+                json,
+                vec!["NAME", "TAKEN AT"],
+                rows,
+            ))?;
+        }
+        Command::Snapshot {
+            command: SnapshotCommand::Diff { from, to, format },
+        } => {
+            let diff = diff_snapshots(&from, to.as_deref())?;
+            written(print_diff(&diff, format))?;
+        }
+        Command::Export {
+            format,
+            redact,
+            output,
+        } => {
+            let format = format.map(ExportFormat::from);
+            match output {
+                Some(path) => {
+                    let saved = save_export(path, format, Some(redact))?;
+                    written(print_line(&saved))?;
+                }
+                None => {
+                    let text = export_env(format.unwrap_or(ExportFormat::Json), redact)?;
+                    written(print_raw(&text))?;
+                }
+            }
+        }
+        Command::Project {
+            command: ProjectCommand::Show { dir, format },
+        } => {
+            let project = open_project(&dir)?;
+            // JSON has the directory and the files read too, the other formats only the variables
+            match format {
+                Format::Json => written(print_json(&project))?,
+                _ => written(print_vars(&project.env, format))?,
+            }
+        }
+        Command::Project {
+            command:
+                ProjectCommand::Add {
+                    key,
+                    value,
+                    dir,
+                    append,
+                    dry_run,
+                },
+        } => {
+            let position = match append {
+                true => Position::Append,
+                false => Position::Prepend,
+            };
+            let output = add_project_var(&dir, &key, &value, Some(dry_run), Some(position))?;
+            written(print_line(&output))?;
+        }
+        Command::Dotenv {
+            command: DotenvCommand::Import { file, dry_run },
+        } => {
+            let output = import_dotenv(file, None, Some(dry_run))?;
+            written(print_line(&output))?;
+        }
+        Command::Dotenv {
+            command:
+                DotenvCommand::Export {
+                    keys,
+                    output,
+                    dry_run,
+                },
+        } => {
+            let output = export_dotenv(keys, output, Some(dry_run))?;
+            written(print_line(&output))?;
+        }
+        Command::Search {
+            text,
+            regex,
+            fuzzy,
+            case_sensitive,
+            fields,
+            origins,
+            health,
+            limit,
+            format,
+        } => {
+            let mode = match (regex, fuzzy) {
+                (true, _) => MatchMode::Regex,
+                (_, true) => MatchMode::Fuzzy,
+                _ => MatchMode::Substring,
+            };
+            let query = SearchQuery {
+                text,
+                mode,
+                case_sensitive,
+                fields: fields.into_iter().map(Field::from).collect(),
+                origins: origins.into_iter().map(Origin::from).collect(),
+                health: health.map(Health::from),
+                limit,
+            };
+            written(print_search(&search_vars(query)?, format))?;
+        }
+        Command::Secret {
+            command:
+                SecretCommand::Add {
+                    key,
+                    value,
+                    dry_run,
+                },
+        } => {
+            // a new vault's passphrase is typed twice, a typo would lock the secrets away
+            let new_vault = !vault_path()?.exists();
+            let output = mark_secret(key, value, passphrase(new_vault)?, Some(dry_run))?;
+            written(print_line(&output))?;
+        }
+        Command::Secret {
+            command: SecretCommand::Remove { key, dry_run },
+        } => {
+            let output = unmark_secret(key, passphrase(false)?, Some(dry_run))?;
+            written(print_line(&output))?;
+        }
+        Command::Secret {
+            command: SecretCommand::List { format },
+        } => {
+            let secrets = list_secrets();
+            let rows = secrets.iter().map(|key| vec![key.to_owned()]).collect();
+            let json = json!({ "schema_version": SCHEMA_VERSION, "secrets": secrets });
+            written(print_items(format, &secrets, json, vec!["KEY"], rows))?;
+        }
+        Command::Secret {
+            command: SecretCommand::Reveal { key },
+        } => {
+            let value = reveal_secret(key, passphrase(false)?)?;
+            written(print_line(&value))?;
+        }
+        Command::Secret {
+            command: SecretCommand::Env { shell },
+        } => {
+            let exports = secret_exports(&passphrase(false)?, shell.into())?;
+            written(print_raw(&exports))?;
+        }
+    }
+    return Ok(ExitCode::SUCCESS);
+}
+
+/// Gets the vault's passphrase from GUNMETAL_VAULT_PASSPHRASE, or asks for it on the terminal without echoing it.
+/// The terminal is used directly, so the prompt works inside `$(gunmetal secret env)`.
+/// ### Arguments
+/// - confirm: if true, the passphrase is asked for twice and must match
+fn passphrase(confirm: bool) -> Result<String, GunmetalError> {
+    if let Ok(passphrase) = std::env::var("GUNMETAL_VAULT_PASSPHRASE") {
+        if !passphrase.is_empty() {
+            return Ok(passphrase);
+        }
+    }
+    let tty_err = |err: std::io::Error| {
+        GunmetalError::InvalidInput(
+            ErrorInfo::new(i18n::message("vault-no-terminal-err", &[])).io(&err),
+        )
+    };
+    let mut tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .map_err(tty_err)?;
+
+    let mut ask = |prompt: &str| -> std::io::Result<String> {
+        write!(tty, "{} ", prompt)?;
+        tty.flush()?;
+        let _ = std::process::Command::new("stty")
+            .arg("-echo")
+            .stdin(tty.try_clone()?)
+            .status();
+        let mut line = String::new();
+        let read = std::io::BufRead::read_line(&mut std::io::BufReader::new(&tty), &mut line);
+        let _ = std::process::Command::new("stty")
+            .arg("echo")
+            .stdin(tty.try_clone()?)
+            .status();
+        writeln!(tty)?;
+        read?;
+        return Ok(String::from(line.trim_end_matches(['\r', '\n'])));
+    };
+    let passphrase = ask(&i18n::message("vault-prompt", &[])).map_err(tty_err)?;
+    if confirm && ask(&i18n::message("vault-prompt-repeat", &[])).map_err(tty_err)? != passphrase {
+        return Err(GunmetalError::InvalidInput(ErrorInfo::new(i18n::message(
+            "vault-mismatch-err",
+            &[],
+        ))));
+    }
+    return Ok(passphrase);
+}
+
+/// Turns an error writing the output into a GunmetalError. A closed pipe (ex: `gunmetal list | head`) isn't an error.
+fn written(result: std::io::Result<()>) -> Result<(), GunmetalError> {
+    return match result {
+        Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => Err(
+            GunmetalError::WriteFailed(ErrorInfo::new(err.to_string()).io(&err)),
+        ),
+        _ => Ok(()),
+    };
+}
@@ -0,0 +1,251 @@
+//! The output formats of the read commands. Scripts should use `json`, `env` or `shell`;
+//! `table` is for people and may change between versions.
+
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::{self, Write};
+
+use gunmetal_core::diff_utils::EntryChange;
+use gunmetal_core::provenance::{Origin, ResolvedEnv, Source, SCHEMA_VERSION};
+use gunmetal_core::search::SearchResults;
+use gunmetal_core::shell_quote::{is_name, quote, ShellDialect};
+use gunmetal_core::snapshots::SnapshotDiff;
+
+/// Separates the entries of a list variable when it's printed
+pub const SEPARATOR: &str = if cfg!(target_os = "windows") {
+    ";"
+} else {
+    ":"
+};
+
+#[derive(Clone, Copy, PartialEq, Debug, ValueEnum)]
+pub enum Format {
+    /// aligned columns, for reading
+    Table,
+    /// JSON, see README.md for the schema
+    Json,
+    /// NUL-terminated records, like `env -0`
+    Env,
+    /// lines that can be passed to `eval`
+    Shell,
+}
+
+/// A result with `schema_version` added in front of its fields
+#[derive(Serialize)]
+struct Versioned<T: Serialize> {
+    schema_version: u32,
+    #[serde(flatten)]
+    value: T,
+}
+
+/// Prints variables: `KEY=VALUE` records for `env`, `export KEY='VALUE'` for `shell`,
+/// the whole `ResolvedEnv` for `json`, and the key, where it's set and the value for `table`
+pub fn print_vars(resolved: &ResolvedEnv, format: Format) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    match format {
+        Format::Json => print_json(resolved)?,
+        // a secret's mask isn't a value to set, so secrets are left out of `env` and `shell`
+        Format::Env => {
+            for var in resolved.vars.iter().filter(|var| !var.secret) {
+                write!(out, "{}={}\0", var.key, var.entries.join(SEPARATOR))?;
+            }
+        }
+        Format::Shell => {
+            for var in resolved.vars.iter().filter(|var| !var.secret) {
+                // a name the shell can't assign would break the whole `eval`
+                if !is_name(&var.key) {
+                    continue;
+                }
+                let value = quote(&var.entries.join(SEPARATOR), ShellDialect::Posix);
+                writeln!(out, "export {}={}", var.key, value)?;
+            }
+        }
+        Format::Table => {
+            let rows = resolved
+                .vars
+                .iter()
+                .map(|var| {
+                    vec![
+                        var.key.to_owned(),
+                        describe_source(var.sources.last()),
+                        var.entries.join(SEPARATOR),
+                    ]
+                })
+                .collect();
+            print_table(vec!["KEY", "SET IN", "VALUE"], rows)?;
+            for issue in &resolved.diagnostics {
+                eprintln!("{}", issue.message);
+            }
+        }
+    }
+    return out.flush();
+}
+
+/// Prints the result of a command that isn't about variables
+/// ### Arguments
+/// - items: what the command found, one per record for `env` and one per line for `shell`
+/// - json: the full result for `json`
+/// - header, rows: the columns for `table`
+pub fn print_items(
+    format: Format,
+    items: &[String],
+    json: impl Serialize,
+    header: Vec<&str>,
+    rows: Vec<Vec<String>>,
+) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    match format {
+        Format::Json => print_json(&json)?,
+        Format::Env => {
+            for item in items {
+                write!(out, "{}\0", item)?;
+            }
+        }
+        Format::Shell => {
+            for item in items {
+                writeln!(out, "{}", quote(item, ShellDialect::Posix))?;
+            }
+        }
+        Format::Table => print_table(header, rows)?,
+    }
+    return out.flush();
+}
+
+/// Prints what changed between two snapshots: the changed keys for `env` and `shell`, and for `table`
+/// a row per variable plus one per added or removed entry of a list
+pub fn print_diff(diff: &SnapshotDiff, format: Format) -> io::Result<()> {
+    let mut keys: Vec<String> = Vec::new();
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for (change, vars) in [("added", &diff.added), ("removed", &diff.removed)] {
+        for var in vars {
+            keys.push(var.key.to_owned());
+            rows.push(vec![
+                String::from(change),
+                var.key.to_owned(),
+                var.entries.join(SEPARATOR),
+            ]);
+        }
+    }
+    for change in &diff.changed {
+        keys.push(change.key.to_owned());
+        if change.entries.is_empty() {
+            let value = format!(
+                "{} -> {}",
+                change.before.join(SEPARATOR),
+                change.after.join(SEPARATOR)
+            );
+            rows.push(vec![String::from("changed"), change.key.to_owned(), value]);
+            continue;
+        }
+        rows.push(vec![
+            String::from("changed"),
+            change.key.to_owned(),
+            String::new(),
+        ]);
+        for entry in &change.entries {
+            let sign = match entry.change {
+                EntryChange::Added => "+",
+                EntryChange::Removed => "-",
+                EntryChange::Kept => continue,
+            };
+            rows.push(vec![
+                String::new(),
+                String::new(),
+                format!("{} {}", sign, entry.entry),
+            ]);
+        }
+    }
+    keys.sort();
+
+    let json = Versioned {
+        schema_version: SCHEMA_VERSION,
+        value: diff,
+    };
+    return print_items(format, &keys, json, vec!["CHANGE", "KEY", "VALUE"], rows);
+}
+
+/// Prints the variables a search found, best first: their names for `env` and `shell`, and for `table`
+/// a row per variable with its score
+pub fn print_search(results: &SearchResults, format: Format) -> io::Result<()> {
+    let keys: Vec<String> = results.hits.iter().map(|hit| hit.key.to_owned()).collect();
+    let rows = results
+        .hits
+        .iter()
+        .map(|hit| {
+            vec![
+                hit.key.to_owned(),
+                hit.score.to_string(),
+                describe_source(hit.sources.last()),
+                hit.entries.join(SEPARATOR),
+            ]
+        })
+        .collect();
+    return print_items(
+        format,
+        &keys,
+        results,
+        vec!["KEY", "SCORE", "SET IN", "VALUE"],
+        rows,
+    );
+}
+
+/// Prints a message on its own line, like `println!` but returning write errors (ex: a closed pipe) instead of panicking
+pub fn print_line(text: &str) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    return writeln!(out, "{}", text);
+}
+
+/// Prints text as is, for output that already ends in a newline (ex: `secret env`)
+pub fn print_raw(text: &str) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    write!(out, "{}", text)?;
+    return out.flush();
+}
+
+/// Prints a value as indented JSON on its own line
+pub fn print_json(value: &impl Serialize) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    serde_json::to_writer_pretty(&mut out, value)?;
+    return writeln!(out);
+}
+
+/// Prints rows with every column but the last padded to the same width
+fn print_table(header: Vec<&str>, rows: Vec<Vec<String>>) -> io::Result<()> {
+    let mut widths: Vec<usize> = header.iter().map(|title| title.chars().count()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = io::stdout().lock();
+    let header: Vec<String> = header.into_iter().map(String::from).collect();
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let mut line = String::new();
+        for (index, cell) in row.iter().enumerate() {
+            if index + 1 == row.len() {
+                line.push_str(cell);
+            } else {
+                line.push_str(&format!("{:width$}  ", cell, width = widths[index]));
+            }
+        }
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    return Ok(());
+}
+
+/// Describes where a value was set, ex: "/home/me/.bashrc:12"
+fn describe_source(source: Option<&Source>) -> String {
+    let source = match source {
+        Some(source) => source,
+        None => return String::from("-"),
+    };
+    return match (&source.file, source.line, source.origin) {
+        (Some(file), Some(line), _) => format!("{}:{}", file, line),
+        (Some(file), None, _) => file.to_owned(),
+        (None, _, Origin::Process) => String::from("process"),
+        (None, _, Origin::Profile) => String::from("profile"),
+        (None, _, Origin::System) => String::from("system"),
+        (None, _, Origin::Project) => String::from("project"),
+    };
+}
@@ -0,0 +1,140 @@
+# Deutsche Meldungen. Fehlende Meldungen werden auf Englisch angezeigt.
+
+full-error = Vollständiger Fehler:
+
+## Fehler
+
+mkdir-err = Das Verzeichnis { $dir } konnte nicht angelegt werden. Bitte folge der Anleitung auf der Hilfeseite, um ein Einstellungsverzeichnis anzulegen.
+make-file-err = Die Datei { $path } konnte nicht angelegt werden. Bitte folge den Schritten auf der Hilfeseite, um das Problem zu finden.
+profile-err = Das Shell-Profil ({ $path }) konnte nicht geöffnet werden. Bitte prüfe, ob die Einstellung für das Shell-Profil auf die richtige Datei zeigt, und versuche es erneut.
+settings-read-err = Die Einstellungsdatei { $path } wurde nicht gefunden oder konnte nicht gelesen werden. Bitte stelle sicher, dass die Datei existiert und für deinen Benutzer lesbar ist, und versuche es erneut.
+write-file-err = In { $path } konnte nicht geschrieben werden.
+conflict-err = { $path } wurde außerhalb von Gunmetal geändert, seit die Datei geladen wurde. Lade die Variablen neu und versuche es erneut.
+lock-file-err = { $path } konnte nicht zum Schreiben gesperrt werden. Bitte schließe alle Programme, die in die Datei schreiben könnten, und versuche es erneut.
+value-not-found-err = "{ $value }" ist für { $key } in { $path } nicht gesetzt. Nur Werte aus dem Shell-Profil können bearbeitet oder entfernt werden.
+unsupported-target-err = { $format } können nicht direkt bearbeitet werden.
+change-failed-err = Änderung #{ $number } (an { $key }) kann nicht angewendet werden, daher wurde nichts geändert.
+change-file-err = { $path } ist keines der Profile in den Einstellungen.
+helper-failed-err = Das Hilfsprogramm mit Administratorrechten konnte { $path } nicht ändern. Es wurde nichts geändert.
+invalid-setting-err = Ungültige Einstellung "{ $field }": { $reason }. Die Einstellungen wurden nicht gespeichert.
+settings-version-err = settings.json stammt aus einer neueren Version von Gunmetal (Einstellungsversion { $found }, diese Version liest bis { $supported }). Bitte aktualisiere Gunmetal.
+json-parse-err = Ein Wert fehlt in der Einstellungsdatei. Bitte öffne die Einstellungen und stelle sicher, dass alle Einstellungen gesetzt sind.
+read-only-err = Der Nur-Lesen-Modus ist aktiv, daher wurde nichts geändert. Schalte "read_only" in den Einstellungen aus, um Variablen zu bearbeiten.
+watch-failed-err = Die Profile konnten nicht auf Änderungen überwacht werden. Außerhalb von Gunmetal geänderte Variablen erscheinen erst nach einem Neustart der App.
+no-config-dir-err = Es wurde kein Verzeichnis für die Einstellungen gefunden. Bitte setze GUNMETAL_CONFIG auf das Verzeichnis, in dem die Einstellungen liegen sollen.
+empty-settings-err = Die Einstellungsdatei ist leer. Bitte öffne die Einstellungen und wähle dein Shell-Profil aus, bevor du es erneut versuchst.
+file-missing-err = Die Datei { $path } existiert nicht.
+file-name-unreadable-err = Die Datei existiert nicht. Der Dateiname konnte nicht ausgelesen werden und ist daher eventuell unlesbar.
+file-path-unreadable-err = Die Datei konnte nicht angelegt werden, weil ihr Pfad nicht in lesbaren Text umgewandelt werden konnte.
+helper-request-err = Das Hilfsprogramm mit Administratorrechten hat eine ungültige Anfrage erhalten.
+helper-launch-err = Das Hilfsprogramm mit Administratorrechten konnte nicht gestartet werden. Systemweite Änderungen brauchen pkexec (oder sudo) und das Programm gunmetal-helper neben dieser App.
+elevation-err = Änderungen für alle Benutzer brauchen ein Administratorpasswort, das hier nicht abgefragt werden kann. Führe den Befehl in einem Terminal aus oder installiere einen polkit-Authentifizierungsagenten.
+rolled-back-err = Das Schreiben der Änderungen ist fehlgeschlagen, alle Dateien wurden in ihren vorherigen Zustand zurückversetzt.
+system-scope-unsupported = Systemweite Variablen werden nur unter Linux unterstützt.
+cmd-fail-start = Der Befehl konnte nicht ausgeführt werden
+cmd-failed-err = { $program } wurde mit { $status } beendet
+var-not-set-err = { $key } ist nicht gesetzt.
+command-not-found-err = { $name } wurde in PATH nicht gefunden.
+no-backup-err = Es gibt keine Sicherung von { $path }.
+backup-read-err = Die Sicherung unter { $path } konnte nicht gelesen werden.
+backup-restored = { $path } wurde aus der Sicherung von { $name } wiederhergestellt.
+root-not-dir-err = { $path } ist kein Verzeichnis und kann daher nicht als Wurzel verwendet werden. Es wurde nichts gelesen oder geschrieben.
+root-symlink-loop-err = Zu viele symbolische Links in { $path } unter { $root }.
+no-snapshot-err = Es gibt keinen Schnappschuss namens { $name }.
+snapshot-exists-err = Ein Schnappschuss namens { $name } existiert bereits. Wähle einen anderen Namen.
+snapshot-name-err = "{ $name }" kann nicht als Name eines Schnappschusses verwendet werden. Verwende nur Buchstaben, Ziffern, '.', '-' und '_'.
+snapshot-read-err = Der Schnappschuss unter { $path } konnte nicht gelesen werden.
+dotenv-line-err = Zeile { $line } von { $path } ist keine Zuweisung der Form KEY=VALUE.
+dotenv-quote-err = Der Wert in Anführungszeichen ab Zeile { $line } von { $path } wird nie geschlossen.
+dotenv-read-err = Die .env-Datei unter { $path } konnte nicht gelesen werden.
+import-system-unsupported = Eine .env-Datei kann nicht in die systemweiten Dateien importiert werden. Importiere sie stattdessen in dein Shell-Profil.
+export-err = Die Variablen konnten nicht als { $format } geschrieben werden.
+project-dir-err = { $path } ist kein Verzeichnis.
+vault-read-err = Der Tresor für Geheimnisse unter { $path } konnte nicht gelesen werden.
+vault-passphrase-err = Die Passphrase entsperrt den Tresor für Geheimnisse unter { $path } nicht.
+vault-empty-passphrase-err = Der Tresor für Geheimnisse braucht eine Passphrase.
+vault-key-err = Der Schlüssel des Tresors konnte nicht aus der Passphrase abgeleitet werden.
+no-secret-err = { $key } ist nicht im Tresor für Geheimnisse.
+secret-export-err = { $key } ist geheim, sein Wert wird nicht in .env-Dateien geschrieben.
+secret-write-err = { $key } ist geheim, sein Wert kann nur im Tresor für Geheimnisse geändert werden.
+vault-no-terminal-err = Keine Passphrase für den Tresor: Setze GUNMETAL_VAULT_PASSPHRASE oder führe dies in einem Terminal aus.
+vault-mismatch-err = Die Passphrasen stimmen nicht überein.
+search-regex-err = "{ $pattern }" ist kein gültiger regulärer Ausdruck.
+
+## Prüfung
+
+validation-err-key = Ungültiger Name "{ $input }" ({ $rule }): { $reason }.
+validation-err-value = Ungültiger Wert "{ $input }" ({ $rule }): { $reason }.
+reason-key-empty = der Variablenname ist leer
+reason-key-too-long = Variablennamen dürfen höchstens { $max } Zeichen lang sein
+reason-windows-key-chars = Windows-Variablennamen dürfen weder '=' noch Nullzeichen enthalten
+reason-key-not-identifier = Namen in { $format } dürfen nur Buchstaben, Ziffern und '_' enthalten und nicht mit einer Ziffer beginnen
+reason-value-empty = der Wert ist leer
+reason-value-nul = Werte dürfen keine Nullzeichen enthalten
+reason-value-too-long = Werte in { $format } dürfen höchstens { $max } Zeichen lang sein
+reason-value-multiline = { $format } können keine Werte über mehrere Zeilen speichern
+reason-value-character = { $format } können das Zeichen { $character } nicht speichern
+reason-shell-missing = { $path } existiert nicht
+reason-not-a-name = "{ $key }" ist kein Variablenname
+reason-unreadable = { $path } kann nicht gelesen werden ({ $error })
+reason-unknown-locale = es gibt keine Meldungen in "{ $locale }"
+reason-root-not-dir = { $path } ist kein Verzeichnis
+
+## Dateiformate
+
+format-posix = Dateien vom Typ POSIX-Shellprofil
+format-fish = Dateien vom Typ fish-Konfiguration
+format-environment-d = environment.d-Dateien
+format-pam-environment = /etc/environment
+format-windows = Windows-Umgebungsvariablen
+
+## Erfolgsmeldungen
+
+add-var-success =
+    Variable erfolgreich hinzugefügt!
+
+    Neue Terminals haben die Variable, bereits geöffnete Programme müssen aber neu gestartet werden, um sie zu sehen.
+remove-var-success =
+    Variable erfolgreich entfernt!
+
+    Andere Programme sehen die Änderung erst nach einem Neustart.
+var-added-already = Die Variable wurde bereits hinzugefügt.
+changes-applied = { $count } Änderung(en) erfolgreich angewendet!
+dotenv-exported = { $count } Variable(n) nach { $path } exportiert.
+secret-added = { $key } ist jetzt geheim. Der Wert liegt im Tresor und das Profil lädt ihn beim Start.
+secret-removed = { $key } ist nicht mehr geheim. Der Wert steht wieder im Profil.
+
+## Prompts
+
+vault-prompt = Passphrase des Tresors:
+vault-prompt-repeat = Passphrase wiederholen:
+
+## PATH
+
+path-empty = leerer Eintrag, die meisten Shells durchsuchen dafür das aktuelle Verzeichnis
+path-relative = { $entry } ist relativ und hängt daher vom Verzeichnis ab, in dem ein Befehl ausgeführt wird
+path-missing = { $entry } existiert nicht
+path-not-dir = { $entry } ist eine Datei und kein Verzeichnis
+path-duplicate = { $entry } ist bereits Eintrag { $first }, dieser wird daher nie durchsucht
+
+## Herkunft der Variablen
+
+issue-incomplete = Zeile { $line } von { $path } öffnet ein Anführungszeichen, das nie geschlossen wird, der Rest der Datei wird daher ignoriert
+issue-overridden = { $key } wird in Zeile { $line } von { $path } erneut gesetzt und ersetzt den Wert aus Zeile { $first }
+issue-unreadable = Zeile { $line } von { $path } lädt { $file }, das nicht gelesen werden kann
+
+## Was bei jeder Fehlerart zu tun ist
+
+suggestion-profile-not-found = Wähle in den Einstellungen ein vorhandenes Shell-Profil aus.
+suggestion-settings-missing = Öffne die Einstellungen und wähle dein Shell-Profil aus.
+suggestion-parse-error = Korrigiere die Datei von Hand oder setze sie in den Einstellungen zurück.
+suggestion-read-failed = Stelle sicher, dass die Datei existiert und lesbar ist, und lade neu.
+suggestion-write-failed = Stelle sicher, dass der Datenträger nicht voll und die Datei nicht schreibgeschützt ist, und versuche es erneut.
+suggestion-permission-denied = Stelle sicher, dass dein Benutzer die Datei lesen und schreiben darf. Dateien für alle Benutzer brauchen die Option "Für alle Benutzer".
+suggestion-conflict = Lade die Variablen neu und versuche es erneut.
+suggestion-invalid-input = Ändere die Eingabe und versuche es erneut.
+suggestion-value-not-found = Lade die Variablen neu, um die aktuellen Werte zu sehen.
+suggestion-read-only = Schalte den Nur-Lesen-Modus in den Einstellungen aus.
+suggestion-unsupported = Bearbeite die Datei stattdessen von Hand.
+suggestion-command-failed = Die Ausgabe des Programms steht im vollständigen Fehler.
+suggestion-other = Details stehen im vollständigen Fehler.
@@ -0,0 +1,142 @@
+# English messages, also used for any message missing from another language.
+# Each message is `id = text`, longer messages continue on indented lines.
+# Arguments are written as { $name }.
+
+full-error = Full Error:
+
+## Errors
+
+mkdir-err = Could not make a directory at { $dir }. Please see the help page and follow instructions on making a settings directory.
+make-file-err = Could not make desired file at { $path }. Please see the help page and follow the steps to diagnose.
+profile-err = Could not open shell profile ({ $path }). Please check that the shell profile setting points to the right file and try again.
+settings-read-err = Could not find or read from settings file at { $path }. Please make sure the file exists and is readable by your user, and try again
+write-file-err = Could not write to { $path }.
+conflict-err = { $path } was changed outside of Gunmetal since it was loaded. Reload the variables and try again.
+lock-file-err = Could not lock { $path } for writing. Please close any program that might be writing to it and try again.
+value-not-found-err = "{ $value }" is not set for { $key } in { $path }. Only values from the shell profile can be edited or removed.
+unsupported-target-err = Editing { $format } in place is not supported.
+change-failed-err = Change #{ $number } (to { $key }) can't be applied, so no changes were made.
+change-file-err = { $path } is not one of the profiles in the settings.
+helper-failed-err = The privileged helper could not update { $path }. No changes were made.
+invalid-setting-err = Invalid setting "{ $field }": { $reason }. The settings were not saved.
+settings-version-err = settings.json is from a newer version of Gunmetal (settings version { $found }, this version reads up to { $supported }). Please update Gunmetal.
+json-parse-err = Value not found in settings file. Please open the settings page and ensure that all settings are set.
+read-only-err = Read-only mode is on, so no changes were made. Turn off "read_only" in the settings to edit variables.
+watch-failed-err = Could not watch the profiles for changes. Variables changed outside of Gunmetal won't show up until the app is restarted.
+no-config-dir-err = Could not find a directory for the settings. Please set GUNMETAL_CONFIG to the directory the settings should be kept in.
+empty-settings-err = Settings file is empty. Please open the settings and choose your shell profile before trying again.
+file-missing-err = File { $path } does not exist.
+file-name-unreadable-err = File does not exist. Error extracting the file's name, so filename might not be readable
+file-path-unreadable-err = Could not make a file. Could not convert path to file into a readable string.
+helper-request-err = The privileged helper received an invalid request.
+helper-launch-err = Could not start the privileged helper. System-wide changes need pkexec (or sudo) and the gunmetal-helper program installed next to this app.
+elevation-err = Changing files for every user needs an administrator password, and there's no way to ask for it here. Run the command in a terminal, or install a polkit authentication agent.
+rolled-back-err = Writing the changes failed, every file was restored to how it was before.
+system-scope-unsupported = System-wide variables are only supported on Linux.
+cmd-fail-start = Command failed to run
+cmd-failed-err = { $program } exited with { $status }
+var-not-set-err = { $key } is not set.
+command-not-found-err = { $name } was not found in PATH.
+no-backup-err = There is no backup of { $path }.
+backup-read-err = Could not read the backup at { $path }.
+backup-restored = Restored { $path } from the backup taken at { $name }.
+root-not-dir-err = { $path } is not a directory, so it can't be used as the root. Nothing was read or written.
+root-symlink-loop-err = Too many symbolic links to follow in { $path } under { $root }.
+no-snapshot-err = There is no snapshot named { $name }.
+snapshot-exists-err = A snapshot named { $name } already exists. Choose another name.
+snapshot-name-err = "{ $name }" can't be used as a snapshot name. Use only letters, digits, '.', '-' and '_'.
+snapshot-read-err = Could not read the snapshot at { $path }.
+dotenv-line-err = Line { $line } of { $path } is not a KEY=VALUE assignment.
+dotenv-quote-err = The quoted value starting on line { $line } of { $path } is never closed.
+dotenv-read-err = Could not read the .env file at { $path }.
+import-system-unsupported = Importing a .env file into the system-wide files is not supported. Import it into your shell profile instead.
+export-err = Could not write the variables as { $format }.
+project-dir-err = { $path } is not a directory.
+vault-read-err = Could not read the secrets vault at { $path }.
+vault-passphrase-err = The passphrase does not unlock the secrets vault at { $path }.
+vault-empty-passphrase-err = The secrets vault needs a passphrase.
+vault-key-err = Could not derive the secrets vault's key from the passphrase.
+no-secret-err = { $key } is not in the secrets vault.
+secret-export-err = { $key } is secret, so its value isn't written to .env files.
+secret-write-err = { $key } is secret, so its value can only be changed in the secrets vault.
+vault-no-terminal-err = No passphrase for the secrets vault: set GUNMETAL_VAULT_PASSPHRASE or run this in a terminal.
+vault-mismatch-err = The passphrases don't match.
+search-regex-err = "{ $pattern }" is not a valid regular expression.
+
+## Validation
+
+validation-err-key = Invalid key "{ $input }" ({ $rule }): { $reason }.
+validation-err-value = Invalid value "{ $input }" ({ $rule }): { $reason }.
+reason-key-empty = variable name is empty
+reason-key-too-long = variable names can be at most { $max } characters
+reason-windows-key-chars = Windows variable names can't contain '=' or null characters
+reason-key-not-identifier = names in { $format } may only contain letters, digits and '_', and can't start with a digit
+reason-value-empty = value is empty
+reason-value-nul = values can't contain null characters
+reason-value-too-long = values in { $format } can be at most { $max } characters
+reason-value-multiline = { $format } can't hold values spanning multiple lines
+reason-value-character = { $format } can't store the character { $character }
+reason-shell-missing = { $path } doesn't exist
+reason-not-a-name = "{ $key }" is not a variable name
+reason-unreadable = { $path } can't be read ({ $error })
+reason-unknown-locale = there are no messages in "{ $locale }"
+reason-root-not-dir = { $path } is not a directory
+
+## File formats
+
+format-posix = POSIX shell profiles
+format-fish = fish configs
+format-environment-d = environment.d files
+format-pam-environment = /etc/environment
+format-windows = the Windows environment
+
+## Success messages
+
+add-var-success =
+    Variable added successfully!
+
+    New terminals will have the variable, but programs that are already open need to be restarted to see it.
+remove-var-success =
+    Variable removed successfully!
+
+    The change will not show up in other programs until they are restarted.
+var-added-already = Variable has been added already.
+changes-applied = { $count } change(s) applied successfully!
+dotenv-exported = Exported { $count } variable(s) to { $path }.
+secret-added = { $key } is now secret. Its value is in the vault and the profile loads it at startup.
+secret-removed = { $key } is no longer secret. Its value is back in the profile.
+
+## Prompts
+
+vault-prompt = Vault passphrase:
+vault-prompt-repeat = Repeat the passphrase:
+
+## PATH
+
+path-empty = empty entry, most shells search the current directory for it
+path-relative = { $entry } is relative, so it depends on the directory a command is run from
+path-missing = { $entry } does not exist
+path-not-dir = { $entry } is a file, not a directory
+path-duplicate = { $entry } is already entry { $first }, so this one is never searched
+
+## Where variables come from
+
+issue-incomplete = line { $line } of { $path } opens a quote that is never closed, so the rest of the file is ignored
+issue-overridden = { $key } is set again on line { $line } of { $path }, replacing the value from line { $first }
+issue-unreadable = line { $line } of { $path } loads { $file }, which can't be read
+
+## What to do about each kind of error
+
+suggestion-profile-not-found = Choose an existing shell profile in the settings.
+suggestion-settings-missing = Open the settings and choose your shell profile.
+suggestion-parse-error = Fix the file by hand, or reset it from the settings.
+suggestion-read-failed = Make sure the file exists and is readable, then reload.
+suggestion-write-failed = Make sure the disk isn't full and the file isn't read-only, then try again.
+suggestion-permission-denied = Make sure your user can read and write the file. Files for every user need the "For all users" option.
+suggestion-conflict = Reload the variables and try again.
+suggestion-invalid-input = Change the input and try again.
+suggestion-value-not-found = Reload the variables to see the current values.
+suggestion-read-only = Turn off read-only mode in the settings.
+suggestion-unsupported = Edit the file by hand instead.
+suggestion-command-failed = See the full error for the program's output.
+suggestion-other = See the full error for details.
@@ -0,0 +1,140 @@
+# 日本語のメッセージ。ここにないメッセージは英語で表示されます。
+
+full-error = エラーの詳細:
+
+## エラー
+
+mkdir-err = ディレクトリ { $dir } を作成できませんでした。ヘルプページの手順に従って設定用のディレクトリを作成してください。
+make-file-err = ファイル { $path } を作成できませんでした。ヘルプページの手順に従って原因を確認してください。
+profile-err = シェルプロファイル ({ $path }) を開けませんでした。シェルプロファイルの設定が正しいファイルを指しているか確認して、もう一度お試しください。
+settings-read-err = 設定ファイル { $path } が見つからないか、読み込めませんでした。ファイルが存在し、ユーザーが読み込めることを確認して、もう一度お試しください。
+write-file-err = { $path } に書き込めませんでした。
+conflict-err = { $path } は読み込まれた後に Gunmetal 以外で変更されました。変数を再読み込みして、もう一度お試しください。
+lock-file-err = { $path } を書き込み用にロックできませんでした。このファイルに書き込んでいる可能性のあるプログラムを閉じて、もう一度お試しください。
+value-not-found-err = { $path } の { $key } に "{ $value }" は設定されていません。編集や削除ができるのはシェルプロファイル内の値だけです。
+unsupported-target-err = { $format } の直接編集には対応していません。
+change-failed-err = 変更 #{ $number } ({ $key }) を適用できないため、何も変更されませんでした。
+change-file-err = { $path } は設定にあるプロファイルではありません。
+helper-failed-err = 管理者権限のヘルパーが { $path } を更新できませんでした。何も変更されていません。
+invalid-setting-err = 設定 "{ $field }" が無効です: { $reason }。設定は保存されませんでした。
+settings-version-err = settings.json は新しいバージョンの Gunmetal で作成されています (設定バージョン { $found }、このバージョンは { $supported } まで対応)。Gunmetal を更新してください。
+json-parse-err = 設定ファイルに値がありません。設定ページを開いて、すべての設定が入力されているか確認してください。
+read-only-err = 読み取り専用モードのため、何も変更されませんでした。変数を編集するには設定で "read_only" をオフにしてください。
+watch-failed-err = プロファイルの変更を監視できませんでした。Gunmetal 以外で変更された変数は、アプリを再起動するまで表示されません。
+no-config-dir-err = 設定用のディレクトリが見つかりませんでした。GUNMETAL_CONFIG に設定を保存するディレクトリを指定してください。
+empty-settings-err = 設定ファイルが空です。設定を開いてシェルプロファイルを選んでから、もう一度お試しください。
+file-missing-err = ファイル { $path } は存在しません。
+file-name-unreadable-err = ファイルが存在しません。ファイル名を取得できなかったため、名前が読めない可能性があります。
+file-path-unreadable-err = ファイルを作成できませんでした。パスを読める文字列に変換できませんでした。
+helper-request-err = 管理者権限のヘルパーが無効な要求を受け取りました。
+helper-launch-err = 管理者権限のヘルパーを起動できませんでした。システム全体の変更には pkexec (または sudo) と、このアプリと同じ場所にある gunmetal-helper が必要です。
+elevation-err = すべてのユーザーのファイルを変更するには管理者パスワードが必要ですが、ここでは入力を求められません。ターミナルでコマンドを実行するか、polkit 認証エージェントをインストールしてください。
+rolled-back-err = 変更の書き込みに失敗したため、すべてのファイルを元の状態に戻しました。
+system-scope-unsupported = システム全体の変数は Linux でのみ対応しています。
+cmd-fail-start = コマンドを実行できませんでした
+cmd-failed-err = { $program } が { $status } で終了しました
+var-not-set-err = { $key } は設定されていません。
+command-not-found-err = { $name } は PATH に見つかりませんでした。
+no-backup-err = { $path } のバックアップはありません。
+backup-read-err = { $path } のバックアップを読み込めませんでした。
+backup-restored = { $path } を { $name } に作成したバックアップから復元しました。
+root-not-dir-err = { $path } はディレクトリではないため、ルートとして使えません。何も読み書きしていません。
+root-symlink-loop-err = { $root } の下の { $path } にたどるシンボリックリンクが多すぎます。
+no-snapshot-err = { $name } という名前のスナップショットはありません。
+snapshot-exists-err = { $name } という名前のスナップショットはすでに存在します。別の名前を選んでください。
+snapshot-name-err = "{ $name }" はスナップショットの名前に使えません。英数字と '.'、'-'、'_' だけを使ってください。
+snapshot-read-err = { $path } のスナップショットを読み込めませんでした。
+dotenv-line-err = { $path } の { $line } 行目は KEY=VALUE の形の代入ではありません。
+dotenv-quote-err = { $path } の { $line } 行目から始まる引用符が閉じられていません。
+dotenv-read-err = { $path } の .env ファイルを読み込めませんでした。
+import-system-unsupported = .env ファイルをシステム全体のファイルにインポートすることはできません。代わりにシェルプロファイルにインポートしてください。
+export-err = 変数を { $format } として書き出せませんでした。
+project-dir-err = { $path } はディレクトリではありません。
+vault-read-err = { $path } のシークレット保管庫を読み込めませんでした。
+vault-passphrase-err = パスフレーズで { $path } のシークレット保管庫を開けませんでした。
+vault-empty-passphrase-err = シークレット保管庫にはパスフレーズが必要です。
+vault-key-err = パスフレーズからシークレット保管庫の鍵を導出できませんでした。
+no-secret-err = { $key } はシークレット保管庫にありません。
+secret-export-err = { $key } はシークレットのため、値は .env ファイルに書き込まれません。
+secret-write-err = { $key } はシークレットのため、値はシークレット保管庫でのみ変更できます。
+vault-no-terminal-err = シークレット保管庫のパスフレーズがありません。GUNMETAL_VAULT_PASSPHRASE を設定するか、ターミナルで実行してください。
+vault-mismatch-err = パスフレーズが一致しません。
+search-regex-err = "{ $pattern }" は正しい正規表現ではありません。
+
+## 入力チェック
+
+validation-err-key = 無効な変数名 "{ $input }" ({ $rule }): { $reason }。
+validation-err-value = 無効な値 "{ $input }" ({ $rule }): { $reason }。
+reason-key-empty = 変数名が空です
+reason-key-too-long = 変数名は最大 { $max } 文字です
+reason-windows-key-chars = Windows の変数名には '=' やヌル文字を使えません
+reason-key-not-identifier = { $format } の変数名に使えるのは英数字と '_' だけで、数字から始めることはできません
+reason-value-empty = 値が空です
+reason-value-nul = 値にヌル文字は使えません
+reason-value-too-long = { $format } の値は最大 { $max } 文字です
+reason-value-multiline = { $format } には複数行の値を保存できません
+reason-value-character = { $format } には文字 { $character } を保存できません
+reason-shell-missing = { $path } は存在しません
+reason-not-a-name = "{ $key }" は変数名ではありません
+reason-unreadable = { $path } を読み込めません ({ $error })
+reason-unknown-locale = "{ $locale }" のメッセージはありません
+reason-root-not-dir = { $path } はディレクトリではありません
+
+## ファイル形式
+
+format-posix = POSIX シェルのプロファイル
+format-fish = fish の設定ファイル
+format-environment-d = environment.d ファイル
+format-pam-environment = /etc/environment
+format-windows = Windows の環境変数
+
+## 成功メッセージ
+
+add-var-success =
+    変数を追加しました！
+
+    新しく開いたターミナルでは使えますが、すでに開いているプログラムで使うには再起動が必要です。
+remove-var-success =
+    変数を削除しました！
+
+    ほかのプログラムに反映するには、そのプログラムを再起動してください。
+var-added-already = この変数はすでに追加されています。
+changes-applied = { $count } 件の変更を適用しました！
+dotenv-exported = { $count } 個の変数を { $path } にエクスポートしました。
+secret-added = { $key } をシークレットにしました。値は保管庫にあり、プロファイルが起動時に読み込みます。
+secret-removed = { $key } はシークレットではなくなりました。値はプロファイルに戻りました。
+
+## Prompts
+
+vault-prompt = 保管庫のパスフレーズ:
+vault-prompt-repeat = もう一度パスフレーズ:
+
+## PATH
+
+path-empty = 空のエントリです。多くのシェルはカレントディレクトリを検索します
+path-relative = { $entry } は相対パスのため、コマンドを実行するディレクトリによって変わります
+path-missing = { $entry } は存在しません
+path-not-dir = { $entry } はディレクトリではなくファイルです
+path-duplicate = { $entry } はすでに { $first } 番目のエントリにあるため、このエントリは検索されません
+
+## 変数の出どころ
+
+issue-incomplete = { $path } の { $line } 行目の引用符が閉じられていないため、ファイルの残りは無視されます
+issue-overridden = { $key } は { $path } の { $line } 行目で再び設定され、{ $first } 行目の値を置き換えます
+issue-unreadable = { $path } の { $line } 行目が読み込む { $file } を読み込めません
+
+## エラーの種類ごとの対処法
+
+suggestion-profile-not-found = 設定で存在するシェルプロファイルを選んでください。
+suggestion-settings-missing = 設定を開いてシェルプロファイルを選んでください。
+suggestion-parse-error = ファイルを手動で修正するか、設定からリセットしてください。
+suggestion-read-failed = ファイルが存在し読み込めることを確認して、再読み込みしてください。
+suggestion-write-failed = ディスクの空き容量とファイルが読み取り専用でないことを確認して、もう一度お試しください。
+suggestion-permission-denied = ユーザーがファイルを読み書きできるか確認してください。全ユーザー向けのファイルには「すべてのユーザー」オプションが必要です。
+suggestion-conflict = 変数を再読み込みして、もう一度お試しください。
+suggestion-invalid-input = 入力を変更して、もう一度お試しください。
+suggestion-value-not-found = 変数を再読み込みして、現在の値を確認してください。
+suggestion-read-only = 設定で読み取り専用モードをオフにしてください。
+suggestion-unsupported = 代わりにファイルを手動で編集してください。
+suggestion-command-failed = プログラムの出力はエラーの詳細を確認してください。
+suggestion-other = 詳しくはエラーの詳細を確認してください。
@@ -0,0 +1,257 @@
+use crate::consts_and_errors::*;
+use crate::env_backend::{backend, Position};
+use crate::secrets::check_not_secret;
+use crate::settings_utils::{check_writable, get_settings};
+use crate::system_scope::{Scope, SystemTarget};
+#[cfg(target_os = "linux")]
+use crate::{
+    diff_utils::unified_diff,
+    fs_root::root,
+    system_scope::{apply_request, read_target, HelperRequest},
+};
+#[cfg(target_os = "linux")]
+use std::io::{IsTerminal, Write};
+#[cfg(target_os = "linux")]
+use std::process::{Command, Stdio};
+
+/// Adds an environment variable.
+/// ### Arguments:
+/// - key (String)
+/// - var_submission (String)
+/// - dry_run (optional bool): if true, nothing is written and a unified diff of each affected file is returned instead
+/// - position (optional Position): "prepend" (the default) adds the value before the existing ones, "append" after them
+/// - scope (optional Scope): "user" writes to the user's shell profile, "system" writes to /etc through the privileged helper (or under the root, if one is set). Defaults to the "write_target" setting
+/// - system_target (optional SystemTarget): the file a "system" scope writes, "profile_d" (the default), "environment" or "environment_d"
+/// ### Returns
+/// Either a success message (or the diff when doing a dry run) or an error message
+/// ### Types of Errors
+/// - ValidationError, when the key or value can't be written to the target file (the message names the failed rule)
+/// - JSONParseError, when a settings value is not found in settings.json
+/// - MakeDirError, when the program is unable to make a dedicated directory when making a new settings.json file
+/// - MakeFileError, when the program is unable to create a file
+/// - EmptySettingsError, when settings.json is empty and the program is awaiting the user to add all settings.
+/// - ProfileOpenError, when the program is unable to open a shell profile
+/// - WriteToFileError, when the program is unable to write to a file (usually the shell profile file)
+/// - ConflictError, when the shell profile was changed by another program since `get_vars` read it
+/// - ReadOnlyError, when read-only mode is on in the settings
+/// - InvalidInput, when the variable is secret (its value can only change in the vault)
+/// - HelperError, when the privileged helper can't be started or refuses a system scope change
+/// - PermissionDenied, when the system scope needs a password that can't be asked for (ex: sudo without a terminal)
+/// - Unsupported, when the system scope is used on another OS than Linux
+pub fn add_var(
+    key: String,
+    var_submission: String,
+    dry_run: Option<bool>,
+    scope: Option<Scope>,
+    position: Option<Position>,
+    system_target: Option<SystemTarget>,
+) -> Result<String, GunmetalError> {
+    let dry_run = dry_run.unwrap_or(false);
+    let position = position.unwrap_or_default();
+    if !dry_run {
+        check_writable()?;
+    }
+    // a secret's value lives in the vault, writing it to a profile would leak it
+    check_not_secret(&key)?;
+    // without a scope, write where the settings say to (user scope if there are no settings, ex: on Windows)
+    let scope = scope.unwrap_or_else(|| {
+        get_settings()
+            .map(|settings| settings.write_target)
+            .unwrap_or(Scope::User)
+    });
+    // Try to append variable, the key and value are validated against the target's format first
+    let result = match scope {
+        Scope::User => backend().write(&key, &var_submission, position, dry_run),
+        Scope::System => append_system(
+            &key,
+            &var_submission,
+            position,
+            system_target.unwrap_or(SystemTarget::ProfileD),
+            dry_run,
+        ),
+    };
+    match &result {
+        Ok(_) if !dry_run => log::info!("added to {} ({:?} scope)", key, scope),
+        Ok(_) => (),
+        Err(err) => log::warn!("could not add to {} ({})", key, err.code()),
+    }
+    return result;
+}
+
+/// Checks if a variable being submitted is already set in the process environment, returns boolean
+/// ### Arguments
+/// key: variable
+/// var_submission: desired submissions
+/// ### Returns:
+/// boolean, true if duplicate, false if not
+#[cfg(target_os = "linux")]
+fn check_if_var_duplicate(key: &String, var_submission: &String) -> bool {
+    return match std::env::var(key) {
+        Ok(vals) => vals.split(':').any(|entry| entry == var_submission),
+        Err(_) => false,
+    };
+}
+
+/// Adds a variable for every user, by asking the privileged helper to write a file under /etc.
+/// Under a root the file is written directly instead, the helper only ever writes the real /etc.
+/// ### Arguments:
+/// - key
+/// - var_submission
+/// - target: the system file to write
+/// - dry_run: if true, return the diff of the target file instead of launching the helper
+/// ### Returns:
+/// A success string (or the diff) or an error message string
+#[cfg(target_os = "linux")]
+fn append_system(
+    key: &String,
+    var_submission: &String,
+    position: Position,
+    target: SystemTarget,
+    dry_run: bool,
+) -> Result<String, GunmetalError> {
+    // check if variable is already there, if so, return. This process's environment isn't the root's
+    let rooted = root()?.is_some();
+    if !rooted && check_if_var_duplicate(key, var_submission) {
+        // nothing would be written, so a dry run has an empty diff
+        if dry_run {
+            return Ok(String::new());
+        }
+        return Ok(var_added_already!());
+    }
+
+    let request = HelperRequest {
+        target,
+        key: key.to_owned(),
+        value: var_submission.to_owned(),
+        position,
+    };
+
+    if dry_run {
+        // reading the system files doesn't need privileges, so the preview is made here
+        let original = read_target(request.target)?;
+        let updated = request.apply_to(&original)?;
+        return Ok(unified_diff(
+            &request.target.location()?,
+            &original,
+            &updated,
+        ));
+    }
+
+    if rooted {
+        return apply_request(&request, &request.target.location()?);
+    }
+    return run_helper(&request);
+}
+
+#[cfg(not(target_os = "linux"))]
+fn append_system(
+    _key: &String,
+    _var_submission: &String,
+    _position: Position,
+    _target: SystemTarget,
+    _dry_run: bool,
+) -> Result<String, GunmetalError> {
+    return Err(GunmetalError::Unsupported(ErrorInfo::new(
+        system_scope_unsupported!(),
+    )));
+}
+
+/// Launches the privileged helper with pkexec (falling back to sudo) and sends it the request.
+/// sudo only asks for a password on a terminal, without one (ex: from the app) it must not need one.
+/// ### Returns:
+/// The helper's success message, or its error output
+#[cfg(target_os = "linux")]
+fn run_helper(request: &HelperRequest) -> Result<String, GunmetalError> {
+    // the helper is installed next to the app's own binary
+    let helper_path = std::env::current_exe()
+        .map_err(|err| launch_err(&err))?
+        .with_file_name("gunmetal-helper");
+    let request_json = serde_json::to_string(request).map_err(|err| {
+        GunmetalError::CommandFailed(ErrorInfo::new(helper_request_err!()).details(err))
+    })?;
+
+    // a password prompt nobody can see would wait forever
+    let interactive = std::io::stdin().is_terminal();
+    let mut last_error: Option<std::io::Error> = None;
+    for escalate in ["pkexec", "sudo"] {
+        let non_interactive = escalate == "sudo" && !interactive;
+        if non_interactive {
+            match sudo_without_password() {
+                Ok(true) => (),
+                Ok(false) => {
+                    return Err(GunmetalError::PermissionDenied(
+                        ErrorInfo::new(elevation_err!()).path(request.target.path()),
+                    ))
+                }
+                // sudo isn't installed
+                Err(err) => {
+                    last_error = Some(err);
+                    continue;
+                }
+            }
+        }
+        let child = Command::new(escalate)
+            // -n makes sudo fail instead of asking for a password
+            .args(if non_interactive {
+                ["-n"].as_slice()
+            } else {
+                &[]
+            })
+            .arg(&helper_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            // escalation tool not installed, try the next one
+            Err(err) => {
+                last_error = Some(err);
+                continue;
+            }
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(request_json.as_bytes())
+                .map_err(|err| launch_err(&err))?;
+        }
+        let output = child.wait_with_output().map_err(|err| launch_err(&err))?;
+
+        if output.status.success() {
+            return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+        }
+        let info = ErrorInfo::new(helper_failed_err!(request.target.path()))
+            .path(request.target.path())
+            .details(String::from_utf8_lossy(&output.stderr).trim());
+        // pkexec exits with 126 when the password prompt is dismissed, and 127 when the user isn't allowed
+        return match output.status.code() {
+            Some(126) | Some(127) if escalate == "pkexec" => {
+                Err(GunmetalError::PermissionDenied(info))
+            }
+            _ => Err(GunmetalError::CommandFailed(info)),
+        };
+    }
+
+    return Err(match last_error {
+        Some(err) => launch_err(&err),
+        None => GunmetalError::CommandFailed(ErrorInfo::new(helper_launch_err!())),
+    });
+}
+
+/// Checks if sudo can run a command right now without asking for a password (ex: NOPASSWD, or a cached password)
+#[cfg(target_os = "linux")]
+fn sudo_without_password() -> std::io::Result<bool> {
+    let status = Command::new("sudo")
+        .args(["-n", "true"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    return Ok(status.success());
+}
+
+#[cfg(target_os = "linux")]
+fn launch_err(err: &std::io::Error) -> GunmetalError {
+    return GunmetalError::CommandFailed(ErrorInfo::new(helper_launch_err!()).io(err));
+}
@@ -0,0 +1,247 @@
+use serde::Serialize;
+use std::fs::{self};
+use std::path::{Path, PathBuf};
+
+use crate::consts_and_errors::*;
+use crate::diff_utils::unified_diff;
+use crate::file_guard::{check_unchanged, remember, replace_file, LockedFile};
+use crate::profile_edit::remove_assignments;
+use crate::settings::load_settings;
+use crate::settings_utils::{check_writable, get_config_path};
+use crate::unix_backend::profile_read_err;
+
+/// Name of the file in each backup directory that holds the path the backups are of
+const SOURCE_FILE_NAME: &str = "source";
+
+/// A copy of a file, taken right before Gunmetal changed it
+#[derive(Serialize, Clone, Debug)]
+pub struct Backup {
+    /// file the backup is of
+    pub source: String,
+    /// where the copy is kept
+    pub path: String,
+    /// name of the backup, the time it was taken (ex: "20240131-154500-123")
+    pub name: String,
+}
+
+/// Finds the directory backups are kept in, `backups` in the config directory
+pub fn backups_dir() -> Result<PathBuf, GunmetalError> {
+    return Ok(PathBuf::from(get_config_path()?).join("backups"));
+}
+
+/// Copies a file's contents into the backups before it's changed, then deletes the oldest backups of it
+/// past the "backup_retention" setting. A retention of 0 turns backups off.
+/// ### Arguments
+/// - source: path of the file about to be changed
+/// - content: its current contents
+/// ### Returns
+/// The new backup, None if backups are turned off, or an error if it couldn't be written
+pub fn save_backup(source: &str, content: &str) -> Result<Option<Backup>, GunmetalError> {
+    // without settings, the default retention applies
+    let settings = get_config_path()
+        .and_then(|path| load_settings(&format!("{}/settings.json", path)))
+        .unwrap_or_default();
+    if settings.backup_retention == 0 {
+        return Ok(None);
+    }
+    let content = without_secrets(source, content, &settings.secrets);
+
+    // backups can hold anything a profile does, so only their owner may read them
+    let dir = source_dir(source)?;
+    let dir_str = dir.to_string_lossy().to_string();
+    make_private_dir(&dir)
+        .and_then(|_| {
+            replace_file(
+                &dir.join(SOURCE_FILE_NAME).to_string_lossy(),
+                source,
+                Some(0o600),
+            )
+        })
+        .map_err(|err| {
+            GunmetalError::from_io(
+                GunmetalError::WriteFailed,
+                GunmetalError::WriteFailed,
+                ErrorInfo::new(mkdir_err!(&dir_str)).path(&dir_str),
+                &err,
+            )
+        })?;
+
+    let mut name = chrono::Local::now().format("%Y%m%d-%H%M%S-%3f").to_string();
+    // two writes in the same millisecond (ex: a batch) still get separate backups
+    while dir.join(&name).exists() {
+        name.push('x');
+    }
+    let path = dir.join(&name).to_string_lossy().to_string();
+    replace_file(&path, &content, Some(0o600)).map_err(|err| {
+        GunmetalError::from_io(
+            GunmetalError::WriteFailed,
+            GunmetalError::WriteFailed,
+            ErrorInfo::new(make_file_err!(&path)).path(&path),
+            &err,
+        )
+    })?;
+
+    for old in list_backups(source)
+        .iter()
+        .skip(settings.backup_retention as usize)
+    {
+        let _ = fs::remove_file(&old.path);
+    }
+    return Ok(Some(Backup {
+        source: String::from(source),
+        path,
+        name,
+    }));
+}
+
+/// Same as `save_backup`, but a backup that can't be written is only logged, so it never blocks a change
+pub fn try_backup(source: &str, content: &str) {
+    if let Err(err) = save_backup(source, content) {
+        log::warn!("could not back up {}: {}", source, err);
+    }
+}
+
+/// Lists the backups of a file, newest first
+pub fn list_backups(source: &str) -> Vec<Backup> {
+    let dir = match source_dir(source) {
+        Ok(dir) => dir,
+        Err(_) => return Vec::new(),
+    };
+    return read_backups(&dir, source);
+}
+
+/// Lists the backups of every file, grouped by file and newest first
+pub fn list_all_backups() -> Vec<Backup> {
+    let entries = match backups_dir().map(fs::read_dir) {
+        Ok(Ok(entries)) => entries,
+        _ => return Vec::new(),
+    };
+    let mut backups: Vec<Backup> = Vec::new();
+    for dir in entries.flatten().map(|entry| entry.path()) {
+        if let Ok(source) = fs::read_to_string(dir.join(SOURCE_FILE_NAME)) {
+            backups.extend(read_backups(&dir, &source));
+        }
+    }
+    return backups;
+}
+
+/// Puts a file back the way it was in one of its backups. The current contents are backed up first, so this can be undone.
+/// ### Arguments
+/// - source: path of the file to restore
+/// - name: which backup to restore, the newest if None
+/// - dry_run (optional bool): if true, nothing is written and a unified diff of the file is returned instead
+/// ### Returns
+/// A success message (or the diff when doing a dry run), or an error
+/// ### Types of Errors
+/// - ValueNotFound, when there's no such backup
+/// - ReadOnly, when read-only mode is on in the settings
+/// - Conflict, when the file was changed by another program since it was loaded
+/// - WriteFailed, when the file can't be written
+pub fn restore_backup(
+    source: &str,
+    name: Option<&str>,
+    dry_run: Option<bool>,
+) -> Result<String, GunmetalError> {
+    let dry_run = dry_run.unwrap_or(false);
+    if !dry_run {
+        check_writable()?;
+    }
+    let backups = list_backups(source);
+    let backup = match name {
+        Some(name) => backups.iter().find(|backup| backup.name == name),
+        None => backups.first(),
+    };
+    let backup = backup.ok_or_else(|| {
+        GunmetalError::ValueNotFound(ErrorInfo::new(no_backup_err!(source)).path(source))
+    })?;
+    let content = fs::read_to_string(&backup.path).map_err(|err| {
+        GunmetalError::from_io(
+            GunmetalError::ReadFailed,
+            GunmetalError::ReadFailed,
+            ErrorInfo::new(backup_read_err!(&backup.path)).path(&backup.path),
+            &err,
+        )
+    })?;
+
+    if dry_run {
+        let current = fs::read_to_string(source).map_err(|err| profile_read_err(source, &err))?;
+        return Ok(unified_diff(source, &current, &content));
+    }
+
+    let mut file = LockedFile::open(source)?;
+    let current = file.read_to_string()?;
+    check_unchanged(source, &current)?;
+    save_backup(source, &current)?;
+    file.replace(&content)?;
+    remember(source, &content);
+
+    log::info!("restored {} from backup {}", source, backup.name);
+    return Ok(backup_restored!(source, &backup.name));
+}
+
+/// Directory holding the backups of one file, named after the file so it's easy to find by hand
+fn source_dir(source: &str) -> Result<PathBuf, GunmetalError> {
+    let file_name = Path::new(source)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    // files with the same name in different directories get their own backups.
+    // FNV-1a, since the directory has to get the same name with every version of Rust
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in source.bytes() {
+        hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+    }
+    return Ok(backups_dir()?.join(format!("{}-{:016x}", file_name, hash)));
+}
+
+/// Leaves the assignments of secret variables out of a profile's backup, their values belong in the vault only.
+/// Files that aren't shell profiles are kept as they are.
+fn without_secrets(source: &str, content: &str, secrets: &[String]) -> String {
+    let mut hidden = String::from(content);
+    for key in secrets {
+        match remove_assignments(&hidden, key, source) {
+            Ok(updated) => hidden = updated,
+            Err(_) => break,
+        }
+    }
+    return hidden;
+}
+
+/// Makes a directory and the ones above it up to the backups directory, readable only by their owner.
+/// Directories made by older versions get their permissions fixed too.
+fn make_private_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        builder.mode(0o700);
+        builder.create(dir)?;
+        for dir in [dir.parent(), Some(dir)].into_iter().flatten() {
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        }
+        return Ok(());
+    }
+    #[cfg(not(unix))]
+    return builder.create(dir);
+}
+
+fn read_backups(dir: &Path, source: &str) -> Vec<Backup> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut backups: Vec<Backup> = entries
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name != SOURCE_FILE_NAME)
+        .map(|name| Backup {
+            source: String::from(source),
+            path: dir.join(&name).to_string_lossy().to_string(),
+            name,
+        })
+        .collect();
+    // names are timestamps, so they sort by age
+    backups.sort_by(|a, b| b.name.cmp(&a.name));
+    return backups;
+}
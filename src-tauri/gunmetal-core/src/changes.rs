@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self};
+
+use crate::backups::try_backup;
+use crate::consts_and_errors::*;
+use crate::diff_utils::unified_diff;
+use crate::env_backend::Position;
+use crate::file_guard::{check_unchanged, remember, LockedFile};
+use crate::fs_root::rooted;
+use crate::profile_edit::{add_value, edit_value, remove_value, set_value};
+use crate::secrets::check_not_secret;
+use crate::settings_utils::{check_writable, get_settings, get_shell_profile_path};
+use crate::unix_backend::profile_read_err;
+
+/// One operation of a batch sent to `apply_changes`.
+/// `file` is the profile to change, as seen from inside the root. It must be one of the profiles in the settings,
+/// and defaults to the active shell profile.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Change {
+    /// adds a value to a variable, like `add_var`
+    Add {
+        key: String,
+        value: String,
+        #[serde(default)]
+        position: Position,
+        #[serde(default)]
+        file: Option<String>,
+    },
+    /// sets a variable to one value, replacing all of its values (ex: a variable imported from a .env file)
+    Set {
+        key: String,
+        value: String,
+        #[serde(default)]
+        file: Option<String>,
+    },
+    /// replaces one of a variable's values with another
+    Edit {
+        key: String,
+        old_value: String,
+        new_value: String,
+        #[serde(default)]
+        file: Option<String>,
+    },
+    /// removes one of a variable's values
+    Remove {
+        key: String,
+        value: String,
+        #[serde(default)]
+        file: Option<String>,
+    },
+}
+
+impl Change {
+    fn key(&self) -> &str {
+        match self {
+            Change::Add { key, .. }
+            | Change::Set { key, .. }
+            | Change::Edit { key, .. }
+            | Change::Remove { key, .. } => key,
+        }
+    }
+
+    fn file(&self) -> Option<&str> {
+        match self {
+            Change::Add { file, .. }
+            | Change::Set { file, .. }
+            | Change::Edit { file, .. }
+            | Change::Remove { file, .. } => file.as_deref(),
+        }
+    }
+
+    /// Applies the change to the contents of a profile
+    fn apply_to(&self, content: &str, path: &str) -> Result<String, GunmetalError> {
+        match self {
+            Change::Add {
+                key,
+                value,
+                position,
+                ..
+            } => add_value(content, key, value, *position, path),
+            Change::Set { key, value, .. } => set_value(content, key, value, path),
+            Change::Edit {
+                key,
+                old_value,
+                new_value,
+                ..
+            } => edit_value(content, key, old_value, new_value, path),
+            Change::Remove { key, value, .. } => remove_value(content, key, value, path),
+        }
+    }
+}
+
+/// Finds the file a change is made to: the profile it names, or else the active shell profile
+fn change_path(change: &Change) -> Result<String, GunmetalError> {
+    let file = match change.file() {
+        Some(file) => file,
+        None => return get_shell_profile_path(),
+    };
+    let settings = get_settings()?;
+    if file != settings.shell_profile && !settings.profiles.iter().any(|profile| profile == file) {
+        return Err(GunmetalError::InvalidInput(
+            ErrorInfo::new(change_file_err!(file)).path(file),
+        ));
+    }
+    return rooted(file);
+}
+
+/// A file touched by a batch, with its contents before and after
+struct PlannedFile {
+    path: String,
+    original: String,
+    updated: String,
+}
+
+/// Applies several changes at once, all or nothing.
+/// Every change is checked first, then every affected file is locked and backed up, then written.
+/// If any write fails, the files already written are restored to their original contents.
+/// ### Arguments
+/// - changes: list of add, set, edit and remove operations, applied in order to the profile each one names
+/// - dry_run (optional bool): if true, nothing is written and a unified diff of each affected file is returned instead
+/// ### Returns
+/// A success message (or the diff when doing a dry run), or an error message naming the change that failed
+/// ### Types of Errors
+/// - ValidationError, when a key or value can't be written to the target file
+/// - InvalidInput, when a change names a file that isn't one of the profiles in the settings, or a secret variable
+/// - ValueNotFoundError, when an edited or removed value isn't set in the profile
+/// - ReadOnlyError, when read-only mode is on in the settings
+/// - ConflictError, when a profile was changed by another program since `get_vars` read it
+/// - WriteToFileError, when a file can't be written (every file is rolled back)
+pub fn apply_changes(changes: Vec<Change>, dry_run: Option<bool>) -> Result<String, GunmetalError> {
+    let dry_run = dry_run.unwrap_or(false);
+    if !dry_run {
+        check_writable()?;
+    }
+
+    // work out the new contents of every file before touching any of them
+    let mut files: Vec<PlannedFile> = Vec::new();
+    for (index, change) in changes.iter().enumerate() {
+        check_not_secret(change.key())
+            .map_err(|err| err.context(change_failed_err!(index + 1, change.key())))?;
+        let path = change_path(change)
+            .map_err(|err| err.context(change_failed_err!(index + 1, change.key())))?;
+        let position = match files.iter().position(|file| file.path == path) {
+            Some(position) => position,
+            None => {
+                let original =
+                    fs::read_to_string(&path).map_err(|err| profile_read_err(&path, &err))?;
+                files.push(PlannedFile {
+                    path,
+                    updated: original.clone(),
+                    original,
+                });
+                files.len() - 1
+            }
+        };
+
+        let file = &mut files[position];
+        file.updated = change
+            .apply_to(&file.updated, &file.path)
+            .map_err(|err| err.context(change_failed_err!(index + 1, change.key())))?;
+    }
+
+    // files the changes leave as they were (ex: a .env file imported again) aren't written or backed up
+    files.retain(|file| file.updated != file.original);
+
+    if dry_run {
+        let diffs: Vec<String> = files
+            .iter()
+            .map(|file| unified_diff(&file.path, &file.original, &file.updated))
+            .collect();
+        return Ok(diffs.concat());
+    }
+
+    // lock every file for the whole batch, and make sure none was changed by someone else
+    let mut locked: Vec<LockedFile> = Vec::new();
+    for file in &files {
+        let mut locked_file = LockedFile::open(&file.path)?;
+        let current = locked_file.read_to_string()?;
+        check_unchanged(&file.path, &current)?;
+        if current != file.original {
+            return Err(GunmetalError::Conflict(
+                ErrorInfo::new(conflict_err!(&file.path)).path(&file.path),
+            ));
+        }
+        locked.push(locked_file);
+    }
+
+    for file in &files {
+        try_backup(&file.path, &file.original);
+    }
+    for (index, file) in files.iter().enumerate() {
+        if let Err(err) = locked[index].replace(&file.updated) {
+            // put back every file written so far, still under the same locks. A failed write leaves
+            // its file as it was, so the one that failed doesn't need it
+            for (written, written_file) in files.iter().enumerate().take(index) {
+                if let Err(err) = locked[written].replace(&written_file.original) {
+                    log::error!("could not roll back {}: {}", written_file.path, err);
+                }
+            }
+            log::error!("writing {} failed, rolled back: {}", file.path, err);
+            return Err(err.context(rolled_back_err!()));
+        }
+    }
+
+    // our own changes aren't conflicts for the next write
+    for file in &files {
+        remember(&file.path, &file.updated);
+    }
+
+    log::info!("applied {} change(s)", changes.len());
+    return Ok(changes_applied!(changes.len()));
+}
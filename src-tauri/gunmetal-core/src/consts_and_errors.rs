@@ -0,0 +1,688 @@
+macro_rules! config_dir_name {
+    () => {
+        "gunmetal"
+    };
+}
+
+// directory name older versions kept settings.json in
+macro_rules! legacy_config_dir_name {
+    () => {
+        "Environment Variable Editor"
+    };
+}
+
+// every message shown to the user comes from the catalogs in locales/, see i18n.rs
+macro_rules! tr {
+    ($id:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        crate::i18n::message($id, &[$((stringify!($name), $value.to_string())),*])
+    };
+}
+
+macro_rules! construct_err_msg {
+    ($message:expr, $full_error:expr) => {
+        format!("{}\n{}\n{}", $message, tr!("full-error"), $full_error)
+    };
+}
+
+macro_rules! mkdir_err {
+    ($dir:expr) => {
+        tr!("mkdir-err", dir = $dir)
+    };
+}
+macro_rules! make_file_err {
+    ($path:expr) => {
+        tr!("make-file-err", path = $path)
+    };
+}
+macro_rules! profile_err {
+    ($path:expr) => {
+        tr!("profile-err", path = $path)
+    };
+}
+macro_rules! settings_read_error {
+    ($path:expr) => {
+        tr!("settings-read-err", path = $path)
+    };
+}
+// only the path, the contents can hold secrets
+macro_rules! write_file_err {
+    ($path:expr) => {
+        tr!("write-file-err", path = $path)
+    };
+}
+macro_rules! conflict_err {
+    ($path:expr) => {
+        tr!("conflict-err", path = $path)
+    };
+}
+macro_rules! lock_file_err {
+    ($path:expr) => {
+        tr!("lock-file-err", path = $path)
+    };
+}
+macro_rules! value_not_found_err {
+    ($value:expr, $key:expr, $path:expr) => {
+        tr!(
+            "value-not-found-err",
+            value = $value,
+            key = $key,
+            path = $path
+        )
+    };
+}
+macro_rules! unsupported_target_err {
+    ($format:expr) => {
+        tr!("unsupported-target-err", format = $format)
+    };
+}
+macro_rules! change_file_err {
+    ($path:expr) => {
+        tr!("change-file-err", path = $path)
+    };
+}
+
+macro_rules! change_failed_err {
+    ($number:expr, $key:expr) => {
+        tr!("change-failed-err", number = $number, key = $key)
+    };
+}
+macro_rules! changes_applied {
+    ($count:expr) => {
+        tr!("changes-applied", count = $count)
+    };
+}
+macro_rules! dotenv_exported {
+    ($count:expr, $path:expr) => {
+        tr!("dotenv-exported", count = $count, path = $path)
+    };
+}
+macro_rules! secret_added {
+    ($key:expr) => {
+        tr!("secret-added", key = $key)
+    };
+}
+macro_rules! secret_removed {
+    ($key:expr) => {
+        tr!("secret-removed", key = $key)
+    };
+}
+macro_rules! helper_failed_err {
+    ($path:expr) => {
+        tr!("helper-failed-err", path = $path)
+    };
+}
+macro_rules! invalid_setting_err {
+    ($field:expr, $reason:expr) => {
+        tr!("invalid-setting-err", field = $field, reason = $reason)
+    };
+}
+macro_rules! settings_version_err {
+    ($found:expr, $supported:expr) => {
+        tr!(
+            "settings-version-err",
+            found = $found,
+            supported = $supported
+        )
+    };
+}
+// the following macros don't take arguments, but i'm still using macros for the sake of consistency anyway
+macro_rules! json_parse_err {
+    () => {
+        tr!("json-parse-err")
+    };
+}
+macro_rules! read_only_err {
+    () => {
+        tr!("read-only-err")
+    };
+}
+macro_rules! watch_failed_err {
+    () => {
+        tr!("watch-failed-err")
+    };
+}
+macro_rules! no_config_dir_err {
+    () => {
+        tr!("no-config-dir-err")
+    };
+}
+macro_rules! empty_settings_err {
+    () => {
+        tr!("empty-settings-err")
+    };
+}
+macro_rules! add_var_success {
+    () => {
+        tr!("add-var-success")
+    };
+}
+macro_rules! var_added_already {
+    () => {
+        tr!("var-added-already")
+    };
+}
+// field is "key" or "value"
+macro_rules! validation_err {
+    ($field:expr, $input:expr, $rule:expr, $reason:expr) => {
+        tr!(
+            &format!("validation-err-{}", $field),
+            input = $input,
+            rule = $rule,
+            reason = $reason
+        )
+    };
+}
+
+macro_rules! helper_request_err {
+    () => {
+        tr!("helper-request-err")
+    };
+}
+macro_rules! elevation_err {
+    () => {
+        tr!("elevation-err")
+    };
+}
+macro_rules! helper_launch_err {
+    () => {
+        tr!("helper-launch-err")
+    };
+}
+macro_rules! rolled_back_err {
+    () => {
+        tr!("rolled-back-err")
+    };
+}
+// only other systems than Linux lack the system scope
+#[cfg(not(target_os = "linux"))]
+macro_rules! system_scope_unsupported {
+    () => {
+        tr!("system-scope-unsupported")
+    };
+}
+
+macro_rules! cmd_fail_start {
+    () => {
+        tr!("cmd-fail-start")
+    };
+}
+
+macro_rules! cmd_failed_err {
+    ($program:expr, $status:expr) => {
+        tr!("cmd-failed-err", program = $program, status = $status)
+    };
+}
+
+macro_rules! var_not_set_err {
+    ($key:expr) => {
+        tr!("var-not-set-err", key = $key)
+    };
+}
+
+macro_rules! command_not_found_err {
+    ($name:expr) => {
+        tr!("command-not-found-err", name = $name)
+    };
+}
+
+macro_rules! root_not_dir_err {
+    ($path:expr) => {
+        tr!("root-not-dir-err", path = $path)
+    };
+}
+
+macro_rules! root_symlink_loop_err {
+    ($path:expr, $root:expr) => {
+        tr!("root-symlink-loop-err", path = $path, root = $root)
+    };
+}
+
+macro_rules! no_snapshot_err {
+    ($name:expr) => {
+        tr!("no-snapshot-err", name = $name)
+    };
+}
+
+macro_rules! snapshot_exists_err {
+    ($name:expr) => {
+        tr!("snapshot-exists-err", name = $name)
+    };
+}
+
+macro_rules! snapshot_name_err {
+    ($name:expr) => {
+        tr!("snapshot-name-err", name = $name)
+    };
+}
+
+macro_rules! snapshot_read_err {
+    ($path:expr) => {
+        tr!("snapshot-read-err", path = $path)
+    };
+}
+
+macro_rules! dotenv_line_err {
+    ($line:expr, $path:expr) => {
+        tr!("dotenv-line-err", line = $line, path = $path)
+    };
+}
+
+macro_rules! dotenv_quote_err {
+    ($line:expr, $path:expr) => {
+        tr!("dotenv-quote-err", line = $line, path = $path)
+    };
+}
+
+macro_rules! dotenv_read_err {
+    ($path:expr) => {
+        tr!("dotenv-read-err", path = $path)
+    };
+}
+
+macro_rules! import_system_unsupported {
+    () => {
+        tr!("import-system-unsupported")
+    };
+}
+
+macro_rules! export_err {
+    ($format:expr) => {
+        tr!("export-err", format = $format)
+    };
+}
+
+macro_rules! project_dir_err {
+    ($path:expr) => {
+        tr!("project-dir-err", path = $path)
+    };
+}
+
+macro_rules! vault_read_err {
+    ($path:expr) => {
+        tr!("vault-read-err", path = $path)
+    };
+}
+
+macro_rules! vault_passphrase_err {
+    ($path:expr) => {
+        tr!("vault-passphrase-err", path = $path)
+    };
+}
+
+macro_rules! vault_empty_passphrase_err {
+    () => {
+        tr!("vault-empty-passphrase-err")
+    };
+}
+
+macro_rules! vault_key_err {
+    () => {
+        tr!("vault-key-err")
+    };
+}
+
+macro_rules! no_secret_err {
+    ($key:expr) => {
+        tr!("no-secret-err", key = $key)
+    };
+}
+
+macro_rules! secret_export_err {
+    ($key:expr) => {
+        tr!("secret-export-err", key = $key)
+    };
+}
+
+macro_rules! secret_write_err {
+    ($key:expr) => {
+        tr!("secret-write-err", key = $key)
+    };
+}
+
+macro_rules! search_regex_err {
+    ($pattern:expr) => {
+        tr!("search-regex-err", pattern = $pattern)
+    };
+}
+
+macro_rules! no_backup_err {
+    ($path:expr) => {
+        tr!("no-backup-err", path = $path)
+    };
+}
+
+macro_rules! backup_read_err {
+    ($path:expr) => {
+        tr!("backup-read-err", path = $path)
+    };
+}
+
+macro_rules! backup_restored {
+    ($path:expr, $name:expr) => {
+        tr!("backup-restored", path = $path, name = $name)
+    };
+}
+
+macro_rules! remove_var_success {
+    () => {
+        tr!("remove-var-success")
+    };
+}
+
+pub(crate) use add_var_success;
+pub(crate) use backup_read_err;
+pub(crate) use backup_restored;
+pub(crate) use change_failed_err;
+pub(crate) use change_file_err;
+pub(crate) use changes_applied;
+pub(crate) use cmd_fail_start;
+pub(crate) use cmd_failed_err;
+pub(crate) use command_not_found_err;
+pub(crate) use config_dir_name;
+pub(crate) use conflict_err;
+pub(crate) use construct_err_msg;
+pub(crate) use dotenv_exported;
+pub(crate) use dotenv_line_err;
+pub(crate) use dotenv_quote_err;
+pub(crate) use dotenv_read_err;
+pub(crate) use elevation_err;
+pub(crate) use empty_settings_err;
+pub(crate) use export_err;
+pub(crate) use helper_failed_err;
+pub(crate) use helper_launch_err;
+pub(crate) use helper_request_err;
+pub(crate) use import_system_unsupported;
+pub(crate) use invalid_setting_err;
+pub(crate) use json_parse_err;
+pub(crate) use legacy_config_dir_name;
+pub(crate) use lock_file_err;
+pub(crate) use make_file_err;
+pub(crate) use mkdir_err;
+pub(crate) use no_backup_err;
+pub(crate) use no_config_dir_err;
+pub(crate) use no_secret_err;
+pub(crate) use no_snapshot_err;
+pub(crate) use profile_err;
+pub(crate) use project_dir_err;
+pub(crate) use read_only_err;
+pub(crate) use remove_var_success;
+pub(crate) use rolled_back_err;
+pub(crate) use root_not_dir_err;
+pub(crate) use root_symlink_loop_err;
+pub(crate) use search_regex_err;
+pub(crate) use secret_added;
+pub(crate) use secret_export_err;
+pub(crate) use secret_removed;
+pub(crate) use secret_write_err;
+pub(crate) use settings_read_error;
+pub(crate) use settings_version_err;
+pub(crate) use snapshot_exists_err;
+pub(crate) use snapshot_name_err;
+pub(crate) use snapshot_read_err;
+#[cfg(not(target_os = "linux"))]
+pub(crate) use system_scope_unsupported;
+pub(crate) use tr;
+pub(crate) use unsupported_target_err;
+pub(crate) use validation_err;
+pub(crate) use value_not_found_err;
+pub(crate) use var_added_already;
+pub(crate) use var_not_set_err;
+pub(crate) use vault_empty_passphrase_err;
+pub(crate) use vault_key_err;
+pub(crate) use vault_passphrase_err;
+pub(crate) use vault_read_err;
+pub(crate) use watch_failed_err;
+pub(crate) use write_file_err;
+
+/// An error from a command, sent to the frontend as JSON. The variant says what went wrong,
+/// so the app can react to each kind of failure (ex: offer to reload after a conflict).
+#[derive(Clone, PartialEq, Debug)]
+pub enum GunmetalError {
+    /// the shell profile in the settings doesn't exist
+    ProfileNotFound(ErrorInfo),
+    /// there are no settings yet, or the shell profile isn't set
+    SettingsMissing(ErrorInfo),
+    /// a file couldn't be parsed (ex: settings.json isn't valid JSON)
+    ParseError(ErrorInfo),
+    /// a file couldn't be read
+    ReadFailed(ErrorInfo),
+    /// a file couldn't be written
+    WriteFailed(ErrorInfo),
+    /// the OS refused to open or write a file
+    PermissionDenied(ErrorInfo),
+    /// a file was changed by another program since it was loaded
+    Conflict(ErrorInfo),
+    /// a key, value or setting can't be used
+    InvalidInput(ErrorInfo),
+    /// a value to edit or remove isn't set
+    ValueNotFound(ErrorInfo),
+    /// read-only mode is on
+    ReadOnly(ErrorInfo),
+    /// the change isn't possible on this platform or for this file
+    Unsupported(ErrorInfo),
+    /// the privileged helper, or another program, failed
+    CommandFailed(ErrorInfo),
+    /// anything else
+    Other(ErrorInfo),
+}
+
+/// What every error carries
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ErrorInfo {
+    /// message to show the user
+    pub message: String,
+    /// file the error is about
+    pub path: Option<String>,
+    /// kind of OS error that caused this, if any
+    pub os_error: Option<std::io::ErrorKind>,
+    /// what the user can do about it, instead of the variant's usual suggestion
+    pub suggestion: Option<String>,
+    /// full text of the underlying error
+    pub details: Option<String>,
+    /// validation rule that failed, for an invalid key or value. The rule also tells which of the two it was
+    pub rule: Option<crate::validation::ValidationRule>,
+}
+
+impl ErrorInfo {
+    pub fn new(message: impl Into<String>) -> ErrorInfo {
+        return ErrorInfo {
+            message: message.into(),
+            ..ErrorInfo::default()
+        };
+    }
+
+    pub fn path(mut self, path: impl Into<String>) -> ErrorInfo {
+        self.path = Some(path.into());
+        return self;
+    }
+
+    /// Records the OS error that caused this
+    pub fn io(mut self, err: &std::io::Error) -> ErrorInfo {
+        self.os_error = Some(err.kind());
+        self.details = Some(err.to_string());
+        return self;
+    }
+
+    pub fn details(mut self, details: impl ToString) -> ErrorInfo {
+        self.details = Some(details.to_string());
+        return self;
+    }
+
+    pub fn suggestion(mut self, suggestion: impl Into<String>) -> ErrorInfo {
+        self.suggestion = Some(suggestion.into());
+        return self;
+    }
+
+    /// Records the validation rule an input broke
+    pub fn rule(mut self, rule: crate::validation::ValidationRule) -> ErrorInfo {
+        self.rule = Some(rule);
+        return self;
+    }
+}
+
+impl GunmetalError {
+    /// Picks the variant for a failed file operation: permission errors always become `PermissionDenied`,
+    /// missing files become `missing`, and anything else becomes `other`
+    /// ### Arguments
+    /// - missing: variant for a file that doesn't exist, ex: `GunmetalError::ProfileNotFound`
+    /// - other: variant for every other failure, ex: `GunmetalError::WriteFailed`
+    /// - info: message and path of the error
+    /// - err: the OS error
+    pub fn from_io(
+        missing: fn(ErrorInfo) -> GunmetalError,
+        other: fn(ErrorInfo) -> GunmetalError,
+        info: ErrorInfo,
+        err: &std::io::Error,
+    ) -> GunmetalError {
+        let info = info.io(err);
+        return match err.kind() {
+            std::io::ErrorKind::PermissionDenied => GunmetalError::PermissionDenied(info),
+            std::io::ErrorKind::NotFound => missing(info),
+            _ => other(info),
+        };
+    }
+
+    /// Stable name of the kind of error, sent to the frontend as `code`
+    pub fn code(&self) -> &'static str {
+        return match self {
+            GunmetalError::ProfileNotFound(_) => "profile_not_found",
+            GunmetalError::SettingsMissing(_) => "settings_missing",
+            GunmetalError::ParseError(_) => "parse_error",
+            GunmetalError::ReadFailed(_) => "read_failed",
+            GunmetalError::WriteFailed(_) => "write_failed",
+            GunmetalError::PermissionDenied(_) => "permission_denied",
+            GunmetalError::Conflict(_) => "conflict",
+            GunmetalError::InvalidInput(_) => "invalid_input",
+            GunmetalError::ValueNotFound(_) => "value_not_found",
+            GunmetalError::ReadOnly(_) => "read_only",
+            GunmetalError::Unsupported(_) => "unsupported",
+            GunmetalError::CommandFailed(_) => "command_failed",
+            GunmetalError::Other(_) => "other",
+        };
+    }
+
+    pub fn info(&self) -> &ErrorInfo {
+        return match self {
+            GunmetalError::ProfileNotFound(info)
+            | GunmetalError::SettingsMissing(info)
+            | GunmetalError::ParseError(info)
+            | GunmetalError::ReadFailed(info)
+            | GunmetalError::WriteFailed(info)
+            | GunmetalError::PermissionDenied(info)
+            | GunmetalError::Conflict(info)
+            | GunmetalError::InvalidInput(info)
+            | GunmetalError::ValueNotFound(info)
+            | GunmetalError::ReadOnly(info)
+            | GunmetalError::Unsupported(info)
+            | GunmetalError::CommandFailed(info)
+            | GunmetalError::Other(info) => info,
+        };
+    }
+
+    /// Puts the error under a message about what was being done, keeping its kind, path and OS error
+    pub fn context(self, message: impl Into<String>) -> GunmetalError {
+        let details = self.to_string();
+        return self.map_info(|info| ErrorInfo {
+            message: message.into(),
+            details: Some(details),
+            ..info
+        });
+    }
+
+    fn map_info(self, update: impl FnOnce(ErrorInfo) -> ErrorInfo) -> GunmetalError {
+        return match self {
+            GunmetalError::ProfileNotFound(info) => GunmetalError::ProfileNotFound(update(info)),
+            GunmetalError::SettingsMissing(info) => GunmetalError::SettingsMissing(update(info)),
+            GunmetalError::ParseError(info) => GunmetalError::ParseError(update(info)),
+            GunmetalError::ReadFailed(info) => GunmetalError::ReadFailed(update(info)),
+            GunmetalError::WriteFailed(info) => GunmetalError::WriteFailed(update(info)),
+            GunmetalError::PermissionDenied(info) => GunmetalError::PermissionDenied(update(info)),
+            GunmetalError::Conflict(info) => GunmetalError::Conflict(update(info)),
+            GunmetalError::InvalidInput(info) => GunmetalError::InvalidInput(update(info)),
+            GunmetalError::ValueNotFound(info) => GunmetalError::ValueNotFound(update(info)),
+            GunmetalError::ReadOnly(info) => GunmetalError::ReadOnly(update(info)),
+            GunmetalError::Unsupported(info) => GunmetalError::Unsupported(update(info)),
+            GunmetalError::CommandFailed(info) => GunmetalError::CommandFailed(update(info)),
+            GunmetalError::Other(info) => GunmetalError::Other(update(info)),
+        };
+    }
+
+    /// What the user can do about the error, in the user's language
+    pub fn suggestion(&self) -> String {
+        if let Some(suggestion) = &self.info().suggestion {
+            return suggestion.to_owned();
+        }
+        // ex: "suggestion-permission-denied"
+        return tr!(&format!("suggestion-{}", self.code().replace('_', "-")));
+    }
+}
+
+/// Same text as the old string errors: the message, then the full underlying error
+impl std::fmt::Display for GunmetalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let info = self.info();
+        match &info.details {
+            Some(details) => write!(f, "{}", construct_err_msg!(&info.message, details)),
+            None => write!(f, "{}", &info.message),
+        }
+    }
+}
+
+impl serde::Serialize for GunmetalError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let info = self.info();
+        let mut state = serializer.serialize_struct("GunmetalError", 8)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &info.message)?;
+        state.serialize_field("path", &info.path)?;
+        state.serialize_field("os_error", &info.os_error.map(os_error_code))?;
+        state.serialize_field("suggestion", &self.suggestion())?;
+        state.serialize_field("details", &info.details)?;
+        state.serialize_field("rule", &info.rule.map(|rule| rule.code()))?;
+        state.serialize_field("field", &info.rule.map(|rule| rule.field()))?;
+        return state.end();
+    }
+}
+
+/// Messages that don't fit another kind of error
+impl From<String> for GunmetalError {
+    fn from(message: String) -> GunmetalError {
+        return GunmetalError::Other(ErrorInfo::new(message));
+    }
+}
+
+impl From<GunmetalError> for String {
+    fn from(err: GunmetalError) -> String {
+        return err.to_string();
+    }
+}
+
+/// Stable name of an OS error kind, sent as `os_error`. The names don't change with the compiler's
+/// debug output, and kinds without a name of their own are sent as "other".
+fn os_error_code(kind: std::io::ErrorKind) -> &'static str {
+    use std::io::ErrorKind;
+    return match kind {
+        ErrorKind::NotFound => "not_found",
+        ErrorKind::PermissionDenied => "permission_denied",
+        ErrorKind::AlreadyExists => "already_exists",
+        ErrorKind::WouldBlock => "would_block",
+        ErrorKind::InvalidInput => "invalid_input",
+        ErrorKind::InvalidData => "invalid_data",
+        ErrorKind::TimedOut => "timed_out",
+        ErrorKind::WriteZero => "write_zero",
+        ErrorKind::Interrupted => "interrupted",
+        ErrorKind::UnexpectedEof => "unexpected_eof",
+        ErrorKind::Unsupported => "unsupported",
+        ErrorKind::OutOfMemory => "out_of_memory",
+        ErrorKind::ConnectionRefused => "connection_refused",
+        ErrorKind::ConnectionReset => "connection_reset",
+        ErrorKind::ConnectionAborted => "connection_aborted",
+        ErrorKind::NotConnected => "not_connected",
+        ErrorKind::AddrInUse => "addr_in_use",
+        ErrorKind::AddrNotAvailable => "addr_not_available",
+        ErrorKind::BrokenPipe => "broken_pipe",
+        _ => "other",
+    };
+}
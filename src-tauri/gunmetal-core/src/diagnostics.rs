@@ -0,0 +1,194 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::consts_and_errors::*;
+use crate::fs_root::rooted;
+use crate::i18n::current_locale;
+use crate::logging::log_files;
+use crate::redact::redact_text;
+use crate::settings::load_settings;
+use crate::settings_utils::get_config_path;
+use crate::shell_quote::{parse_profile, ShellDialect};
+
+/// What the parser made of one profile. Only variable names are kept, never values.
+#[derive(Serialize, Debug)]
+pub struct ProfileDiagnostics {
+    pub path: String,
+    /// "posix" or "fish"
+    pub dialect: String,
+    /// why the profile couldn't be read, if it couldn't
+    pub error: Option<String>,
+    /// how many assignments were found
+    pub assignments: usize,
+    /// every variable assigned, in the order first assigned
+    pub keys: Vec<String>,
+    /// variables assigned again later without keeping their earlier value
+    pub overridden: Vec<String>,
+    /// line number where a statement starts that is still open at the end of the file (ex: an unclosed quote)
+    pub incomplete_line: Option<usize>,
+}
+
+/// Everything in diagnostics.json
+#[derive(Serialize, Debug)]
+pub struct Diagnostics {
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+    pub locale: String,
+    pub config_path: Option<String>,
+    /// why the settings couldn't be read, if they couldn't
+    pub settings_error: Option<String>,
+    pub profiles: Vec<ProfileDiagnostics>,
+}
+
+/// Saves the logs, the settings and what the parser found in each profile to a zip file, to attach to bug reports
+/// ### Arguments
+/// - path (optional String): where to save the zip. Defaults to a new file in the config directory
+/// ### Returns
+/// The path the zip was saved to, or an error
+/// ### Types of Errors
+/// - WriteFailed, when the zip can't be written
+/// - PermissionDenied, when the user can't write to the path
+pub fn export_diagnostics(path: Option<String>) -> Result<String, GunmetalError> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let name = format!(
+                "gunmetal-diagnostics-{}.zip",
+                chrono::Local::now().format("%Y%m%d-%H%M%S")
+            );
+            PathBuf::from(get_config_path()?)
+                .join(name)
+                .to_string_lossy()
+                .to_string()
+        }
+    };
+    log::info!("exporting diagnostics to {}", path);
+
+    let file = File::create(&path).map_err(|err| {
+        GunmetalError::from_io(
+            GunmetalError::WriteFailed,
+            GunmetalError::WriteFailed,
+            ErrorInfo::new(make_file_err!(&path)).path(&path),
+            &err,
+        )
+    })?;
+    write_bundle(file).map_err(|err| {
+        GunmetalError::WriteFailed(
+            ErrorInfo::new(make_file_err!(&path))
+                .path(&path)
+                .details(err),
+        )
+    })?;
+    return Ok(path);
+}
+
+/// Writes the logs, settings.json and diagnostics.json into the zip
+fn write_bundle(file: File) -> zip::result::ZipResult<()> {
+    let mut zip = ZipWriter::new(file);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for log_file in log_files() {
+        let name = log_file.file_name().unwrap_or_default().to_string_lossy();
+        // logs are redacted as they're written, this also covers logs from older versions
+        let content = fs::read_to_string(&log_file).unwrap_or_default();
+        zip.start_file(format!("logs/{}", name), options)?;
+        zip.write_all(redact_text(&content).as_bytes())?;
+    }
+
+    if let Ok(config_path) = get_config_path() {
+        let settings_path = Path::new(&config_path).join("settings.json");
+        if let Ok(settings) = fs::read_to_string(&settings_path) {
+            zip.start_file("settings.json", options)?;
+            zip.write_all(settings.as_bytes())?;
+        }
+    }
+
+    let diagnostics = serde_json::to_string_pretty(&collect_diagnostics()).unwrap_or_default();
+    zip.start_file("diagnostics.json", options)?;
+    zip.write_all(diagnostics.as_bytes())?;
+
+    zip.finish()?;
+    return Ok(());
+}
+
+/// Gathers the app's version, the platform and what the parser found in each profile in the settings
+pub fn collect_diagnostics() -> Diagnostics {
+    let config_path = get_config_path().ok();
+    let settings = match &config_path {
+        Some(config_path) => load_settings(&format!("{}/settings.json", config_path)),
+        None => Err(GunmetalError::SettingsMissing(ErrorInfo::new(
+            no_config_dir_err!(),
+        ))),
+    };
+
+    let mut profiles: Vec<ProfileDiagnostics> = Vec::new();
+    if let Ok(settings) = &settings {
+        for profile in settings.profiles.iter().chain([&settings.shell_profile]) {
+            if profile.is_empty() {
+                continue;
+            }
+            // the profiles are under the root, if one is set
+            let profile = rooted(profile).unwrap_or_else(|_| profile.to_owned());
+            if !profiles.iter().any(|found| found.path == profile) {
+                profiles.push(profile_diagnostics(&profile));
+            }
+        }
+    }
+
+    return Diagnostics {
+        version: String::from(env!("CARGO_PKG_VERSION")),
+        os: String::from(std::env::consts::OS),
+        arch: String::from(std::env::consts::ARCH),
+        locale: current_locale(),
+        config_path,
+        settings_error: settings.err().map(|err| err.to_string()),
+        profiles,
+    };
+}
+
+/// Parses a profile and describes what was found, without any values
+pub fn profile_diagnostics(path: &str) -> ProfileDiagnostics {
+    let dialect = ShellDialect::from_profile_path(path);
+    let mut diagnostics = ProfileDiagnostics {
+        path: String::from(path),
+        dialect: String::from(match dialect {
+            ShellDialect::Posix => "posix",
+            ShellDialect::Fish => "fish",
+        }),
+        error: None,
+        assignments: 0,
+        keys: Vec::new(),
+        overridden: Vec::new(),
+        incomplete_line: None,
+    };
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) => {
+            diagnostics.error = Some(err.to_string());
+            return diagnostics;
+        }
+    };
+
+    let parsed = parse_profile(&content, dialect);
+    let mut seen: HashSet<String> = HashSet::new();
+    diagnostics.assignments = parsed.assignments.len();
+    for profile_assignment in &parsed.assignments {
+        let assignment = &profile_assignment.assignment;
+        if seen.insert(assignment.key.to_owned()) {
+            diagnostics.keys.push(assignment.key.to_owned());
+        } else if !assignment.keeps_existing && !diagnostics.overridden.contains(&assignment.key) {
+            diagnostics.overridden.push(assignment.key.to_owned());
+        }
+    }
+    // the open statement runs to the end of the file, so it starts this many lines before the end
+    diagnostics.incomplete_line = parsed
+        .incomplete
+        .map(|statement| content.lines().count() + 1 - statement.split('\n').count());
+    return diagnostics;
+}
@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+
+/// Number of unchanged lines shown around each change in a hunk
+const CONTEXT_LINES: usize = 3;
+
+/// One line of an edit script between two versions of a file
+#[derive(Clone, Copy, PartialEq)]
+enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// What happened to one entry of a list variable between two versions
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryChange {
+    Kept,
+    Added,
+    Removed,
+}
+
+/// One entry of a list variable in a diff between two versions
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct EntryDiff {
+    pub change: EntryChange,
+    pub entry: String,
+}
+
+/// Lines up two versions of a list variable entry by entry, the way a diff of PATH with one directory per line would
+/// ### Returns
+/// Every entry of both versions in order, each marked kept, added or removed
+pub fn diff_entries(old: &[String], new: &[String]) -> Vec<EntryDiff> {
+    let old: Vec<&str> = old.iter().map(String::as_str).collect();
+    let new: Vec<&str> = new.iter().map(String::as_str).collect();
+    return edit_script(&old, &new)
+        .into_iter()
+        .map(|line| {
+            let (change, entry) = match line {
+                DiffLine::Same(entry) => (EntryChange::Kept, entry),
+                DiffLine::Added(entry) => (EntryChange::Added, entry),
+                DiffLine::Removed(entry) => (EntryChange::Removed, entry),
+            };
+            EntryDiff {
+                change,
+                entry: String::from(entry),
+            }
+        })
+        .collect();
+}
+
+/// Builds a unified diff (the same format as `diff -u`) between two versions of a file
+/// ### Arguments
+/// - path: path of the file, used in the `---`/`+++` headers
+/// - old: current contents of the file
+/// - new: contents the file would have after the change
+/// ### Returns
+/// The diff as a String, empty if both versions are the same
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    // lines keep their '\n', so adding or removing the one at the end of the file is a change too
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let script = edit_script(&old_lines, &new_lines);
+
+    // nothing changed, no diff to show
+    if script.iter().all(|line| matches!(line, DiffLine::Same(_))) {
+        return String::new();
+    }
+
+    let mut diff = format!("--- {}\n+++ {}\n", path, path);
+    for (start, end) in hunk_ranges(&script) {
+        // line numbers of the hunk in both files (1-based)
+        let old_start = 1 + script[..start]
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Added(_)))
+            .count();
+        let new_start = 1 + script[..start]
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Removed(_)))
+            .count();
+        let hunk = &script[start..end];
+        let old_len = hunk
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Added(_)))
+            .count();
+        let new_len = hunk
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Removed(_)))
+            .count();
+
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range_str(old_start, old_len),
+            hunk_range_str(new_start, new_len)
+        ));
+        for line in hunk {
+            let (marker, text) = match line {
+                DiffLine::Same(text) => (' ', text),
+                DiffLine::Removed(text) => ('-', text),
+                DiffLine::Added(text) => ('+', text),
+            };
+            diff.push(marker);
+            diff.push_str(text);
+            // the last line of a file without a newline at the end is marked, like `diff -u` does
+            if !text.ends_with('\n') {
+                diff.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    return diff;
+}
+
+/// Formats the start/length pair of a hunk header, `diff -u` style
+fn hunk_range_str(start: usize, len: usize) -> String {
+    match len {
+        // empty ranges point at the line before the change
+        0 => format!("{},0", start - 1),
+        1 => format!("{}", start),
+        _ => format!("{},{}", start, len),
+    }
+}
+
+/// Computes the shortest edit script between two lists of lines using their longest common subsequence
+fn edit_script<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffLine<'a>> {
+    // lcs[i][j] is the length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut script: Vec<DiffLine> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            script.push(DiffLine::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            script.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            script.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+    script.extend(old[i..].iter().map(|line| DiffLine::Removed(line)));
+    script.extend(new[j..].iter().map(|line| DiffLine::Added(line)));
+
+    return script;
+}
+
+/// Groups the changed lines of an edit script into hunks, each padded with context lines
+/// ### Returns
+/// A list of (start, end) index ranges into the edit script
+fn hunk_ranges(script: &[DiffLine]) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+
+    for (index, line) in script.iter().enumerate() {
+        if matches!(line, DiffLine::Same(_)) {
+            continue;
+        }
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = (index + CONTEXT_LINES + 1).min(script.len());
+        match ranges.last_mut() {
+            // merge with the previous hunk if their context overlaps
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    return ranges;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_an_added_line_with_context() {
+        let old = "a\nb\nc\nd\ne\nf\n";
+        let new = "a\nb\nc\nX\nd\ne\nf\n";
+        assert_eq!(
+            unified_diff("/p", old, new),
+            "--- /p\n+++ /p\n@@ -1,6 +1,7 @@\n a\n b\n c\n+X\n d\n e\n f\n"
+        );
+    }
+
+    #[test]
+    fn splits_far_apart_changes_into_hunks() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let new = "one\n2\n3\n4\n5\n6\n7\n8\n9\nten\n";
+        assert_eq!(
+            unified_diff("/p", old, new),
+            "--- /p\n+++ /p\n@@ -1,4 +1,4 @@\n-1\n+one\n 2\n 3\n 4\n@@ -7,4 +7,4 @@\n 7\n 8\n 9\n-10\n+ten\n"
+        );
+    }
+
+    #[test]
+    fn is_empty_when_nothing_changed() {
+        assert_eq!(unified_diff("/p", "a\nb\n", "a\nb\n"), "");
+        assert_eq!(unified_diff("/p", "", ""), "");
+    }
+
+    #[test]
+    fn shows_a_new_file() {
+        assert_eq!(
+            unified_diff("/p", "", "a\n"),
+            "--- /p\n+++ /p\n@@ -0,0 +1 @@\n+a\n"
+        );
+    }
+
+    #[test]
+    fn shows_a_change_to_the_last_newline() {
+        assert_eq!(
+            unified_diff("/p", "a\nb", "a\nb\n"),
+            "--- /p\n+++ /p\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n"
+        );
+        assert_eq!(
+            unified_diff("/p", "a\n", "a"),
+            "--- /p\n+++ /p\n@@ -1 +1 @@\n-a\n+a\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn lines_up_list_entries() {
+        let old = vec![String::from("/a"), String::from("/b")];
+        let new = vec![String::from("/b"), String::from("/c")];
+        let diff = diff_entries(&old, &new);
+        let changes: Vec<(EntryChange, &str)> = diff
+            .iter()
+            .map(|entry| (entry.change, entry.entry.as_str()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (EntryChange::Removed, "/a"),
+                (EntryChange::Kept, "/b"),
+                (EntryChange::Added, "/c"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,395 @@
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::backups::try_backup;
+use crate::changes::{apply_changes, Change};
+use crate::consts_and_errors::*;
+use crate::diff_utils::unified_diff;
+use crate::file_guard::replace_file;
+use crate::provenance::{resolve, ResolvedVar};
+use crate::settings_utils::{check_writable, get_settings};
+use crate::shell_quote::is_name;
+use crate::system_scope::Scope;
+
+/// Joins the entries of a list variable when it's written to a .env file
+const SEPARATOR: &str = if cfg!(target_os = "windows") {
+    ";"
+} else {
+    ":"
+};
+
+/// A variable read from a .env file
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct DotenvVar {
+    pub key: String,
+    /// the value with quoting and escapes removed
+    pub value: String,
+    /// line the assignment starts on, starting at 1
+    pub line: usize,
+}
+
+/// Reads the variables of a .env file: `KEY=value` lines, optionally starting with `export`,
+/// with values unquoted, in single quotes (taken as is) or in double quotes (with `\n`, `\t`, `\"`, `\\` and `\$` escapes).
+/// Quoted values can span several lines. Blank lines and `#` comments are skipped.
+/// ### Arguments
+/// - content: contents of the file
+/// - path: path of the file, for error messages
+/// ### Returns
+/// The variables in the order they're first set (a key set twice keeps its last value),
+/// or an error naming the first line that can't be read
+pub fn parse_dotenv(content: &str, path: &str) -> Result<Vec<DotenvVar>, GunmetalError> {
+    let mut vars: Vec<DotenvVar> = Vec::new();
+    let lines: Vec<&str> = content.lines().collect();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line_number = index + 1;
+        let line = lines[index].trim_start();
+        index += 1;
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = match line.strip_prefix("export") {
+            Some(rest) if rest.starts_with([' ', '\t']) => rest.trim_start(),
+            _ => line,
+        };
+        let (key, rest) = line
+            .split_once('=')
+            .ok_or_else(|| line_err(line_number, path))?;
+        let key = key.trim_end();
+        if !is_name(key) {
+            return Err(line_err(line_number, path));
+        }
+        let rest = rest.trim_start();
+
+        let value = match rest.chars().next() {
+            Some(quote @ ('\'' | '"')) => {
+                // keep adding lines until the quote is closed
+                let mut quoted = String::from(&rest[1..]);
+                loop {
+                    if let Some(value) = closed_value(&quoted, quote) {
+                        break value;
+                    }
+                    if index >= lines.len() {
+                        return Err(GunmetalError::ParseError(
+                            ErrorInfo::new(dotenv_quote_err!(line_number, path)).path(path),
+                        ));
+                    }
+                    quoted.push('\n');
+                    quoted.push_str(lines[index]);
+                    index += 1;
+                }
+            }
+            // an unquoted value ends at a comment
+            _ => {
+                let end = rest
+                    .find(" #")
+                    .or_else(|| rest.find("\t#"))
+                    .unwrap_or(rest.len());
+                String::from(rest[..end].trim_end())
+            }
+        };
+
+        match vars.iter_mut().find(|var| var.key == key) {
+            Some(var) => {
+                var.value = value;
+                var.line = line_number;
+            }
+            None => vars.push(DotenvVar {
+                key: String::from(key),
+                value,
+                line: line_number,
+            }),
+        }
+    }
+    return Ok(vars);
+}
+
+/// Reads a .env file and sets each of its variables in the shell profile, all or nothing, like `apply_changes`.
+/// Each variable replaces any value it had. Variables with an empty value (ex: `KEY=` in a template) are skipped.
+/// ### Arguments
+/// - path: the .env file
+/// - target (optional Scope): where to write, defaults to the "write_target" setting. Only "user" is supported
+/// - dry_run (optional bool): if true, nothing is written and a unified diff of the profile is returned instead
+/// ### Returns
+/// A success message (or the diff when doing a dry run), or an error
+/// ### Types of Errors
+/// - ReadFailed, when the .env file can't be read
+/// - ParseError, when a line isn't an assignment or a quoted value is never closed
+/// - Unsupported, when the target is "system"
+/// - any error of `apply_changes`
+pub fn import_dotenv(
+    path: String,
+    target: Option<Scope>,
+    dry_run: Option<bool>,
+) -> Result<String, GunmetalError> {
+    let target = target.unwrap_or_else(|| {
+        get_settings()
+            .map(|settings| settings.write_target)
+            .unwrap_or(Scope::User)
+    });
+    // the privileged helper can only add values, not replace them
+    if target == Scope::System {
+        return Err(GunmetalError::Unsupported(ErrorInfo::new(
+            import_system_unsupported!(),
+        )));
+    }
+
+    let content = fs::read_to_string(&path).map_err(|err| {
+        GunmetalError::from_io(
+            GunmetalError::ReadFailed,
+            GunmetalError::ReadFailed,
+            ErrorInfo::new(dotenv_read_err!(&path)).path(&path),
+            &err,
+        )
+    })?;
+    let changes: Vec<Change> = parse_dotenv(&content, &path)?
+        .into_iter()
+        .filter(|var| !var.value.is_empty())
+        .map(|var| Change::Set {
+            key: var.key,
+            value: var.value,
+            file: None,
+        })
+        .collect();
+
+    log::info!("importing {} variable(s) from {}", changes.len(), path);
+    return apply_changes(changes, dry_run);
+}
+
+/// Writes variables as they are now to a .env file, replacing the file if it exists (a backup of it is kept).
+/// Lists are joined with ':' (';' on Windows). Secret variables are refused, their values stay in the vault.
+/// ### Arguments
+/// - keys: the variables to write, in order
+/// - path: the .env file to write
+/// - dry_run (optional bool): if true, nothing is written and a unified diff of the file is returned instead
+/// ### Returns
+/// A success message (or the diff when doing a dry run), or an error
+/// ### Types of Errors
+/// - ValueNotFound, when one of the variables isn't set
+/// - InvalidInput, when one of the variables is secret
+/// - ReadOnly, when read-only mode is on in the settings
+/// - WriteFailed, when the file can't be written
+/// - PermissionDenied, when the user can't write to the path
+pub fn export_dotenv(
+    keys: Vec<String>,
+    path: String,
+    dry_run: Option<bool>,
+) -> Result<String, GunmetalError> {
+    let dry_run = dry_run.unwrap_or(false);
+    if !dry_run {
+        check_writable()?;
+    }
+    let content = dotenv_content(&keys, &resolve()?.vars)?;
+
+    let original = match Path::new(&path).exists() {
+        true => fs::read_to_string(&path).unwrap_or_default(),
+        false => String::new(),
+    };
+    if dry_run {
+        return Ok(unified_diff(&path, &original, &content));
+    }
+
+    if !original.is_empty() {
+        try_backup(&path, &original);
+    }
+    // a failed write leaves the old file as it was
+    replace_file(&path, &content, None).map_err(|err| {
+        GunmetalError::from_io(
+            GunmetalError::WriteFailed,
+            GunmetalError::WriteFailed,
+            ErrorInfo::new(make_file_err!(&path)).path(&path),
+            &err,
+        )
+    })?;
+    log::info!("exported {} variable(s) to {}", keys.len(), path);
+    return Ok(dotenv_exported!(keys.len(), &path));
+}
+
+/// Builds the .env line for a variable, quoting the value only if it needs it:
+/// single quotes when they're enough, otherwise double quotes with escapes
+pub fn dotenv_line(key: &str, value: &str) -> String {
+    let plain = |ch: char| ch.is_ascii_alphanumeric() || "_-./:@,+%".contains(ch);
+    if value.chars().all(plain) {
+        return format!("{}={}", key, value);
+    }
+    if !value.contains(['\'', '\n', '\r']) {
+        return format!("{}='{}'", key, value);
+    }
+
+    let mut escaped = String::new();
+    for ch in value.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '$' => escaped.push_str("\\$"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(ch),
+        }
+    }
+    return format!("{}=\"{}\"", key, escaped);
+}
+
+/// Builds the contents of a .env file holding some of the variables, one line each
+/// ### Arguments
+/// - keys: the variables to write, in order
+/// - vars: every variable, as `resolve` finds them
+/// ### Returns
+/// The contents, or an error if one of the variables isn't set or is secret
+fn dotenv_content(keys: &[String], vars: &[ResolvedVar]) -> Result<String, GunmetalError> {
+    let mut content = String::new();
+    for key in keys {
+        let var = vars
+            .iter()
+            .find(|var| &var.key == key)
+            .ok_or_else(|| GunmetalError::ValueNotFound(ErrorInfo::new(var_not_set_err!(key))))?;
+        // only the mask is known here, and the real value must not end up in a plain file anyway
+        if var.secret {
+            return Err(GunmetalError::InvalidInput(ErrorInfo::new(
+                secret_export_err!(key),
+            )));
+        }
+        content.push_str(&dotenv_line(key, &var.entries.join(SEPARATOR)));
+        content.push('\n');
+    }
+    return Ok(content);
+}
+
+/// Reads a quoted value up to its closing quote
+/// ### Arguments
+/// - quoted: everything after the opening quote
+/// - quote: the opening quote, `'` or `"`
+/// ### Returns
+/// The value with escapes removed (in double quotes only), or None if the quote isn't closed yet
+fn closed_value(quoted: &str, quote: char) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = quoted.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            _ if ch == quote => return Some(value),
+            '\\' if quote == '"' => match chars.next()? {
+                'n' => value.push('\n'),
+                'r' => value.push('\r'),
+                't' => value.push('\t'),
+                escaped @ ('"' | '\\' | '$') => value.push(escaped),
+                other => {
+                    value.push('\\');
+                    value.push(other);
+                }
+            },
+            _ => value.push(ch),
+        }
+    }
+    return None;
+}
+
+fn line_err(line: usize, path: &str) -> GunmetalError {
+    return GunmetalError::ParseError(ErrorInfo::new(dotenv_line_err!(line, path)).path(path));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Vec<(String, String, usize)> {
+        return parse_dotenv(content, "/p/.env")
+            .unwrap()
+            .into_iter()
+            .map(|var| (var.key, var.value, var.line))
+            .collect();
+    }
+
+    #[test]
+    fn reads_plain_and_exported_assignments() {
+        let content = "# comment\n\nA=1\nexport B = two # note\nexport\tC=3\nexportD=4\nA=5\n";
+        let vars: Vec<(String, String)> = parse(content)
+            .into_iter()
+            .map(|(key, value, _)| (key, value))
+            .collect();
+        assert_eq!(
+            vars,
+            vec![
+                (String::from("A"), String::from("5")),
+                (String::from("B"), String::from("two")),
+                (String::from("C"), String::from("3")),
+                (String::from("exportD"), String::from("4")),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_quoted_and_multiline_values() {
+        let content = "A='$HOME \\n'\nB=\"tab\\there \\\"q\\\" \\$HOME\"\nC=\"one\ntwo\"\nD=last\n";
+        let vars = parse(content);
+        assert_eq!(vars[0].1, "$HOME \\n");
+        assert_eq!(vars[1].1, "tab\there \"q\" $HOME");
+        assert_eq!(vars[2].1, "one\ntwo");
+        // lines are counted from where each assignment starts
+        assert_eq!(vars[3].2, 5);
+    }
+
+    #[test]
+    fn refuses_an_unclosed_quote() {
+        let err = parse_dotenv("A=1\nB=\"open\nC=3\n", "/p/.env").unwrap_err();
+        assert!(matches!(err, GunmetalError::ParseError(_)));
+        assert_eq!(err.to_string(), dotenv_quote_err!(2, "/p/.env"));
+
+        let err = parse_dotenv("A=1\nnot an assignment\n", "/p/.env").unwrap_err();
+        assert_eq!(err.to_string(), dotenv_line_err!(2, "/p/.env"));
+    }
+
+    #[test]
+    fn quotes_values_so_they_read_back_the_same() {
+        let values = [
+            "/usr/bin:/bin",
+            "",
+            "two words",
+            "$HOME and `cmd`",
+            "it's",
+            "line one\nline two",
+            "back\\slash \"quoted\" $VAR",
+            "carriage\r\nreturn",
+        ];
+        for value in values {
+            let line = dotenv_line("KEY", value);
+            assert_eq!(
+                parse(&line),
+                vec![(String::from("KEY"), String::from(value), 1)]
+            );
+        }
+        assert_eq!(dotenv_line("KEY", "/usr/bin"), "KEY=/usr/bin");
+        assert_eq!(dotenv_line("KEY", "a b"), "KEY='a b'");
+        assert_eq!(dotenv_line("KEY", "it's"), "KEY=\"it's\"");
+    }
+
+    #[test]
+    fn refuses_to_export_secrets() {
+        let vars = vec![
+            ResolvedVar {
+                key: String::from("PATH"),
+                entries: vec![String::from("/usr/bin"), String::from("/bin")],
+                sources: Vec::new(),
+                secret: false,
+            },
+            ResolvedVar {
+                key: String::from("TOKEN"),
+                entries: vec![String::from("****")],
+                sources: Vec::new(),
+                secret: true,
+            },
+        ];
+        assert_eq!(
+            dotenv_content(&[String::from("PATH")], &vars).unwrap(),
+            format!("PATH=/usr/bin{}/bin\n", SEPARATOR)
+        );
+        let err =
+            dotenv_content(&[String::from("PATH"), String::from("TOKEN")], &vars).unwrap_err();
+        assert!(matches!(err, GunmetalError::InvalidInput(_)));
+        assert!(matches!(
+            dotenv_content(&[String::from("MISSING")], &vars),
+            Err(GunmetalError::ValueNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::consts_and_errors::*;
+use crate::memory_backend::MemoryBackend;
+use crate::provenance::{process_sources, Provenance};
+use crate::system_scope::Scope;
+#[allow(unused_imports)]
+use crate::unix_backend::UnixProfileBackend;
+#[allow(unused_imports)]
+use crate::windows_backend::{SystemCommandRunner, WindowsBackend};
+
+/// Somewhere a backend reads variables from or writes them to
+#[derive(Serialize, Clone, Debug)]
+pub struct Target {
+    /// path of the file, or registry key on Windows
+    pub location: String,
+    /// kind of target, ex: "POSIX shell profiles"
+    pub format: String,
+    pub scope: Scope,
+}
+
+/// Which end of a variable a new value is added to
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Position {
+    /// before the existing values, so it wins (ex: a directory searched first in PATH)
+    #[default]
+    Prepend,
+    /// after the existing values
+    Append,
+}
+
+/// Reads and writes environment variables on one platform.
+/// The Tauri commands only talk to this trait, so each platform's logic can be swapped out (or faked).
+pub trait EnvBackend: Sync {
+    /// Reads every variable with its entries
+    fn read(&self) -> Result<HashMap<String, Vec<String>>, GunmetalError>;
+
+    /// Adds a value to the front or the end of a variable
+    /// ### Returns
+    /// A success message, or the diff of the change when `dry_run` is true
+    fn write(
+        &self,
+        key: &str,
+        value: &str,
+        position: Position,
+        dry_run: bool,
+    ) -> Result<String, GunmetalError>;
+
+    /// Removes one value from a variable
+    /// ### Returns
+    /// A success message, or the diff of the change when `dry_run` is true
+    fn remove(&self, key: &str, value: &str, dry_run: bool) -> Result<String, GunmetalError>;
+
+    /// Lists the places this backend reads from and writes to
+    fn list_targets(&self) -> Result<Vec<Target>, GunmetalError>;
+
+    /// Finds where each variable is set, and any problems in those places.
+    /// By default everything comes from the process environment.
+    fn provenance(&self) -> Result<Provenance, GunmetalError> {
+        let mut provenance = Provenance::default();
+        process_sources(&mut provenance);
+        return Ok(provenance);
+    }
+}
+
+static MEMORY_BACKEND: MemoryBackend = MemoryBackend::new();
+#[cfg(target_os = "windows")]
+static PLATFORM_BACKEND: WindowsBackend<SystemCommandRunner> =
+    WindowsBackend::new(SystemCommandRunner);
+#[cfg(not(target_os = "windows"))]
+static PLATFORM_BACKEND: UnixProfileBackend = UnixProfileBackend;
+
+/// Gets the backend for the current platform.
+/// Setting `GUNMETAL_BACKEND=memory` uses an in-memory fake instead, so the app can be tried without touching any files.
+pub fn backend() -> &'static dyn EnvBackend {
+    if std::env::var("GUNMETAL_BACKEND").as_deref() == Ok("memory") {
+        return &MEMORY_BACKEND;
+    }
+    return &PLATFORM_BACKEND;
+}
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+
+use crate::consts_and_errors::*;
+use crate::file_guard::replace_file;
+use crate::provenance::{resolve, Issue, Source, SCHEMA_VERSION};
+use crate::redact::{is_secret_key, REDACTED};
+use crate::settings::Settings;
+use crate::settings_utils::get_settings;
+
+/// File formats the variables can be exported to
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ExportFormat {
+    /// Guesses the format from a file's extension, JSON if it's not one of the others
+    pub fn from_path(path: &str) -> ExportFormat {
+        let path = path.to_lowercase();
+        if path.ends_with(".yaml") || path.ends_with(".yml") {
+            return ExportFormat::Yaml;
+        }
+        if path.ends_with(".toml") {
+            return ExportFormat::Toml;
+        }
+        return ExportFormat::Json;
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "JSON",
+            ExportFormat::Yaml => "YAML",
+            ExportFormat::Toml => "TOML",
+        }
+    }
+}
+
+/// Whether a variable holds one value or a list of them
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueKind {
+    Scalar,
+    /// a list of entries, like PATH
+    List,
+}
+
+/// A variable as it's exported
+#[derive(Serialize, Clone, Debug)]
+pub struct ExportedVar {
+    pub key: String,
+    pub kind: ValueKind,
+    /// one entry for scalars. A redacted variable has a single `[redacted]` entry
+    pub entries: Vec<String>,
+    /// true if the value was hidden because the variable holds a secret or is marked secret
+    pub redacted: bool,
+    /// the files the value comes from, in the order they apply, without repeats
+    pub files: Vec<String>,
+    /// every place that sets the value, see `ResolvedVar`
+    pub sources: Vec<Source>,
+}
+
+/// Every variable with where it's set and the problems found, for inventories and audits
+#[derive(Serialize, Clone, Debug)]
+pub struct EnvExport {
+    /// see `SCHEMA_VERSION`
+    pub schema_version: u32,
+    /// when the export was made, RFC 3339
+    pub exported_at: String,
+    pub vars: Vec<ExportedVar>,
+    pub diagnostics: Vec<Issue>,
+}
+
+/// Gathers every variable, sorted by name, with where it's set, whether it's a list and the problems found
+/// ### Arguments
+/// - redact: if true, the values of variables holding secrets are replaced by `[redacted]`
+/// ### Returns
+/// The export, or an error if the variables can't be read
+pub fn gather_export(redact: bool) -> Result<EnvExport, GunmetalError> {
+    let resolved = resolve()?;
+    // without settings, only variables with several entries count as lists
+    let settings = get_settings().unwrap_or_default();
+    let secrets: Vec<String> = resolved
+        .vars
+        .iter()
+        .filter(|var| var.secret)
+        .map(|var| var.key.to_owned())
+        .collect();
+
+    let vars = resolved
+        .vars
+        .into_iter()
+        .map(|var| {
+            let kind = match is_list(&settings, &var.key, &var.entries) {
+                true => ValueKind::List,
+                false => ValueKind::Scalar,
+            };
+            // secret variables are already masked, whether redacting or not
+            let redacted = var.secret || (redact && is_secret_key(&var.key));
+            let mut files: Vec<String> = Vec::new();
+            for file in var.sources.iter().filter_map(|source| source.file.as_ref()) {
+                if !files.contains(file) {
+                    files.push(file.to_owned());
+                }
+            }
+            ExportedVar {
+                entries: match redacted {
+                    true => vec![String::from(REDACTED)],
+                    false => var.entries,
+                },
+                key: var.key,
+                kind,
+                redacted,
+                files,
+                sources: var.sources,
+            }
+        })
+        .collect();
+
+    // messages can quote a secret's value (ex: a PATH entry), so they're hidden with it.
+    // Like their values, the messages about secret variables are hidden whether redacting or not
+    let mut diagnostics = resolved.diagnostics;
+    for issue in &mut diagnostics {
+        let hidden = issue.key.as_deref().is_some_and(|key| {
+            secrets.iter().any(|secret| secret == key) || (redact && is_secret_key(key))
+        });
+        if hidden {
+            issue.message = String::from(REDACTED);
+        }
+    }
+
+    return Ok(EnvExport {
+        schema_version: SCHEMA_VERSION,
+        exported_at: chrono::Local::now().to_rfc3339(),
+        vars,
+        diagnostics,
+    });
+}
+
+/// Writes every variable, with where it's set and the problems found, as JSON, YAML or TOML
+/// ### Arguments
+/// - format: the format to write
+/// - redact: if true, the values of variables holding secrets are replaced by `[redacted]`
+/// ### Returns
+/// The exported text, or an error if the variables can't be read
+pub fn export_env(format: ExportFormat, redact: bool) -> Result<String, GunmetalError> {
+    let export = gather_export(redact)?;
+    let text = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&export).map_err(|err| err.to_string()),
+        ExportFormat::Yaml => serde_yaml::to_string(&export).map_err(|err| err.to_string()),
+        ExportFormat::Toml => toml::to_string_pretty(&export).map_err(|err| err.to_string()),
+    };
+    return text.map_err(|err| {
+        GunmetalError::Other(ErrorInfo::new(export_err!(format.name())).details(err))
+    });
+}
+
+/// Exports every variable to a file, see `export_env`. Only its owner may read the file, it can hold secrets
+/// ### Arguments
+/// - path: the file to write, replaced if it exists
+/// - format (optional ExportFormat): defaults to the one matching the file's extension (.json, .yaml, .yml or .toml)
+/// - redact (optional bool): if true, the values of variables holding secrets are replaced by `[redacted]`
+/// ### Returns
+/// The path the export was saved to, or an error
+/// ### Types of Errors
+/// - WriteFailed, when the file can't be written
+/// - PermissionDenied, when the user can't write to the path
+pub fn save_export(
+    path: String,
+    format: Option<ExportFormat>,
+    redact: Option<bool>,
+) -> Result<String, GunmetalError> {
+    let format = format.unwrap_or_else(|| ExportFormat::from_path(&path));
+    let text = export_env(format, redact.unwrap_or(false))?;
+    replace_file(&path, &text, Some(0o600)).map_err(|err| {
+        GunmetalError::from_io(
+            GunmetalError::WriteFailed,
+            GunmetalError::WriteFailed,
+            ErrorInfo::new(make_file_err!(&path)).path(&path),
+            &err,
+        )
+    })?;
+    log::info!("exported the variables to {}", path);
+    return Ok(path);
+}
+
+/// Checks if a variable is a list: the "list_overrides" setting decides, otherwise only variables with several entries are
+fn is_list(settings: &Settings, key: &str, entries: &[String]) -> bool {
+    return match settings.list_overrides.get(key) {
+        Some(is_list) => *is_list,
+        None => entries.len() > 1,
+    };
+}
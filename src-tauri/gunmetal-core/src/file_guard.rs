@@ -0,0 +1,361 @@
+use fs2::FileExt;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::{self};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::consts_and_errors::*;
+
+/// What a file looked like when it was last shown to the user
+#[derive(Clone, PartialEq, Debug)]
+struct Fingerprint {
+    hash: u64,
+    modified: Option<SystemTime>,
+}
+
+/// Fingerprints of every profile read by `get_vars`, keyed by path
+static FINGERPRINTS: Mutex<Option<HashMap<String, Fingerprint>>> = Mutex::new(None);
+
+/// Records the state of a file that was just read, so later writes can tell if someone else changed it
+/// ### Arguments
+/// - path: path of the file
+/// - content: the contents that were read
+pub fn remember(path: &str, content: &str) {
+    let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    let fingerprint = Fingerprint {
+        hash: hash_content(content),
+        modified,
+    };
+    if let Ok(mut fingerprints) = FINGERPRINTS.lock() {
+        fingerprints
+            .get_or_insert_with(HashMap::new)
+            .insert(String::from(path), fingerprint);
+    }
+}
+
+/// Checks that a file still has the contents it had when it was last read
+/// ### Arguments
+/// - path: path of the file
+/// - content: the file's current contents
+/// ### Returns
+/// Nothing if the file is unchanged (or was never read), a conflict error otherwise
+pub fn check_unchanged(path: &str, content: &str) -> Result<(), GunmetalError> {
+    let fingerprints = match FINGERPRINTS.lock() {
+        Ok(fingerprints) => fingerprints,
+        Err(_) => return Ok(()),
+    };
+    let recorded = match fingerprints.as_ref().and_then(|map| map.get(path)) {
+        Some(recorded) => recorded,
+        None => return Ok(()),
+    };
+
+    // an unchanged modification time means nobody wrote to the file
+    let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    if modified.is_some() && modified == recorded.modified {
+        return Ok(());
+    }
+    // the modification time can change without the contents changing (ex: `touch`), so the hash decides
+    if recorded.hash != hash_content(content) {
+        return Err(GunmetalError::Conflict(
+            ErrorInfo::new(conflict_err!(path)).path(path),
+        ));
+    }
+    return Ok(());
+}
+
+/// A file held under an exclusive advisory lock (`flock`) until dropped. Writes replace the whole file
+/// through a temporary file, so a crash or a full disk never leaves it cut short.
+/// The lock stays on the file at the path: the new file is locked before it's renamed into place,
+/// and anyone who was waiting on the old one sees it was replaced and locks the new one instead.
+pub struct LockedFile {
+    /// path the file was opened with, used in messages
+    path: String,
+    /// the file written to, with links resolved so a linked profile stays a link
+    target: PathBuf,
+    file: fs::File,
+}
+
+impl LockedFile {
+    /// Opens a file and waits for an exclusive lock on it
+    pub fn open(path: &str) -> Result<LockedFile, GunmetalError> {
+        let open_err = |err: io::Error| {
+            GunmetalError::from_io(
+                GunmetalError::ProfileNotFound,
+                GunmetalError::ReadFailed,
+                ErrorInfo::new(profile_err!(path)).path(path),
+                &err,
+            )
+        };
+        let target = fs::canonicalize(path).map_err(open_err)?;
+        loop {
+            let file = fs::File::open(&target).map_err(open_err)?;
+            file.lock_exclusive().map_err(|err| {
+                GunmetalError::WriteFailed(ErrorInfo::new(lock_file_err!(path)).path(path).io(&err))
+            })?;
+            // replaced while waiting for the lock, so the lock is on a file nobody reads anymore
+            if is_same_file(&file, &target) {
+                return Ok(LockedFile {
+                    path: String::from(path),
+                    target,
+                    file,
+                });
+            }
+        }
+    }
+
+    /// Reads the whole file
+    pub fn read_to_string(&mut self) -> Result<String, GunmetalError> {
+        let mut content = String::new();
+        self.file
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| self.file.read_to_string(&mut content))
+            .map_err(|err| {
+                GunmetalError::ReadFailed(
+                    ErrorInfo::new(profile_err!(&self.path))
+                        .path(&self.path)
+                        .io(&err),
+                )
+            })?;
+        return Ok(content);
+    }
+
+    /// Replaces the whole contents of the file, keeping its permissions. The file is either fully
+    /// replaced or left as it was, and stays locked
+    pub fn replace(&mut self, content: &str) -> Result<(), GunmetalError> {
+        return self.replace_with_mode(content, None);
+    }
+
+    /// Same as `replace`, with the file's permissions set to `mode` (the old file's if None)
+    pub fn replace_with_mode(
+        &mut self,
+        content: &str,
+        mode: Option<u32>,
+    ) -> Result<(), GunmetalError> {
+        let target = self.target.to_string_lossy().to_string();
+        self.file = write_replacement(&target, content, mode, true)
+            .map_err(|err| write_err(&self.path, &err))?;
+        return Ok(());
+    }
+
+    /// Appends text to the end of the file, replacing it like `replace` does
+    pub fn append(&mut self, text: &str) -> Result<(), GunmetalError> {
+        let content = self.read_to_string()?;
+        return self.replace(&format!("{}{}", content, text));
+    }
+}
+
+impl Drop for LockedFile {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+/// Checks that a path still leads to an open file, and wasn't replaced by another one
+#[cfg(unix)]
+fn is_same_file(file: &fs::File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    return match (file.metadata(), fs::metadata(path)) {
+        (Ok(open), Ok(current)) => open.dev() == current.dev() && open.ino() == current.ino(),
+        _ => false,
+    };
+}
+
+/// Windows can't rename over a file that's open, so the file at the path is always the open one
+#[cfg(not(unix))]
+fn is_same_file(_file: &fs::File, _path: &Path) -> bool {
+    return true;
+}
+
+/// Writes a whole file at once: the contents go to a temporary file next to it, which then replaces the original.
+/// If anything fails partway, the original file is left as it was. The file keeps the permissions it had.
+pub fn write_atomically(path: &str, content: &str) -> Result<(), GunmetalError> {
+    return replace_file(path, content, None).map_err(|err| write_err(path, &err));
+}
+
+/// Same as `write_atomically`, with the file's permissions set to `mode` from the moment it's made,
+/// ex: 0o600 for a file only its owner may read
+pub fn write_atomically_with_mode(
+    path: &str,
+    content: &str,
+    mode: u32,
+) -> Result<(), GunmetalError> {
+    return replace_file(path, content, Some(mode)).map_err(|err| write_err(path, &err));
+}
+
+/// Writes a temporary file in the same directory as `path` and renames it over `path`.
+/// The temporary file is always a new file (`O_CREAT|O_EXCL|O_NOFOLLOW`), so a link left in its place
+/// (ex: inside a disk image) can't send the write anywhere else.
+/// ### Arguments
+/// - path: the file to replace, with links in its directory already resolved (ex: by `rooted`)
+/// - mode: permissions for the new file, the old file's (or the default ones) if None
+pub(crate) fn replace_file(path: &str, content: &str, mode: Option<u32>) -> io::Result<()> {
+    return write_replacement(path, content, mode, false).map(|_| ());
+}
+
+/// Does the work of `replace_file`
+/// ### Arguments
+/// - lock: if true, the new file is locked before it's renamed into place (see `LockedFile`)
+/// ### Returns
+/// The new file, opened for reading
+fn write_replacement(
+    path: &str,
+    content: &str,
+    mode: Option<u32>,
+    lock: bool,
+) -> io::Result<fs::File> {
+    let target = Path::new(path);
+    let dir = match target.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = target
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, path))?
+        .to_string_lossy()
+        .to_string();
+    #[cfg(unix)]
+    let mode = {
+        use std::os::unix::fs::PermissionsExt;
+        mode.or_else(|| {
+            fs::symlink_metadata(target)
+                .ok()
+                .filter(|metadata| metadata.is_file())
+                .map(|metadata| metadata.permissions().mode() & 0o7777)
+        })
+    };
+
+    let (tmp_path, mut file) = create_temporary(dir, &name, mode)?;
+    let write_result = file
+        .write_all(content.as_bytes())
+        .and_then(|_| set_mode(&file, mode))
+        .and_then(|_| file.sync_all())
+        .and_then(|_| match lock {
+            true => file.lock_exclusive(),
+            false => Ok(()),
+        })
+        .and_then(|_| fs::rename(&tmp_path, target));
+    if let Err(err) = write_result {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(err);
+    }
+    return Ok(file);
+}
+
+/// Makes a new, empty temporary file next to a file, trying other names if one is taken
+fn create_temporary(dir: &Path, name: &str, mode: Option<u32>) -> io::Result<(PathBuf, fs::File)> {
+    let mut attempt = 0;
+    loop {
+        let tmp_path = dir.join(format!(
+            ".{}.gunmetal-tmp-{}-{}",
+            name,
+            std::process::id(),
+            attempt
+        ));
+        let mut options = fs::OpenOptions::new();
+        options.read(true).write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.custom_flags(libc::O_NOFOLLOW);
+            // nothing is written before the permissions are right
+            options.mode(mode.unwrap_or(0o666));
+        }
+        match options.open(&tmp_path) {
+            Ok(file) => return Ok((tmp_path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Sets the exact permissions of an open file, whatever the umask took away when it was made
+#[cfg(unix)]
+fn set_mode(file: &fs::File, mode: Option<u32>) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    return match mode {
+        Some(mode) => file.set_permissions(fs::Permissions::from_mode(mode)),
+        None => Ok(()),
+    };
+}
+
+#[cfg(not(unix))]
+fn set_mode(_file: &fs::File, _mode: Option<u32>) -> io::Result<()> {
+    return Ok(());
+}
+
+/// Error for a failed write, which is a `PermissionDenied` if the OS refused it
+fn write_err(path: &str, err: &std::io::Error) -> GunmetalError {
+    return GunmetalError::from_io(
+        GunmetalError::WriteFailed,
+        GunmetalError::WriteFailed,
+        ErrorInfo::new(write_file_err!(path)).path(path),
+        err,
+    );
+}
+
+fn hash_content(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    return hasher.finish();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes an empty directory for a test, named after it so tests running at the same time don't share one
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gunmetal-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        return dir;
+    }
+
+    #[test]
+    fn replaces_and_appends_under_the_lock() {
+        let dir = test_dir("locked-replace");
+        let path = dir.join("profile").to_string_lossy().to_string();
+        fs::write(&path, "export A=1\n").unwrap();
+
+        let mut file = LockedFile::open(&path).unwrap();
+        file.replace("export B=2\n").unwrap();
+        file.append("export C=3\n").unwrap();
+        assert_eq!(file.read_to_string().unwrap(), "export B=2\nexport C=3\n");
+        // the file at the path is the one still locked, so nobody else can take it in between
+        assert!(is_same_file(&file.file, Path::new(&path)));
+        drop(file);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "export B=2\nexport C=3\n"
+        );
+        // no temporary files are left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn writes_through_a_linked_profile() {
+        let dir = test_dir("locked-link");
+        let real = dir.join("dotfiles-bashrc");
+        let link = dir.join(".bashrc");
+        fs::write(&real, "export A=1\n").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let mut file = LockedFile::open(&link.to_string_lossy()).unwrap();
+        file.replace("export A=2\n").unwrap();
+        drop(file);
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&real).unwrap(), "export A=2\n");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,200 @@
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use crate::consts_and_errors::*;
+use crate::fs_root::rooted;
+use crate::provenance::{resolve, ResolvedVar};
+use crate::settings::load_settings;
+use crate::settings_utils::get_config_path;
+
+/// How long to wait for more changes before reloading, since editors often save a file in several steps
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// The entries added to and removed from one variable
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct VarDiff {
+    pub key: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// What `start_watching` reports after the variables change
+#[derive(Serialize, Clone, Debug)]
+pub struct VarsChanged {
+    /// every variable with where it's set, like `resolve` returns: secrets are masked and flagged
+    pub vars: Vec<ResolvedVar>,
+    /// only the variables that changed
+    pub diff: Vec<VarDiff>,
+}
+
+/// Starts a thread that watches the settings file and every profile in the settings.
+/// Whenever one of them changes (from Gunmetal or any other program), the variables are read again
+/// and passed to `on_change` if any of them changed.
+pub fn start_watching(on_change: impl FnMut(VarsChanged) + Send + 'static) {
+    thread::spawn(move || {
+        if let Err(err) = watch_files(on_change) {
+            log::error!("{}", construct_err_msg!(watch_failed_err!(), err));
+        }
+    });
+}
+
+fn watch_files(mut on_change: impl FnMut(VarsChanged)) -> Result<(), String> {
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(|err| err.to_string())?;
+    let mut watched_dirs: Vec<PathBuf> = Vec::new();
+    let mut files = files_to_watch();
+    watch_dirs(&mut watcher, &mut watched_dirs, &files);
+
+    let mut last_vars = resolve()
+        .map(|env| entries_by_key(&env.vars))
+        .unwrap_or_default();
+    loop {
+        let events = match next_events(&receiver) {
+            Some(events) => events,
+            // the watcher was dropped, nothing more will come
+            None => return Ok(()),
+        };
+        let touched = events
+            .iter()
+            .flat_map(|event| event.paths.iter())
+            .any(|path| files.contains(path));
+        if !touched {
+            continue;
+        }
+
+        // the settings might have changed which profiles are used
+        files = files_to_watch();
+        watch_dirs(&mut watcher, &mut watched_dirs, &files);
+
+        // the same model the rest of the app reads, so secrets never leave the vault
+        let env = match resolve() {
+            Ok(env) => env,
+            // ex: a profile that's being replaced, the next event will have the finished file
+            Err(err) => {
+                log::warn!("{}", err);
+                continue;
+            }
+        };
+        let vars = entries_by_key(&env.vars);
+        let diff = diff_vars(&last_vars, &vars);
+        if diff.is_empty() {
+            continue;
+        }
+        let payload = VarsChanged {
+            vars: env.vars,
+            diff,
+        };
+        log::info!("{} variable(s) changed on disk", payload.diff.len());
+        on_change(payload);
+        last_vars = vars;
+    }
+}
+
+/// Gets the entries of each variable, to compare them
+fn entries_by_key(vars: &[ResolvedVar]) -> HashMap<String, Vec<String>> {
+    return vars
+        .iter()
+        .map(|var| (var.key.to_owned(), var.entries.clone()))
+        .collect();
+}
+
+/// Waits for a change, then collects any others that follow right after it
+fn next_events(receiver: &Receiver<notify::Result<Event>>) -> Option<Vec<Event>> {
+    let mut events: Vec<Event> = Vec::new();
+    events.extend(receiver.recv().ok()?.ok());
+    while let Ok(event) = receiver.recv_timeout(SETTLE_TIME) {
+        events.extend(event.ok());
+    }
+    return Some(events);
+}
+
+/// Lists the settings file and every profile in the settings
+fn files_to_watch() -> Vec<PathBuf> {
+    let settings_path = match get_config_path() {
+        Ok(config_path) => PathBuf::from(config_path).join("settings.json"),
+        Err(_) => return Vec::new(),
+    };
+    let mut files = vec![settings_path.clone()];
+    if let Ok(settings) = load_settings(&settings_path.to_string_lossy()) {
+        for profile in settings.profiles.iter().chain([&settings.shell_profile]) {
+            if !Path::new(profile).is_absolute() {
+                continue;
+            }
+            // the profiles are under the root, if one is set
+            let profile = match rooted(profile) {
+                Ok(profile) => PathBuf::from(profile),
+                Err(_) => continue,
+            };
+            if !files.contains(&profile) {
+                files.push(profile);
+            }
+        }
+    }
+    return files;
+}
+
+/// Watches the directories the files are in, and stops watching ones that are no longer needed.
+/// Directories are watched instead of the files, since many editors save by replacing the file with a new one.
+fn watch_dirs(
+    watcher: &mut RecommendedWatcher,
+    watched_dirs: &mut Vec<PathBuf>,
+    files: &[PathBuf],
+) {
+    let dirs: Vec<PathBuf> = files
+        .iter()
+        .filter_map(|file| file.parent().map(PathBuf::from))
+        .collect::<BTreeSet<PathBuf>>()
+        .into_iter()
+        .collect();
+
+    for dir in watched_dirs.iter().filter(|dir| !dirs.contains(dir)) {
+        let _ = watcher.unwatch(dir);
+    }
+    // a directory that doesn't exist yet (ex: no settings saved) is tried again the next time the files are listed
+    watched_dirs.retain(|dir| dirs.contains(dir));
+    for dir in dirs {
+        if !watched_dirs.contains(&dir) && watcher.watch(&dir, RecursiveMode::NonRecursive).is_ok()
+        {
+            watched_dirs.push(dir);
+        }
+    }
+}
+
+/// Compares two sets of variables
+/// ### Returns
+/// The entries added and removed for each variable that changed, sorted by variable name
+pub fn diff_vars(
+    old: &HashMap<String, Vec<String>>,
+    new: &HashMap<String, Vec<String>>,
+) -> Vec<VarDiff> {
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    let empty: Vec<String> = Vec::new();
+
+    let mut diff: Vec<VarDiff> = Vec::new();
+    for key in keys {
+        let old_entries = old.get(key).unwrap_or(&empty);
+        let new_entries = new.get(key).unwrap_or(&empty);
+        if old_entries == new_entries {
+            continue;
+        }
+        diff.push(VarDiff {
+            key: key.to_owned(),
+            added: new_entries
+                .iter()
+                .filter(|entry| !old_entries.contains(entry))
+                .cloned()
+                .collect(),
+            removed: old_entries
+                .iter()
+                .filter(|entry| !new_entries.contains(entry))
+                .cloned()
+                .collect(),
+        });
+    }
+    return diff;
+}
@@ -0,0 +1,269 @@
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::consts_and_errors::*;
+use crate::settings::load_settings;
+use crate::settings_utils::get_config_path;
+
+/// Symbolic links followed while resolving one path before giving up, the same limit as Linux
+const MAX_SYMLINKS: usize = 40;
+
+/// Root given for this run only (ex: `gunmetal --root`), takes the place of the "root" setting
+static ROOT_OVERRIDE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Sets the root for the rest of this run, over the "root" setting. None goes back to the setting.
+pub fn set_root_override(root: Option<String>) {
+    *ROOT_OVERRIDE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = root;
+}
+
+/// Finds the directory that profiles, home directories and files under /etc are read from and written to:
+/// the root set for this run, else the "root" setting.
+/// Gunmetal's own settings, logs and backups stay where they are.
+/// ### Returns
+/// The root, None for the real root, or an error if the root isn't a directory
+pub fn root() -> Result<Option<PathBuf>, GunmetalError> {
+    let override_root = ROOT_OVERRIDE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone();
+    let root = match override_root {
+        Some(root) => root,
+        // without settings there's no root to use
+        None => get_config_path()
+            .and_then(|path| load_settings(&format!("{}/settings.json", path)))
+            .map(|settings| settings.root)
+            .unwrap_or_default(),
+    };
+    return open_root(&root);
+}
+
+/// Checks a root directory and gets its full path
+/// ### Returns
+/// The root with symbolic links resolved, None for "" or "/", or an error if it isn't a directory
+pub fn open_root(root: &str) -> Result<Option<PathBuf>, GunmetalError> {
+    if root.is_empty() {
+        return Ok(None);
+    }
+    // a root that can't be opened must never fall back to the real one
+    let root_path = fs::canonicalize(root)
+        .ok()
+        .filter(|path| path.is_dir())
+        .ok_or_else(|| {
+            GunmetalError::InvalidInput(ErrorInfo::new(root_not_dir_err!(root)).path(root))
+        })?;
+    if root_path == Path::new("/") {
+        return Ok(None);
+    }
+    return Ok(Some(root_path));
+}
+
+/// Finds where a path is on this machine, under the root if one is set
+/// ### Arguments
+/// - path: the path as seen from inside the root, ex: "/etc/environment"
+/// ### Returns
+/// The path to read or write, or an error if the root isn't usable
+pub fn rooted(path: &str) -> Result<String, GunmetalError> {
+    return match root()? {
+        Some(root) => resolve_in_root(&root, path),
+        None => Ok(String::from(path)),
+    };
+}
+
+/// Finds how a path on this machine is seen from inside the root, the opposite of `rooted`
+/// ### Arguments
+/// - path: a path under the root, ex: "/mnt/image/etc/environment"
+/// ### Returns
+/// The path inside the root, ex: "/etc/environment", or an error if the root isn't usable
+pub fn unrooted(path: &Path) -> Result<PathBuf, GunmetalError> {
+    return match root()? {
+        Some(root) => match path.strip_prefix(&root) {
+            Ok(rest) => Ok(Path::new("/").join(rest)),
+            Err(_) => Ok(path.to_path_buf()),
+        },
+        None => Ok(path.to_path_buf()),
+    };
+}
+
+/// Resolves a path the way it would be inside a chroot: `..` stops at the root, and symbolic links,
+/// even absolute ones, are followed inside the root. So no path, however it's written, ends up outside the root.
+/// ### Arguments
+/// - root: the root directory, with symbolic links already resolved (see `open_root`)
+/// - path: the path as seen from inside the root. Relative paths start at the root too
+/// ### Returns
+/// The path on this machine, or an error if there are too many links to follow
+pub fn resolve_in_root(root: &Path, path: &str) -> Result<String, GunmetalError> {
+    // names of the directories resolved so far, below the root
+    let mut resolved: Vec<OsString> = Vec::new();
+    let mut pending: VecDeque<OsString> = components(Path::new(path));
+    let mut links = 0;
+
+    while let Some(name) = pending.pop_front() {
+        if name == ".." {
+            resolved.pop();
+            continue;
+        }
+        resolved.push(name);
+        let current: PathBuf = resolved
+            .iter()
+            .fold(root.to_path_buf(), |dir, name| dir.join(name));
+        let target = match fs::symlink_metadata(&current) {
+            Ok(metadata) if metadata.file_type().is_symlink() => fs::read_link(&current),
+            // missing parts (ex: a file about to be made) can't be links
+            _ => continue,
+        };
+        let target = target.map_err(|err| {
+            let current = current.to_string_lossy().to_string();
+            GunmetalError::from_io(
+                GunmetalError::ReadFailed,
+                GunmetalError::ReadFailed,
+                ErrorInfo::new(profile_err!(&current)).path(&current),
+                &err,
+            )
+        })?;
+
+        links += 1;
+        if links > MAX_SYMLINKS {
+            return Err(GunmetalError::InvalidInput(
+                ErrorInfo::new(root_symlink_loop_err!(path, root.to_string_lossy())).path(path),
+            ));
+        }
+        // the link is replaced by its target: an absolute target starts over at the root
+        resolved.pop();
+        if target.is_absolute() {
+            resolved.clear();
+        }
+        for name in components(&target).into_iter().rev() {
+            pending.push_front(name);
+        }
+    }
+
+    let full_path = resolved
+        .iter()
+        .fold(root.to_path_buf(), |dir, name| dir.join(name));
+    return Ok(full_path.to_string_lossy().to_string());
+}
+
+/// Splits a path into the names in it, keeping `..` but dropping the root and `.`
+fn components(path: &Path) -> VecDeque<OsString> {
+    return path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_os_string()),
+            Component::ParentDir => Some(OsString::from("..")),
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => None,
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gunmetal-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        return fs::canonicalize(dir).unwrap();
+    }
+
+    fn under(root: &Path, path: &str) -> String {
+        return root.join(path).to_string_lossy().to_string();
+    }
+
+    #[test]
+    fn keeps_dot_dot_inside_the_root() {
+        let root = test_dir("root-dotdot");
+        assert_eq!(
+            resolve_in_root(&root, "/../../etc/./environment").unwrap(),
+            under(&root, "etc/environment")
+        );
+        assert_eq!(
+            resolve_in_root(&root, "home/user/../../../.profile").unwrap(),
+            under(&root, ".profile")
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_absolute_links_inside_the_root() {
+        use std::os::unix::fs::symlink;
+        let root = test_dir("root-absolute");
+        fs::create_dir_all(root.join("etc")).unwrap();
+        symlink("/etc/environment.real", root.join("etc/environment")).unwrap();
+        symlink("/", root.join("etc/escape")).unwrap();
+
+        assert_eq!(
+            resolve_in_root(&root, "/etc/environment").unwrap(),
+            under(&root, "etc/environment.real")
+        );
+        assert_eq!(
+            resolve_in_root(&root, "/etc/escape/../etc/passwd").unwrap(),
+            under(&root, "etc/passwd")
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_a_chain_of_relative_links() {
+        use std::os::unix::fs::symlink;
+        let root = test_dir("root-chain");
+        fs::create_dir_all(root.join("home/user/dotfiles")).unwrap();
+        // .profile -> dotfiles/profile -> ../../../../shared/profile, which climbs past the root
+        symlink("dotfiles/profile", root.join("home/user/.profile")).unwrap();
+        symlink(
+            "../../../../shared/profile",
+            root.join("home/user/dotfiles/profile"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            resolve_in_root(&root, "/home/user/.profile").unwrap(),
+            under(&root, "shared/profile")
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stops_at_a_link_loop() {
+        use std::os::unix::fs::symlink;
+        let root = test_dir("root-loop");
+        symlink("b", root.join("a")).unwrap();
+        symlink("/a", root.join("b")).unwrap();
+
+        let err = resolve_in_root(&root, "/a/file").unwrap_err();
+        assert!(matches!(err, GunmetalError::InvalidInput(_)));
+        assert_eq!(
+            err.to_string(),
+            root_symlink_loop_err!("/a/file", root.to_string_lossy())
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn refuses_a_root_that_is_not_a_directory() {
+        let dir = test_dir("root-file");
+        let file = under(&dir, "file");
+        fs::write(&file, "").unwrap();
+
+        assert!(matches!(
+            open_root(&file),
+            Err(GunmetalError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            open_root(&under(&dir, "missing")),
+            Err(GunmetalError::InvalidInput(_))
+        ));
+        assert_eq!(open_root(&under(&dir, "")).unwrap(), Some(dir.clone()));
+        assert_eq!(open_root("/").unwrap(), None);
+        assert_eq!(open_root("").unwrap(), None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
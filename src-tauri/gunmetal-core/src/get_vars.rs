@@ -0,0 +1,27 @@
+use crate::consts_and_errors::*;
+use crate::env_backend::{backend, Target};
+use crate::secrets::mask_vars;
+use std::collections::HashMap;
+
+/// Gets all environment variables and their entries.
+/// On macOS and Linux the shell profile's current state is remembered, so a later write can tell if the file was changed in the meantime.
+/// Secret variables have their value masked, see `reveal_secret`.
+pub fn get_vars() -> Result<HashMap<String, Vec<String>>, GunmetalError> {
+    let mut vars = backend().read()?;
+    mask_vars(&mut vars);
+    return Ok(vars);
+}
+
+/// Lists the files (or registry keys) variables are read from and written to
+pub fn list_targets() -> Result<Vec<Target>, GunmetalError> {
+    return backend().list_targets();
+}
+
+/// Gets the entries of one variable
+/// ### Returns
+/// The variable's entries (masked if it's secret), or an error if it isn't set
+pub fn get_var(key: &str) -> Result<Vec<String>, GunmetalError> {
+    return get_vars()?
+        .remove(key)
+        .ok_or_else(|| GunmetalError::ValueNotFound(ErrorInfo::new(var_not_set_err!(key))));
+}
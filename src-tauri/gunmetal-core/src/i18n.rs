@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Languages shipped with the app, as (locale, catalog). English is first, and is used for anything missing from the others.
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+    ("ja", include_str!("../locales/ja.ftl")),
+];
+
+const FALLBACK_LOCALE: &str = "en";
+
+/// Locale chosen in the settings, empty to use the system's
+static LOCALE: Mutex<String> = Mutex::new(String::new());
+
+/// Catalogs parsed the first time a message is needed
+static MESSAGES: Mutex<Option<HashMap<&'static str, HashMap<String, String>>>> = Mutex::new(None);
+
+/// Sets the language of every message after this
+/// ### Arguments
+/// - locale: a locale like "de" or "ja_JP.UTF-8", or "" to follow the system's language
+pub fn set_locale(locale: &str) {
+    *LOCALE.lock().unwrap_or_else(|err| err.into_inner()) = String::from(locale);
+}
+
+/// Lists the locales there are messages for
+pub fn available_locales() -> Vec<&'static str> {
+    return CATALOGS.iter().map(|(locale, _)| *locale).collect();
+}
+
+/// Finds the language messages should be shown in: the one from the settings,
+/// else the system's from $LC_ALL, $LC_MESSAGES or $LANG
+pub fn current_locale() -> String {
+    let chosen = LOCALE
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .to_owned();
+    if !chosen.is_empty() {
+        return language(&chosen);
+    }
+    for var in ["LC_ALL", "LC_MESSAGES", "LANG"] {
+        match std::env::var(var) {
+            Ok(locale) if !locale.is_empty() => return language(&locale),
+            _ => continue,
+        }
+    }
+    return String::from(FALLBACK_LOCALE);
+}
+
+/// Turns a locale like "de_DE.UTF-8" into its language, "de"
+fn language(locale: &str) -> String {
+    return locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+}
+
+/// Looks up a message in the current language and fills in its arguments
+/// ### Arguments
+/// - id: name of the message in the catalogs, ex: "profile-err"
+/// - args: values for the `{ $name }` placeholders in the message
+/// ### Returns
+/// The message, in English if the current language doesn't have it, or the id itself if no language does
+pub fn message(id: &str, args: &[(&str, String)]) -> String {
+    let locale = current_locale();
+    let mut messages = MESSAGES.lock().unwrap_or_else(|err| err.into_inner());
+    let catalogs = messages.get_or_insert_with(|| {
+        CATALOGS
+            .iter()
+            .map(|(locale, source)| (*locale, parse_catalog(source)))
+            .collect()
+    });
+
+    let template = [locale.as_str(), FALLBACK_LOCALE]
+        .iter()
+        .find_map(|locale| catalogs.get(locale).and_then(|catalog| catalog.get(id)));
+    let mut text = match template {
+        Some(template) => template.to_owned(),
+        None => return String::from(id),
+    };
+    // placeholders without a value are left as they are, so a missing argument is easy to spot
+    for (name, value) in args {
+        text = text.replace(&format!("{{ ${} }}", name), value);
+    }
+    return text;
+}
+
+/// Reads a catalog. Each message is `id = text` at the start of a line, and continues on the indented lines after it.
+/// Lines starting with '#' are comments.
+fn parse_catalog(source: &str) -> HashMap<String, String> {
+    let mut catalog: HashMap<String, String> = HashMap::new();
+    let mut current: Option<(String, Vec<String>)> = None;
+
+    for line in source.lines() {
+        let continues = line.starts_with(' ') || line.trim().is_empty();
+        if continues {
+            if let Some((_, lines)) = current.as_mut() {
+                lines.push(String::from(line.trim()));
+            }
+            continue;
+        }
+
+        if let Some((id, lines)) = current.take() {
+            catalog.insert(id, lines.join("\n").trim().to_owned());
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        if let Some((id, text)) = line.split_once('=') {
+            current = Some((id.trim().to_owned(), vec![String::from(text.trim())]));
+        }
+    }
+    if let Some((id, lines)) = current {
+        catalog.insert(id, lines.join("\n").trim().to_owned());
+    }
+    return catalog;
+}
@@ -0,0 +1,49 @@
+//! Everything Gunmetal does, without the GUI: reading and writing variables in shell profiles,
+//! system files and the registry, the settings, and what goes in logs and bug reports.
+//!
+//! The Tauri app and the privileged helper are thin wrappers over this crate, and anything else
+//! (other binaries, integration tests) can link it the same way.
+
+// explicit `return` is the style of this codebase
+#![allow(clippy::needless_return)]
+
+mod consts_and_errors;
+mod memory_backend;
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+mod unix_backend;
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+mod windows_backend;
+
+pub mod add_vars;
+pub mod backups;
+pub mod changes;
+pub mod diagnostics;
+pub mod diff_utils;
+pub mod dotenv;
+pub mod env_backend;
+pub mod env_export;
+pub mod file_guard;
+pub mod file_watcher;
+pub mod fs_root;
+pub mod get_vars;
+pub mod i18n;
+pub mod logging;
+pub mod manage_settings;
+pub mod path_check;
+pub mod profile_edit;
+pub mod project_env;
+pub mod provenance;
+pub mod redact;
+pub mod remove_vars;
+pub mod search;
+pub mod secrets;
+pub mod settings;
+pub mod settings_utils;
+pub mod shell_detect;
+pub mod shell_quote;
+pub mod snapshots;
+pub mod system_scope;
+pub mod target_format;
+pub mod validation;
+
+pub use consts_and_errors::{ErrorInfo, GunmetalError};
@@ -0,0 +1,146 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use crate::consts_and_errors::*;
+use crate::redact::redact_text;
+use crate::settings_utils::get_config_path;
+
+/// Name of the log being written, older logs get ".1", ".2", ... added to it
+pub const LOG_FILE_NAME: &str = "gunmetal.log";
+
+/// Size a log can grow to before it's rotated
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+/// How many rotated logs to keep besides the current one
+const KEPT_LOGS: usize = 3;
+
+/// Level used when $GUNMETAL_LOG isn't set
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+/// Writes each record as one line of JSON to the log file in the config directory
+struct FileLogger {
+    dir: PathBuf,
+    file: Mutex<Option<File>>,
+}
+
+/// Finds the directory logs are kept in, `logs` in the config directory
+pub fn logs_dir() -> Result<PathBuf, GunmetalError> {
+    return Ok(PathBuf::from(get_config_path()?).join("logs"));
+}
+
+/// Starts logging to the log file. The level comes from $GUNMETAL_LOG ("error", "warn", "info", "debug" or "trace"), info by default.
+/// ### Returns
+/// Nothing, or an error if the logs directory can't be made (the app still runs, without logs)
+pub fn init_logging() -> Result<(), GunmetalError> {
+    let dir = logs_dir()?;
+    fs::create_dir_all(&dir).map_err(|err| {
+        let dir = dir.to_string_lossy().to_string();
+        GunmetalError::from_io(
+            GunmetalError::WriteFailed,
+            GunmetalError::WriteFailed,
+            ErrorInfo::new(mkdir_err!(&dir)).path(dir),
+            &err,
+        )
+    })?;
+    let level = std::env::var("GUNMETAL_LOG")
+        .ok()
+        .and_then(|level| LevelFilter::from_str(&level).ok())
+        .unwrap_or(DEFAULT_LEVEL);
+
+    let logger = FileLogger {
+        dir,
+        file: Mutex::new(None),
+    };
+    // only fails if a logger was already set, which can be kept
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(level);
+    }
+    return Ok(());
+}
+
+/// Lists the log files, newest first
+pub fn log_files() -> Vec<PathBuf> {
+    let dir = match logs_dir() {
+        Ok(dir) => dir,
+        Err(_) => return Vec::new(),
+    };
+    return (0..=KEPT_LOGS)
+        .map(|number| rotated_path(&dir, number))
+        .filter(|path| path.is_file())
+        .collect();
+}
+
+/// Path of a log, 0 being the current one
+fn rotated_path(dir: &Path, number: usize) -> PathBuf {
+    if number == 0 {
+        return dir.join(LOG_FILE_NAME);
+    }
+    return dir.join(format!("{}.{}", LOG_FILE_NAME, number));
+}
+
+impl FileLogger {
+    /// Opens the current log, rotating it first if it's full
+    fn open(&self) -> std::io::Result<File> {
+        let current = rotated_path(&self.dir, 0);
+        let full = fs::metadata(&current)
+            .map(|metadata| metadata.len() >= MAX_LOG_SIZE)
+            .unwrap_or(false);
+        if full {
+            // the oldest log is replaced by the one before it
+            for number in (1..=KEPT_LOGS).rev() {
+                let older = rotated_path(&self.dir, number - 1);
+                if older.exists() {
+                    fs::rename(&older, rotated_path(&self.dir, number))?;
+                }
+            }
+        }
+        return OpenOptions::new().create(true).append(true).open(&current);
+    }
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        return metadata.level() <= log::max_level();
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        // values of variables like API_TOKEN=... never make it into the file
+        let line = serde_json::json!({
+            "time": chrono::Local::now().to_rfc3339(),
+            "level": record.level().as_str(),
+            "target": record.target(),
+            "message": redact_text(&record.args().to_string()),
+        });
+
+        let mut file = self.file.lock().unwrap_or_else(|err| err.into_inner());
+        let full = file
+            .as_ref()
+            .and_then(|file| file.metadata().ok())
+            .map(|metadata| metadata.len() >= MAX_LOG_SIZE)
+            .unwrap_or(true);
+        if full {
+            *file = self.open().ok();
+        }
+        if let Some(file) = file.as_mut() {
+            let _ = writeln!(file, "{}", line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = self
+            .file
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .as_mut()
+        {
+            let _ = file.flush();
+        }
+    }
+}
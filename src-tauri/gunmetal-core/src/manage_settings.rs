@@ -0,0 +1,71 @@
+use std::fs::{self};
+use std::path::Path;
+
+use crate::consts_and_errors::*;
+use crate::i18n::set_locale;
+use crate::settings::{
+    load_settings, save_settings, validate_settings, Settings, SETTINGS_VERSION,
+};
+use crate::settings_utils::get_config_path;
+use crate::shell_detect::detect_settings;
+
+/// Gets the current settings, so the settings page can show them.
+/// If there is no settings file yet, settings detected from the user's shells are returned (nothing is written until the user saves).
+/// ### Types of Errors
+/// - SettingsReadError, when settings.json exists but can't be read
+/// - JSONParseError, when settings.json isn't valid JSON
+pub fn get_settings() -> Result<Settings, GunmetalError> {
+    let settings_path = format!("{}/settings.json", get_config_path()?);
+    if !Path::new(&settings_path).exists() {
+        return Ok(detect_settings());
+    }
+    return load_settings(&settings_path);
+}
+
+/// Saves new settings from the settings page.
+/// ### Arguments
+/// - settings: every setting, missing ones are set to their defaults
+/// ### Returns
+/// The settings as they were saved, or an error message
+/// ### Types of Errors
+/// - InvalidSettingError, when a setting can't be used (ex: the shell profile doesn't exist or isn't readable)
+/// - MakeDirError, when the settings directory can't be made
+/// - WriteToFileError, when settings.json can't be written (the old settings are kept)
+pub fn update_settings(mut settings: Settings) -> Result<Settings, GunmetalError> {
+    validate_settings(&settings)?;
+    settings.version = SETTINGS_VERSION;
+    // the active profile is always one of the user's profiles
+    if !settings.shell_profile.is_empty() && !settings.profiles.contains(&settings.shell_profile) {
+        settings.profiles.push(settings.shell_profile.to_owned());
+    }
+
+    save_to_config_dir(&settings)?;
+    set_locale(&settings.locale);
+    log::info!("settings saved");
+    return Ok(settings);
+}
+
+/// Puts every setting back to its default
+/// ### Returns
+/// The default settings, or an error message if they couldn't be saved
+pub fn reset_settings() -> Result<Settings, GunmetalError> {
+    let settings = Settings::default();
+    save_to_config_dir(&settings)?;
+    set_locale(&settings.locale);
+    log::info!("settings reset to their defaults");
+    return Ok(settings);
+}
+
+/// Saves settings.json in the config directory, making the directory if needed
+fn save_to_config_dir(settings: &Settings) -> Result<(), GunmetalError> {
+    let config_path = get_config_path()?;
+    fs::create_dir_all(&config_path).map_err(|err| {
+        GunmetalError::from_io(
+            GunmetalError::WriteFailed,
+            GunmetalError::WriteFailed,
+            ErrorInfo::new(mkdir_err!(&config_path)).path(&config_path),
+            &err,
+        )
+    })?;
+    return save_settings(&format!("{}/settings.json", config_path), settings);
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::consts_and_errors::*;
+use crate::diff_utils::unified_diff;
+use crate::env_backend::{EnvBackend, Position, Target};
+use crate::shell_quote::ShellDialect;
+use crate::system_scope::Scope;
+use crate::target_format::TargetFormat;
+use crate::validation::validate;
+
+/// Backend that keeps variables in memory and never touches the system.
+/// It starts out with a copy of the process environment, and follows the same rules as a POSIX shell profile.
+pub struct MemoryBackend {
+    vars: Mutex<Option<HashMap<String, Vec<String>>>>,
+}
+
+impl MemoryBackend {
+    pub const fn new() -> MemoryBackend {
+        return MemoryBackend {
+            vars: Mutex::new(None),
+        };
+    }
+
+    /// Runs a function on the variables, copying the process environment in on first use
+    fn with_vars<T>(&self, action: impl FnOnce(&mut HashMap<String, Vec<String>>) -> T) -> T {
+        // a panic while holding the lock can't leave the map half-updated, so a poisoned lock is still usable
+        let mut vars = self
+            .vars
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let vars = vars.get_or_insert_with(|| {
+            std::env::vars()
+                .map(|(key, vals)| (key, vals.split(':').map(str::to_string).collect()))
+                .collect()
+        });
+        return action(vars);
+    }
+}
+
+impl EnvBackend for MemoryBackend {
+    fn read(&self) -> Result<HashMap<String, Vec<String>>, GunmetalError> {
+        return Ok(self.with_vars(|vars| vars.clone()));
+    }
+
+    fn write(
+        &self,
+        key: &str,
+        value: &str,
+        position: Position,
+        dry_run: bool,
+    ) -> Result<String, GunmetalError> {
+        validate(key, value, TargetFormat::Shell(ShellDialect::Posix))?;
+
+        return self.with_vars(|vars| {
+            // nothing is inserted until the write happens, so a dry run leaves no empty variable behind
+            let entries = vars.get(key).cloned().unwrap_or_default();
+            if entries.iter().any(|entry| entry == value) {
+                return Ok(match dry_run {
+                    true => String::new(),
+                    false => var_added_already!(),
+                });
+            }
+
+            let mut updated = entries.clone();
+            match position {
+                Position::Prepend => updated.insert(0, String::from(value)),
+                Position::Append => updated.push(String::from(value)),
+            }
+            if dry_run {
+                return Ok(unified_diff(
+                    &format!("memory:{}", key),
+                    &entries.join("\n"),
+                    &updated.join("\n"),
+                ));
+            }
+            vars.insert(String::from(key), updated);
+            return Ok(add_var_success!());
+        });
+    }
+
+    fn remove(&self, key: &str, value: &str, dry_run: bool) -> Result<String, GunmetalError> {
+        return self.with_vars(|vars| {
+            let entries = match vars.get_mut(key) {
+                Some(entries) if entries.iter().any(|entry| entry == value) => entries,
+                _ => {
+                    return Err(GunmetalError::ValueNotFound(
+                        ErrorInfo::new(value_not_found_err!(value, key, "memory")).path("memory"),
+                    ))
+                }
+            };
+
+            let updated: Vec<String> = entries
+                .iter()
+                .filter(|entry| entry.as_str() != value)
+                .cloned()
+                .collect();
+            if dry_run {
+                return Ok(unified_diff(
+                    &format!("memory:{}", key),
+                    &entries.join("\n"),
+                    &updated.join("\n"),
+                ));
+            }
+            *entries = updated;
+            if entries.is_empty() {
+                vars.remove(key);
+            }
+            return Ok(remove_var_success!());
+        });
+    }
+
+    fn list_targets(&self) -> Result<Vec<Target>, GunmetalError> {
+        return Ok(vec![Target {
+            location: String::from("memory"),
+            format: String::from("in-memory variables"),
+            scope: Scope::User,
+        }]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_values_at_either_end() {
+        let backend = MemoryBackend::new();
+        backend
+            .write("GUNMETAL_TEST_ADD", "b", Position::Prepend, false)
+            .unwrap();
+        backend
+            .write("GUNMETAL_TEST_ADD", "a", Position::Prepend, false)
+            .unwrap();
+        backend
+            .write("GUNMETAL_TEST_ADD", "c", Position::Append, false)
+            .unwrap();
+        assert_eq!(
+            backend.read().unwrap().get("GUNMETAL_TEST_ADD"),
+            Some(&vec![
+                String::from("a"),
+                String::from("b"),
+                String::from("c")
+            ])
+        );
+    }
+
+    #[test]
+    fn removes_the_variable_with_its_last_value() {
+        let backend = MemoryBackend::new();
+        backend
+            .write("GUNMETAL_TEST_REMOVE", "a", Position::Prepend, false)
+            .unwrap();
+        backend.remove("GUNMETAL_TEST_REMOVE", "a", false).unwrap();
+        assert!(!backend.read().unwrap().contains_key("GUNMETAL_TEST_REMOVE"));
+        assert!(backend.remove("GUNMETAL_TEST_REMOVE", "a", false).is_err());
+    }
+
+    #[test]
+    fn changes_nothing_on_a_dry_run() {
+        let backend = MemoryBackend::new();
+        let diff = backend
+            .write("GUNMETAL_TEST_DRY", "a", Position::Prepend, true)
+            .unwrap();
+        assert!(diff.contains("+a"), "{}", diff);
+        assert!(!backend.read().unwrap().contains_key("GUNMETAL_TEST_DRY"));
+    }
+}
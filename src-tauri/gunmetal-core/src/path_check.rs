@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::consts_and_errors::*;
+use crate::env_backend::backend;
+use crate::fs_root::{resolve_in_root, root};
+
+/// What can be wrong with an entry of PATH
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PathProblem {
+    /// an empty entry, which most shells treat as the current directory
+    Empty,
+    /// a relative directory, which depends on where a command is run from
+    Relative,
+    /// the directory doesn't exist
+    Missing,
+    /// the entry is a file, not a directory
+    NotADirectory,
+    /// the same directory is earlier in PATH, so this entry is never used
+    Duplicate,
+}
+
+/// One problem found in PATH
+#[derive(Serialize, Clone, Debug)]
+pub struct PathIssue {
+    /// position of the entry in PATH, starting at 0
+    pub index: usize,
+    pub entry: String,
+    pub problem: PathProblem,
+    /// the problem explained for the user
+    pub message: String,
+}
+
+/// Checks every entry of PATH as the shell profile sets it
+/// ### Returns
+/// The problems found, in the order of the entries (empty if PATH is fine), or an error if the variables can't be read
+pub fn check_path() -> Result<Vec<PathIssue>, GunmetalError> {
+    let vars = backend().read()?;
+    let entries = vars.get("PATH").cloned().unwrap_or_default();
+    return Ok(check_path_entries(&entries));
+}
+
+/// Checks a list of PATH entries for directories that are missing, relative or repeated.
+/// Under a root, the directories are looked for in the root.
+pub fn check_path_entries(entries: &[String]) -> Vec<PathIssue> {
+    let root = root().ok().flatten();
+    let mut issues: Vec<PathIssue> = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let issue = |problem: PathProblem, message: String| PathIssue {
+            index,
+            entry: entry.to_owned(),
+            problem,
+            message,
+        };
+        let path = Path::new(entry);
+        let found = on_disk(root.as_deref(), entry);
+
+        if entry.is_empty() {
+            issues.push(issue(PathProblem::Empty, tr!("path-empty")));
+        } else if let Some(first) = entries[..index].iter().position(|earlier| earlier == entry) {
+            issues.push(issue(
+                PathProblem::Duplicate,
+                tr!("path-duplicate", entry = entry, first = first + 1),
+            ));
+        } else if !path.is_absolute() {
+            issues.push(issue(
+                PathProblem::Relative,
+                tr!("path-relative", entry = entry),
+            ));
+        } else if !found.as_deref().is_some_and(Path::exists) {
+            issues.push(issue(
+                PathProblem::Missing,
+                tr!("path-missing", entry = entry),
+            ));
+        } else if !found.as_deref().is_some_and(Path::is_dir) {
+            issues.push(issue(
+                PathProblem::NotADirectory,
+                tr!("path-not-dir", entry = entry),
+            ));
+        }
+    }
+    return issues;
+}
+
+/// Finds a command in PATH as the shell profile sets it, like `which -a`
+/// ### Arguments
+/// - name: name of the command, ex: "python3"
+/// ### Returns
+/// Every executable with that name, in the order the shell would try them, or an error if there's none
+pub fn which(name: &str) -> Result<Vec<String>, GunmetalError> {
+    let vars = backend().read()?;
+    let entries = vars.get("PATH").cloned().unwrap_or_default();
+    let found = find_in_entries(name, &entries);
+    if found.is_empty() {
+        return Err(GunmetalError::ValueNotFound(ErrorInfo::new(
+            command_not_found_err!(name),
+        )));
+    }
+    return Ok(found);
+}
+
+/// Lists the executables named `name` in the given PATH entries. Under a root, they're looked for
+/// in the root, but the paths returned are as seen from inside it.
+pub fn find_in_entries(name: &str, entries: &[String]) -> Vec<String> {
+    let root = root().ok().flatten();
+    // a name with a directory in it isn't looked up in PATH
+    if name.contains('/') {
+        return match is_executable(on_disk(root.as_deref(), name)) {
+            true => vec![String::from(name)],
+            false => Vec::new(),
+        };
+    }
+
+    let mut found: Vec<String> = Vec::new();
+    for entry in entries.iter().filter(|entry| !entry.is_empty()) {
+        for candidate in candidate_names(name) {
+            let path = Path::new(entry).join(&candidate);
+            let path_str = path.to_string_lossy().to_string();
+            if is_executable(on_disk(root.as_deref(), &path_str)) && !found.contains(&path_str) {
+                found.push(path_str);
+            }
+        }
+    }
+    return found;
+}
+
+/// Names a command can have on disk: the name itself, and on Windows the name with each extension in PATHEXT
+fn candidate_names(name: &str) -> Vec<String> {
+    let mut names = vec![String::from(name)];
+    if cfg!(target_os = "windows") {
+        let extensions =
+            std::env::var("PATHEXT").unwrap_or_else(|_| String::from(".EXE;.BAT;.CMD"));
+        for extension in extensions
+            .split(';')
+            .filter(|extension| !extension.is_empty())
+        {
+            names.push(format!("{}{}", name, extension.to_lowercase()));
+        }
+    }
+    return names;
+}
+
+/// Where a path from inside the root is on this machine. Relative paths are left alone.
+/// ### Returns
+/// The path, or None if it has too many links to follow
+fn on_disk(root: Option<&Path>, path: &str) -> Option<PathBuf> {
+    return match root {
+        Some(root) if Path::new(path).is_absolute() => {
+            resolve_in_root(root, path).ok().map(PathBuf::from)
+        }
+        _ => Some(PathBuf::from(path)),
+    };
+}
+
+fn is_executable(path: Option<PathBuf>) -> bool {
+    let path = match path {
+        Some(path) => path,
+        None => return false,
+    };
+    if !path.is_file() {
+        return false;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        return path
+            .metadata()
+            .map(|metadata| metadata.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false);
+    }
+    #[cfg(not(unix))]
+    return true;
+}
@@ -0,0 +1,216 @@
+use crate::consts_and_errors::*;
+use crate::env_backend::Position;
+use crate::shell_quote::{add_line, parse_profile, set_line, ProfileAssignment, ShellDialect};
+use crate::target_format::TargetFormat;
+use crate::validation::{validate, validate_value};
+
+/// Adds a value to the front or end of a variable by appending an assignment to the profile
+/// ### Arguments
+/// - content: current contents of the profile
+/// - key: variable name
+/// - value: value to add
+/// - position: which end of the variable the value goes on
+/// - path: path of the profile, which decides its format
+/// ### Returns
+/// The new contents of the profile, or a validation error
+pub fn add_value(
+    content: &str,
+    key: &str,
+    value: &str,
+    position: Position,
+    path: &str,
+) -> Result<String, GunmetalError> {
+    let dialect = shell_dialect(path)?;
+    validate(key, value, TargetFormat::Shell(dialect))?;
+
+    return Ok(format!(
+        "{}\n{}",
+        content,
+        add_line(key, value, position, dialect)
+    ));
+}
+
+/// Sets a variable to a single value, replacing what it was. The last assignment of the variable is rewritten
+/// if it sets the whole value (so setting the same value again changes nothing), otherwise one is appended
+/// ### Arguments
+/// - content: current contents of the profile
+/// - key: variable name
+/// - value: the new value
+/// - path: path of the profile, which decides its format
+/// ### Returns
+/// The new contents of the profile, or a validation error
+pub fn set_value(
+    content: &str,
+    key: &str,
+    value: &str,
+    path: &str,
+) -> Result<String, GunmetalError> {
+    let dialect = shell_dialect(path)?;
+    validate(key, value, TargetFormat::Shell(dialect))?;
+
+    let last = parse_profile(content, dialect)
+        .assignments
+        .into_iter()
+        .rev()
+        .find(|found| found.assignment.key == key);
+    return match last {
+        // an assignment that adds to the existing value (ex: `PATH=a:$PATH`) has to stay
+        Some(found) if !found.assignment.keeps_existing && !found.assignment.appends => Ok(
+            replace_lines(content, &found, Some(set_line(key, value, dialect))),
+        ),
+        _ => Ok(format!("{}\n{}", content, set_line(key, value, dialect))),
+    };
+}
+
+/// Replaces one of a variable's values in the profile, keeping the rest of the line as it was
+/// ### Arguments
+/// - content: current contents of the profile
+/// - key: variable name
+/// - old_value: value to replace, must be set in the profile
+/// - new_value: value to put in its place
+/// - path: path of the profile, which decides its format
+/// ### Returns
+/// The new contents of the profile, or an error if the value isn't in the profile
+pub fn edit_value(
+    content: &str,
+    key: &str,
+    old_value: &str,
+    new_value: &str,
+    path: &str,
+) -> Result<String, GunmetalError> {
+    let dialect = shell_dialect(path)?;
+    validate_value(new_value, TargetFormat::Shell(dialect))?;
+
+    let mut found = find_assignment(content, key, old_value, dialect)
+        .ok_or_else(|| not_found_err(old_value, key, path))?;
+    found
+        .assignment
+        .replace_value(old_value, new_value, dialect);
+
+    return Ok(replace_lines(
+        content,
+        &found,
+        found.assignment.render(dialect),
+    ));
+}
+
+/// Removes one of a variable's values from the profile. If nothing is left of the assignment, the line is removed.
+/// ### Arguments
+/// - content: current contents of the profile
+/// - key: variable name
+/// - value: value to remove, must be set in the profile
+/// - path: path of the profile, which decides its format
+/// ### Returns
+/// The new contents of the profile, or an error if the value isn't in the profile
+pub fn remove_value(
+    content: &str,
+    key: &str,
+    value: &str,
+    path: &str,
+) -> Result<String, GunmetalError> {
+    let dialect = shell_dialect(path)?;
+
+    let mut found = find_assignment(content, key, value, dialect)
+        .ok_or_else(|| not_found_err(value, key, path))?;
+    found.assignment.remove_value(value);
+
+    return Ok(replace_lines(
+        content,
+        &found,
+        found.assignment.render(dialect),
+    ));
+}
+
+/// Removes every assignment of a variable from the profile, whatever values it sets
+/// ### Arguments
+/// - content: current contents of the profile
+/// - key: variable name
+/// - path: path of the profile, which decides its format
+/// ### Returns
+/// The new contents of the profile (the same if the variable isn't assigned), or an error if it can't be edited in place
+pub fn remove_assignments(content: &str, key: &str, path: &str) -> Result<String, GunmetalError> {
+    let dialect = shell_dialect(path)?;
+    let mut updated = String::from(content);
+    // last first, so the line numbers of the ones before stay right
+    for found in parse_profile(content, dialect).assignments.iter().rev() {
+        if found.assignment.key == key {
+            updated = replace_lines(&updated, found, None);
+        }
+    }
+    return Ok(updated);
+}
+
+/// Gets the dialect of a profile, only shell profiles can be edited in place
+fn shell_dialect(path: &str) -> Result<ShellDialect, GunmetalError> {
+    match TargetFormat::from_path(path) {
+        TargetFormat::Shell(dialect) => return Ok(dialect),
+        format => {
+            return Err(GunmetalError::Unsupported(
+                ErrorInfo::new(unsupported_target_err!(format.name())).path(path),
+            ))
+        }
+    }
+}
+
+fn not_found_err(value: &str, key: &str, path: &str) -> GunmetalError {
+    return GunmetalError::ValueNotFound(
+        ErrorInfo::new(value_not_found_err!(value, key, path)).path(path),
+    );
+}
+
+/// Finds the last assignment of a variable that sets the given value (the one that takes effect)
+fn find_assignment(
+    content: &str,
+    key: &str,
+    value: &str,
+    dialect: ShellDialect,
+) -> Option<ProfileAssignment> {
+    return parse_profile(content, dialect)
+        .assignments
+        .into_iter()
+        .rev()
+        .find(|found| {
+            found.assignment.key == key
+                && found.assignment.entries.iter().any(|entry| entry == value)
+        });
+}
+
+/// Replaces the lines of an assignment with its new text, or removes them
+fn replace_lines(content: &str, found: &ProfileAssignment, replacement: Option<String>) -> String {
+    // split on '\n' rather than using lines() so the file's trailing newline survives the join
+    let mut lines: Vec<&str> = content.split('\n').collect();
+    match &replacement {
+        Some(text) => {
+            lines.splice(found.first_line..=found.last_line, [text.as_str()]);
+        }
+        None => {
+            lines.drain(found.first_line..=found.last_line);
+        }
+    }
+    return lines.join("\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setting_the_same_value_again_changes_nothing() {
+        let content = "# profile\nexport IMP=one\n";
+        let updated = set_value(content, "IMP", "two", "/home/user/.bashrc").unwrap();
+        assert_eq!(updated, "# profile\nexport IMP='two'\n");
+        assert_eq!(
+            set_value(&updated, "IMP", "two", "/home/user/.bashrc").unwrap(),
+            updated
+        );
+    }
+
+    #[test]
+    fn setting_keeps_assignments_that_add_to_the_value() {
+        let content = "export PATH=/opt/bin:$PATH";
+        assert_eq!(
+            set_value(content, "PATH", "/bin", "/home/user/.bashrc").unwrap(),
+            "export PATH=/opt/bin:$PATH\nexport PATH='/bin'"
+        );
+    }
+}
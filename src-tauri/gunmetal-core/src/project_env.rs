@@ -0,0 +1,501 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+use crate::consts_and_errors::*;
+use crate::diff_utils::unified_diff;
+use crate::dotenv::parse_dotenv;
+use crate::env_backend::Position;
+use crate::file_guard::remember;
+use crate::fs_root::{resolve_in_root, root, rooted, unrooted};
+use crate::path_check::check_path_entries;
+use crate::provenance::{resolve, Issue, IssueKind, Origin, ResolvedEnv, ResolvedVar, Source};
+use crate::secrets::{check_not_secret, mask_env};
+use crate::settings::Settings;
+use crate::settings_utils::{check_writable, get_settings};
+use crate::shell_quote::{add_line, parse_profile, Assignment, ShellDialect};
+use crate::target_format::TargetFormat;
+use crate::unix_backend::{append_assignment, append_cmd_to_map};
+use crate::validation::validate;
+
+/// The direnv file of a directory, a bash script
+pub const ENVRC: &str = ".envrc";
+/// `KEY=value` file read in directories without an .envrc
+pub const DOTENV: &str = ".env";
+
+/// The variables of a project directory: the global ones, with the .envrc and .env files up the tree applied on top
+#[derive(Serialize, Clone, Debug)]
+pub struct ProjectEnv {
+    /// the project directory, with symbolic links resolved
+    pub dir: String,
+    /// the .envrc and .env files applied, outermost first
+    pub files: Vec<String>,
+    /// the effective variables, with the same fields as `resolve` (schema_version, vars, diagnostics).
+    /// Sources in the project's files have the origin "project"
+    #[serde(flatten)]
+    pub env: ResolvedEnv,
+}
+
+/// A line of an .envrc that changes the environment
+enum Directive {
+    /// `export KEY=value`
+    Assign(Assignment),
+    /// `PATH_add dir...`, which puts the directories in front of PATH
+    PathAdd(Vec<String>),
+    /// `dotenv [file]` (`required`) or `dotenv_if_exists [file]`, which load a .env file
+    Dotenv {
+        file: Option<String>,
+        required: bool,
+    },
+}
+
+/// Variables being layered, with where each is set
+struct Layers {
+    settings: Settings,
+    vars: HashMap<String, Vec<String>>,
+    sources: HashMap<String, Vec<Source>>,
+    issues: Vec<Issue>,
+}
+
+/// Finds the effective variables of a project directory. Going from the outermost directory down to the project,
+/// each directory's .envrc is applied (its `export`, `PATH_add`, `dotenv` and `dotenv_if_exists` lines,
+/// the rest of the script is ignored), or its .env if it has no .envrc.
+/// ### Arguments
+/// - dir: the project directory, as seen from inside the root if one is set
+/// ### Returns
+/// The variables with where they're set, or an error if the directory or the global variables can't be read
+pub fn open_project(dir: &str) -> Result<ProjectEnv, GunmetalError> {
+    let dir_path = project_dir(dir)?;
+    let global = resolve()?;
+
+    let mut layers = Layers {
+        settings: get_settings().unwrap_or_default(),
+        vars: HashMap::new(),
+        sources: HashMap::new(),
+        // PATH is checked again once the project's entries are in
+        issues: global
+            .diagnostics
+            .into_iter()
+            .filter(|issue| issue.kind != IssueKind::Path)
+            .collect(),
+    };
+    for var in global.vars {
+        layers.sources.insert(var.key.to_owned(), var.sources);
+        layers.vars.insert(var.key, var.entries);
+    }
+
+    let mut files: Vec<String> = Vec::new();
+    for file in project_files(&dir_path)? {
+        let path = file.to_string_lossy().to_string();
+        match file.file_name().and_then(|name| name.to_str()) {
+            Some(ENVRC) => layers.apply_envrc(&path),
+            _ => layers.apply_dotenv(&path, None),
+        }
+        files.push(path);
+    }
+
+    if let Some(path) = layers.vars.get("PATH") {
+        for issue in check_path_entries(path) {
+            layers.issues.push(Issue {
+                kind: IssueKind::Path,
+                problem: Some(issue.problem),
+                key: Some(String::from("PATH")),
+                file: None,
+                line: None,
+                message: issue.message,
+            });
+        }
+    }
+
+    let mut vars: Vec<ResolvedVar> = layers
+        .vars
+        .into_iter()
+        .map(|(key, entries)| ResolvedVar {
+            sources: layers.sources.remove(&key).unwrap_or_default(),
+            // secrets set in the project's files are masked like the global ones
+            secret: layers.settings.secrets.contains(&key),
+            key,
+            entries,
+        })
+        .collect();
+    vars.sort_by(|a, b| a.key.cmp(&b.key));
+    let mut env = ResolvedEnv {
+        schema_version: global.schema_version,
+        vars,
+        diagnostics: layers.issues,
+    };
+    mask_env(&mut env);
+    return Ok(ProjectEnv {
+        dir: dir_path.to_string_lossy().to_string(),
+        files,
+        env,
+    });
+}
+
+/// Adds a value to a variable in a project's .envrc, with the same writer as `add_var`. The .envrc is made if needed.
+/// ### Arguments
+/// - dir: the project directory, as seen from inside the root if one is set
+/// - key, value: the variable and the value to add
+/// - dry_run (optional bool): if true, nothing is written and a unified diff of the .envrc is returned instead
+/// - position (optional Position): "prepend" (the default) or "append"
+/// ### Returns
+/// A success message (or the diff when doing a dry run), or an error
+/// ### Types of Errors
+/// - InvalidInput, when the directory isn't one, or the key or value can't be written to the .envrc
+/// - ReadOnly, when read-only mode is on in the settings
+/// - Conflict, when the .envrc was changed by another program since `open_project` read it
+/// - WriteFailed, when the .envrc can't be made or written
+pub fn add_project_var(
+    dir: &str,
+    key: &str,
+    value: &str,
+    dry_run: Option<bool>,
+    position: Option<Position>,
+) -> Result<String, GunmetalError> {
+    let dry_run = dry_run.unwrap_or(false);
+    let position = position.unwrap_or_default();
+    if !dry_run {
+        check_writable()?;
+    }
+    check_not_secret(key)?;
+    let path = project_file(root()?.as_deref(), &project_dir(dir)?, ENVRC)?
+        .to_string_lossy()
+        .to_string();
+
+    let result = if Path::new(&path).exists() {
+        append_assignment(&path, key, value, position, dry_run, |content| {
+            parse_profile(content, ShellDialect::Posix)
+                .assignments
+                .iter()
+                .any(|found| {
+                    found.assignment.key == key
+                        && found.assignment.entries.iter().any(|entry| entry == value)
+                })
+        })
+    } else {
+        make_envrc(&path, key, value, position, dry_run)
+    };
+    match &result {
+        Ok(_) if !dry_run => log::info!("added to {} in {}", key, path),
+        Ok(_) => (),
+        Err(err) => log::warn!("could not add to {} in {} ({})", key, path, err.code()),
+    }
+    return result;
+}
+
+/// Checks a project directory and finds where it is on this machine
+fn project_dir(dir: &str) -> Result<PathBuf, GunmetalError> {
+    // under a root, links are followed inside it (an absolute link must not lead out of the image)
+    let path = match root()? {
+        Some(root) => Some(PathBuf::from(resolve_in_root(&root, dir)?)),
+        None => fs::canonicalize(dir).ok(),
+    };
+    return path.filter(|path| path.is_dir()).ok_or_else(|| {
+        GunmetalError::InvalidInput(ErrorInfo::new(project_dir_err!(dir)).path(dir))
+    });
+}
+
+/// Makes an .envrc that sets a variable, once the key and value are known to be valid
+fn make_envrc(
+    path: &str,
+    key: &str,
+    value: &str,
+    position: Position,
+    dry_run: bool,
+) -> Result<String, GunmetalError> {
+    validate(key, value, TargetFormat::from_path(path))?;
+    let content = add_line(key, value, position, ShellDialect::Posix);
+    if dry_run {
+        return Ok(unified_diff(path, "", &content));
+    }
+    // an .envrc made by someone else in the meantime is left alone
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .map_err(|err| {
+            GunmetalError::from_io(
+                GunmetalError::WriteFailed,
+                GunmetalError::WriteFailed,
+                ErrorInfo::new(make_file_err!(path)).path(path),
+                &err,
+            )
+        })?;
+    remember(path, &content);
+    return Ok(add_var_success!());
+}
+
+/// Lists the .envrc (or else .env) of the directory and each one above it, up to the root, outermost first
+fn project_files(dir: &Path) -> Result<Vec<PathBuf>, GunmetalError> {
+    let root = root()?;
+    let top = root.clone().unwrap_or_else(|| PathBuf::from("/"));
+    let mut files: Vec<PathBuf> = Vec::new();
+    for ancestor in dir
+        .ancestors()
+        .take_while(|ancestor| ancestor.starts_with(&top))
+    {
+        for name in [ENVRC, DOTENV] {
+            let file = project_file(root.as_deref(), ancestor, name)?;
+            if file.is_file() {
+                files.push(file);
+                break;
+            }
+        }
+    }
+    files.reverse();
+    return Ok(files);
+}
+
+/// Finds a file in a project directory on this machine. Under a root, links to it are followed inside the root,
+/// so an .envrc linking to an absolute path reads (and writes) that path in the image, never on this machine
+/// ### Arguments
+/// - root: the root, None for the real one
+/// - dir: the directory on this machine, ex: from `project_dir`
+/// - name: the file's name, ex: ".envrc"
+fn project_file(root: Option<&Path>, dir: &Path, name: &str) -> Result<PathBuf, GunmetalError> {
+    return match root {
+        Some(root) => {
+            let inside = match dir.strip_prefix(root) {
+                Ok(rest) => Path::new("/").join(rest).join(name),
+                Err(_) => dir.join(name),
+            };
+            Ok(PathBuf::from(resolve_in_root(
+                root,
+                &inside.to_string_lossy(),
+            )?))
+        }
+        None => Ok(dir.join(name)),
+    };
+}
+
+impl Layers {
+    /// Applies the directives of an .envrc in order, noting anything that can't be read as an issue
+    fn apply_envrc(&mut self, path: &str) {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) => return self.unreadable(path, None, &err.to_string()),
+        };
+        // later edits through `add_project_var` can tell if the file changed in the meantime
+        remember(path, &content);
+        // paths in the .envrc are seen from inside the root, like the shell that would run it sees them
+        let dir = Path::new(path).parent().unwrap_or(Path::new("/"));
+        let dir = match unrooted(dir) {
+            Ok(dir) => dir,
+            Err(err) => return self.unreadable(path, None, &err.to_string()),
+        };
+
+        let parsed = parse_profile(&content, ShellDialect::Posix);
+        let mut directives: Vec<(usize, Directive)> = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            let mut words = line.split_whitespace().map(unquote);
+            let directive = match words.next().as_deref() {
+                Some("PATH_add") => Directive::PathAdd(words.collect()),
+                Some("dotenv") => Directive::Dotenv {
+                    file: words.next(),
+                    required: true,
+                },
+                Some("dotenv_if_exists") => Directive::Dotenv {
+                    file: words.next(),
+                    required: false,
+                },
+                _ => continue,
+            };
+            directives.push((index, directive));
+        }
+        for found in parsed.assignments {
+            directives.push((found.first_line, Directive::Assign(found.assignment)));
+        }
+        directives.sort_by_key(|(index, _)| *index);
+
+        for (index, directive) in directives {
+            let source = Source {
+                origin: Origin::Project,
+                file: Some(String::from(path)),
+                line: Some(index + 1),
+            };
+            match directive {
+                Directive::Assign(assignment) => {
+                    let sources = self.sources.entry(assignment.key.to_owned()).or_default();
+                    if !assignment.keeps_existing {
+                        sources.clear();
+                    }
+                    sources.push(source);
+                    append_cmd_to_map(assignment, &mut self.vars);
+                }
+                Directive::PathAdd(dirs) => {
+                    let mut entries: Vec<String> =
+                        dirs.iter().map(|added| expand_path(&dir, added)).collect();
+                    let path_var = self.vars.entry(String::from("PATH")).or_default();
+                    entries.append(path_var);
+                    *path_var = entries;
+                    self.sources
+                        .entry(String::from("PATH"))
+                        .or_default()
+                        .push(source);
+                }
+                Directive::Dotenv { file, required } => {
+                    let file = expand_path(&dir, file.as_deref().unwrap_or(DOTENV));
+                    match rooted(&file) {
+                        Ok(file) if required || Path::new(&file).exists() => {
+                            self.apply_dotenv(&file, Some(source))
+                        }
+                        Ok(_) => (),
+                        Err(err) => self.unreadable(&file, Some(source), &err.to_string()),
+                    }
+                }
+            }
+        }
+
+        if let Some(statement) = parsed.incomplete {
+            // the open statement runs to the end of the file, so it starts this many lines before the end
+            let line = content.lines().count() + 1 - statement.split('\n').count();
+            self.issues.push(Issue {
+                kind: IssueKind::Incomplete,
+                problem: None,
+                key: None,
+                file: Some(String::from(path)),
+                line: Some(line),
+                message: tr!("issue-incomplete", line = line, path = path),
+            });
+        }
+    }
+
+    /// Sets every variable of a .env file, each replacing what it was
+    /// ### Arguments
+    /// - path: the .env file
+    /// - loaded_from: the `dotenv` line that loads it, None for a directory's own .env
+    fn apply_dotenv(&mut self, path: &str, loaded_from: Option<Source>) {
+        let vars = fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|content| parse_dotenv(&content, path).map_err(|err| err.to_string()));
+        let vars = match vars {
+            Ok(vars) => vars,
+            Err(err) => return self.unreadable(path, loaded_from, &err),
+        };
+
+        for var in vars {
+            let entries = self.settings.split_entries(&var.key, &var.value);
+            self.sources.insert(
+                var.key.to_owned(),
+                vec![Source {
+                    origin: Origin::Project,
+                    file: Some(String::from(path)),
+                    line: Some(var.line),
+                }],
+            );
+            self.vars.insert(var.key, entries);
+        }
+    }
+
+    /// Notes a file that can't be read, at the line that loads it if there's one
+    fn unreadable(&mut self, path: &str, loaded_from: Option<Source>, reason: &str) {
+        let issue = match loaded_from {
+            Some(Source {
+                file: Some(file),
+                line: Some(line),
+                ..
+            }) => Issue {
+                kind: IssueKind::Unreadable,
+                problem: None,
+                key: None,
+                message: tr!("issue-unreadable", line = line, path = &file, file = path),
+                file: Some(file),
+                line: Some(line),
+            },
+            _ => Issue {
+                kind: IssueKind::Unreadable,
+                problem: None,
+                key: None,
+                file: Some(String::from(path)),
+                line: None,
+                message: String::from(reason),
+            },
+        };
+        self.issues.push(issue);
+    }
+}
+
+/// Removes the quotes around a word of an .envrc directive, ex: `PATH_add "bin"`
+fn unquote(word: &str) -> String {
+    return ['"', '\'']
+        .iter()
+        .find_map(|quote| word.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(word)
+        .to_string();
+}
+
+/// Turns a path from an .envrc into an absolute one, the way direnv does: `~` is the home directory,
+/// relative paths start at the .envrc's directory. Paths are seen from inside the root, use `rooted` to read them
+fn expand_path(dir: &Path, path: &str) -> String {
+    // without a home directory, `~` is left for the shell to expand
+    let home = dirs::home_dir();
+    let expanded = match (path.strip_prefix("~/"), home) {
+        (Some(rest), Some(home)) => home.join(rest),
+        (None, Some(home)) if path == "~" => home,
+        _ => dir.join(path),
+    };
+    // `..` is resolved by name, like direnv, so PATH doesn't get entries like /project/sub/../bin.
+    // It stops at /, like it does in a shell
+    let mut normal = PathBuf::new();
+    for component in expanded.components() {
+        match component {
+            Component::ParentDir => {
+                normal.pop();
+            }
+            Component::CurDir => (),
+            component => normal.push(component),
+        }
+    }
+    return normal.to_string_lossy().to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes an empty directory for a test, named after it so tests running at the same time don't share one
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gunmetal-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        return fs::canonicalize(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_an_absolute_envrc_link_inside_the_root() {
+        let outside = test_dir("project-outside");
+        let victim = outside.join("victim");
+        fs::write(&victim, "export KEEP=1\n").unwrap();
+        let root = test_dir("project-root");
+        let project = root.join("proj");
+        fs::create_dir_all(&project).unwrap();
+        std::os::unix::fs::symlink(&victim, project.join(ENVRC)).unwrap();
+
+        // the link's target is looked up in the image, where it doesn't exist
+        let file = project_file(Some(&root), &project, ENVRC).unwrap();
+        assert!(file.starts_with(&root));
+        assert!(!file.is_file());
+        // so the .envrc is written there, and the file on this machine is left alone
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        let path = file.to_string_lossy().to_string();
+        make_envrc(&path, "FOO", "bar", Position::Prepend, false).unwrap();
+        assert!(fs::read_to_string(&file).unwrap().contains("FOO"));
+        assert_eq!(fs::read_to_string(&victim).unwrap(), "export KEEP=1\n");
+
+        fs::remove_dir_all(outside).unwrap();
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn takes_the_file_in_the_directory_without_a_root() {
+        let dir = Path::new("/home/me/proj");
+        assert_eq!(
+            project_file(None, dir, ENVRC).unwrap(),
+            PathBuf::from("/home/me/proj/.envrc")
+        );
+    }
+}
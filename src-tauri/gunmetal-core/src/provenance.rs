@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::consts_and_errors::*;
+use crate::env_backend::backend;
+use crate::path_check::{check_path_entries, PathProblem};
+use crate::secrets::mask_env;
+use crate::shell_quote::{parse_profile, ShellDialect};
+use crate::system_scope::{environment_assignments, read_target, SystemTarget};
+use crate::target_format::TargetFormat;
+
+/// Version of the `ResolvedEnv` JSON. Fields are only ever added within a version,
+/// anything removed or changed in meaning gets a new version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Where a variable's value was set
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Origin {
+    /// the environment Gunmetal was started with
+    Process,
+    /// the user's shell profile
+    Profile,
+    /// a file read for every user, like /etc/environment
+    System,
+    /// a project's .envrc or .env, or a file it loads
+    Project,
+}
+
+/// One place that sets a variable
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct Source {
+    pub origin: Origin,
+    /// file the variable is set in, None for the process environment
+    pub file: Option<String>,
+    /// line of the file, starting at 1
+    pub line: Option<usize>,
+}
+
+impl Source {
+    pub fn process() -> Source {
+        return Source {
+            origin: Origin::Process,
+            file: None,
+            line: None,
+        };
+    }
+}
+
+/// A variable with its entries and everywhere it's set
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct ResolvedVar {
+    pub key: String,
+    pub entries: Vec<String>,
+    /// places that set the current value, in the order they apply. An assignment that replaces the value drops the ones before it.
+    pub sources: Vec<Source>,
+    /// true if the variable is secret, its entries are then a single mask
+    #[serde(default)]
+    pub secret: bool,
+}
+
+/// Kinds of problems found while resolving the variables
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// a quote in the profile is never closed
+    Incomplete,
+    /// a variable is set again later in the profile without keeping its earlier value
+    Overridden,
+    /// an entry of PATH has a problem, which one is in `problem`
+    Path,
+    /// a file a project's .envrc loads can't be read
+    Unreadable,
+}
+
+/// A problem found while resolving the variables
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct Issue {
+    pub kind: IssueKind,
+    /// what's wrong with the entry, for `Path` issues
+    pub problem: Option<PathProblem>,
+    /// variable the problem is about, if any
+    pub key: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    /// the problem explained for the user
+    pub message: String,
+}
+
+/// Where each variable is set, as found by a backend
+#[derive(Default)]
+pub struct Provenance {
+    pub sources: HashMap<String, Vec<Source>>,
+    pub issues: Vec<Issue>,
+}
+
+/// Every variable, with where it's set and any problems found, sorted by name
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct ResolvedEnv {
+    /// see `SCHEMA_VERSION`
+    pub schema_version: u32,
+    pub vars: Vec<ResolvedVar>,
+    pub diagnostics: Vec<Issue>,
+}
+
+impl ResolvedEnv {
+    /// Keeps only one variable and the problems about it
+    /// ### Returns
+    /// The narrowed result, or an error if the variable isn't set
+    pub fn only(mut self, key: &str) -> Result<ResolvedEnv, GunmetalError> {
+        self.vars.retain(|var| var.key == key);
+        if self.vars.is_empty() {
+            return Err(GunmetalError::ValueNotFound(ErrorInfo::new(
+                var_not_set_err!(key),
+            )));
+        }
+        self.diagnostics
+            .retain(|issue| issue.key.as_deref() == Some(key));
+        return Ok(self);
+    }
+}
+
+/// Reads every variable together with where it's set and the problems found on the way
+/// ### Returns
+/// The resolved variables, or an error if the variables can't be read
+pub fn resolve() -> Result<ResolvedEnv, GunmetalError> {
+    let mut vars = backend().read()?;
+    // a statement that couldn't be read is reported as an issue instead
+    vars.remove("Error:");
+    let mut provenance = backend().provenance()?;
+
+    if let Some(path) = vars.get("PATH") {
+        for issue in check_path_entries(path) {
+            provenance.issues.push(Issue {
+                kind: IssueKind::Path,
+                problem: Some(issue.problem),
+                key: Some(String::from("PATH")),
+                file: None,
+                line: None,
+                message: issue.message,
+            });
+        }
+    }
+
+    let mut resolved: Vec<ResolvedVar> = vars
+        .into_iter()
+        .map(|(key, entries)| ResolvedVar {
+            sources: provenance.sources.remove(&key).unwrap_or_default(),
+            key,
+            entries,
+            secret: false,
+        })
+        .collect();
+    resolved.sort_by(|a, b| a.key.cmp(&b.key));
+    let mut env = ResolvedEnv {
+        schema_version: SCHEMA_VERSION,
+        vars: resolved,
+        diagnostics: provenance.issues,
+    };
+    mask_env(&mut env);
+    return Ok(env);
+}
+
+/// Marks every variable of the process environment as coming from it
+pub fn process_sources(provenance: &mut Provenance) {
+    for (key, _) in std::env::vars() {
+        provenance
+            .sources
+            .entry(key)
+            .or_default()
+            .push(Source::process());
+    }
+}
+
+/// Adds the assignments of a shell profile, in order, noting the ones that replace an earlier one
+/// ### Arguments
+/// - path: path of the profile, which decides its dialect
+/// - content: contents of the profile
+/// - origin: Profile for the user's own profile, System for one sourced for every user
+pub fn profile_sources(provenance: &mut Provenance, path: &str, content: &str, origin: Origin) {
+    let parsed = parse_profile(content, ShellDialect::from_profile_path(path));
+    // line each variable was last assigned on in this file
+    let mut assigned: HashMap<String, usize> = HashMap::new();
+
+    for profile_assignment in &parsed.assignments {
+        let assignment = &profile_assignment.assignment;
+        let line = profile_assignment.first_line + 1;
+        let sources = provenance
+            .sources
+            .entry(assignment.key.to_owned())
+            .or_default();
+        if !assignment.keeps_existing {
+            sources.clear();
+            if let Some(first) = assigned.get(&assignment.key) {
+                provenance.issues.push(Issue {
+                    kind: IssueKind::Overridden,
+                    problem: None,
+                    key: Some(assignment.key.to_owned()),
+                    file: Some(String::from(path)),
+                    line: Some(line),
+                    message: tr!(
+                        "issue-overridden",
+                        key = &assignment.key,
+                        line = line,
+                        path = path,
+                        first = first
+                    ),
+                });
+            }
+        }
+        sources.push(Source {
+            origin,
+            file: Some(String::from(path)),
+            line: Some(line),
+        });
+        assigned.insert(assignment.key.to_owned(), line);
+    }
+
+    if let Some(statement) = parsed.incomplete {
+        // the open statement runs to the end of the file, so it starts this many lines before the end
+        let line = content.lines().count() + 1 - statement.split('\n').count();
+        provenance.issues.push(Issue {
+            kind: IssueKind::Incomplete,
+            problem: None,
+            key: None,
+            file: Some(String::from(path)),
+            line: Some(line),
+            message: tr!("issue-incomplete", line = line, path = path),
+        });
+    }
+}
+
+/// Adds the variables set in the system-wide files Gunmetal can write to, the ones that exist
+pub fn system_sources(provenance: &mut Provenance) {
+    for target in [
+        SystemTarget::Environment,
+        SystemTarget::EnvironmentD,
+        SystemTarget::ProfileD,
+    ] {
+        // a file that can't be read just isn't listed as a source
+        let (path, content) = match target
+            .location()
+            .and_then(|path| Ok((path, read_target(target)?)))
+        {
+            Ok(found) => found,
+            Err(_) => continue,
+        };
+        if let TargetFormat::Shell(_) = target.format() {
+            profile_sources(provenance, &path, &content, Origin::System);
+            continue;
+        }
+        for (index, key, value) in environment_assignments(&content) {
+            let sources = provenance.sources.entry(key.to_owned()).or_default();
+            // only environment.d can refer to the existing value (`KEY=${KEY}:value`), otherwise each line replaces it
+            if !value.contains(&format!("${{{}}}", key)) && !value.contains(&format!("${}", key)) {
+                sources.clear();
+            }
+            sources.push(Source {
+                origin: Origin::System,
+                file: Some(path.to_owned()),
+                line: Some(index + 1),
+            });
+        }
+    }
+}
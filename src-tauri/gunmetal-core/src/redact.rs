@@ -0,0 +1,118 @@
+use crate::settings::load_settings;
+use crate::settings_utils::get_config_path;
+
+/// Shown instead of a secret value
+pub const REDACTED: &str = "[redacted]";
+
+/// Parts of a variable name that mean its value is probably a secret, ex: "GITHUB_TOKEN" or "DB_PASSWORD"
+const SECRET_WORDS: &[&str] = &[
+    "SECRET",
+    "TOKEN",
+    "PASSWORD",
+    "PASSWD",
+    "PASSPHRASE",
+    "CREDENTIAL",
+    "PRIVATE",
+    "API_KEY",
+    "APIKEY",
+    "ACCESS_KEY",
+    "AUTH",
+    "COOKIE",
+    "SESSION",
+];
+
+/// Checks if a variable holds a secret: it's kept in the vault, or its name looks like it
+pub fn is_secret_key(key: &str) -> bool {
+    return looks_secret(key) || vault_keys().iter().any(|secret| secret == key);
+}
+
+/// Checks if a variable name looks like it holds a secret
+fn looks_secret(key: &str) -> bool {
+    let key = key.to_uppercase();
+    return SECRET_WORDS.iter().any(|word| key.contains(word)) || key.ends_with("_KEY");
+}
+
+/// Lists the variables kept in the vault. The settings are read directly, since reading them through
+/// `get_settings` logs, and the logger redacts through here
+fn vault_keys() -> Vec<String> {
+    return get_config_path()
+        .and_then(|path| load_settings(&format!("{}/settings.json", path)))
+        .map(|settings| settings.secrets)
+        .unwrap_or_default();
+}
+
+/// Hides the values of every `KEY=value` in a text whose key is in the vault or looks like it holds a secret.
+/// Quoted values are hidden up to their closing quote, others up to the next whitespace.
+/// ### Arguments
+/// - text: any text, ex: a log message or a line of a profile
+/// ### Returns
+/// The text with secret values replaced by `REDACTED`
+pub fn redact_text(text: &str) -> String {
+    let vault_keys = vault_keys();
+    let chars: Vec<char> = text.chars().collect();
+    let mut redacted = String::with_capacity(text.len());
+    let mut index = 0;
+
+    while index < chars.len() {
+        redacted.push(chars[index]);
+        if chars[index] != '=' {
+            index += 1;
+            continue;
+        }
+
+        // the key is the run of name characters right before the '='
+        let key_start = chars[..index]
+            .iter()
+            .rposition(|ch| !(ch.is_ascii_alphanumeric() || *ch == '_'))
+            .map_or(0, |position| position + 1);
+        let key: String = chars[key_start..index].iter().collect();
+        index += 1;
+        if key.is_empty() || !(looks_secret(&key) || vault_keys.contains(&key)) {
+            continue;
+        }
+
+        let value_end = match chars.get(index) {
+            Some(quote) if *quote == '"' || *quote == '\'' => chars[index + 1..]
+                .iter()
+                .position(|ch| ch == quote)
+                .map_or(chars.len(), |position| index + position + 2),
+            _ => chars[index..]
+                .iter()
+                .position(|ch| ch.is_whitespace())
+                .map_or(chars.len(), |position| index + position),
+        };
+        if value_end > index {
+            redacted.push_str(REDACTED);
+            index = value_end;
+        }
+    }
+    return redacted;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spots_secret_looking_names() {
+        for key in [
+            "GITHUB_TOKEN",
+            "db_password",
+            "AWS_SECRET_ACCESS_KEY",
+            "SSH_KEY",
+        ] {
+            assert!(looks_secret(key), "{}", key);
+        }
+        for key in ["PATH", "HOME", "KEYBOARD_LAYOUT", "EDITOR"] {
+            assert!(!looks_secret(key), "{}", key);
+        }
+    }
+
+    #[test]
+    fn hides_quoted_and_plain_values() {
+        assert_eq!(
+            redact_text("export API_TOKEN='a b' EDITOR=vim DB_PASSWORD=hunter2"),
+            "export API_TOKEN=[redacted] EDITOR=vim DB_PASSWORD=[redacted]"
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use crate::consts_and_errors::*;
+use crate::env_backend::backend;
+use crate::settings_utils::check_writable;
+
+/// Removes one entry from an environment variable.
+/// ### Arguments:
+/// - key (String)
+/// - value (String): the entry to remove
+/// - dry_run (optional bool): if true, nothing is written and a unified diff of each affected file is returned instead
+/// ### Returns
+/// Either a success message (or the diff when doing a dry run) or an error message
+/// ### Types of Errors
+/// - ValueNotFoundError, when the entry isn't set in the shell profile (ex: it only comes from the process environment)
+/// - ProfileOpenError, when the program is unable to open a shell profile
+/// - WriteToFileError, when the program is unable to write to a file (usually the shell profile file)
+/// - ReadOnlyError, when read-only mode is on in the settings
+/// - ConflictError, when the shell profile was changed by another program since `get_vars` read it
+pub fn remove_var(
+    key: String,
+    value: String,
+    dry_run: Option<bool>,
+) -> Result<String, GunmetalError> {
+    let dry_run = dry_run.unwrap_or(false);
+    if !dry_run {
+        check_writable()?;
+    }
+    let result = backend().remove(&key, &value, dry_run);
+    match &result {
+        Ok(_) if !dry_run => log::info!("removed an entry from {}", key),
+        Ok(_) => (),
+        Err(err) => log::warn!("could not remove an entry from {} ({})", key, err.code()),
+    }
+    return result;
+}
@@ -0,0 +1,593 @@
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::consts_and_errors::*;
+use crate::provenance::{resolve, IssueKind, Origin, ResolvedEnv, Source, SCHEMA_VERSION};
+
+/// How the search text is matched
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    /// the text appears as is
+    Substring,
+    /// the text is a regular expression, see the `regex` crate for the syntax
+    Regex,
+    /// the characters of the text appear in order, with anything in between
+    Fuzzy,
+}
+
+/// Parts of a variable the search text is matched against
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    Key,
+    /// each entry of the value separately
+    Value,
+    /// the files the variable is set in
+    File,
+}
+
+/// Whether any problem was found about a variable
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Health {
+    Healthy,
+    /// at least one issue is about the variable, see `SearchHit::issues`
+    Unhealthy,
+}
+
+/// What to look for. Every filter given must match, an empty one matches everything.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct SearchQuery {
+    /// text to look for. Empty to only filter
+    pub text: String,
+    pub mode: MatchMode,
+    /// if true, upper and lower case letters are different
+    pub case_sensitive: bool,
+    /// fields to match the text against, all of them if empty
+    pub fields: Vec<Field>,
+    /// only variables set in one of these places, any if empty
+    pub origins: Vec<Origin>,
+    /// only variables in this state, any if not given
+    pub health: Option<Health>,
+    /// most results to return, all if not given
+    pub limit: Option<usize>,
+}
+
+impl Default for SearchQuery {
+    fn default() -> SearchQuery {
+        return SearchQuery {
+            text: String::new(),
+            mode: MatchMode::Substring,
+            case_sensitive: false,
+            fields: Vec::new(),
+            origins: Vec::new(),
+            health: None,
+            limit: None,
+        };
+    }
+}
+
+/// Part of a text to highlight, in characters (not bytes) from the start, end excluded
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+pub struct Highlight {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Where the search text was found in a variable
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct FieldMatch {
+    pub field: Field,
+    /// the entry (for `value`) or the source (for `file`) that matched, None for `key`
+    pub index: Option<usize>,
+    pub ranges: Vec<Highlight>,
+}
+
+/// A variable found by a search
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct SearchHit {
+    pub key: String,
+    /// masked for secret variables, which are only found by key or file
+    pub entries: Vec<String>,
+    pub sources: Vec<Source>,
+    pub secret: bool,
+    pub health: Health,
+    /// kinds of the problems about the variable, without repeats
+    pub issues: Vec<IssueKind>,
+    /// higher is a better match. Key matches count more than value matches, which count more than file matches
+    pub score: u32,
+    pub matches: Vec<FieldMatch>,
+}
+
+/// The variables found, best first
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct SearchResults {
+    /// see `SCHEMA_VERSION`
+    pub schema_version: u32,
+    /// how many variables matched, before `limit` was applied
+    pub total: usize,
+    pub hits: Vec<SearchHit>,
+}
+
+/// Searches every variable, see `search`
+/// ### Returns
+/// The variables found, or an error if the variables can't be read or the regular expression is invalid
+pub fn search_vars(query: SearchQuery) -> Result<SearchResults, GunmetalError> {
+    return search(resolve()?, &query);
+}
+
+/// Finds the variables matching a query, ranked by how well they match
+/// ### Arguments
+/// - env: the variables to search, ex: from `resolve` or `open_project`
+/// - query: what to look for
+/// ### Returns
+/// The variables found, best first (by name when the scores are the same), or an error if the regular expression is invalid
+/// ### Types of Errors
+/// - InvalidInput, when the mode is regex and the text isn't a valid regular expression
+pub fn search(env: ResolvedEnv, query: &SearchQuery) -> Result<SearchResults, GunmetalError> {
+    let matcher = Matcher::new(query)?;
+    let searches = |field: Field| query.fields.is_empty() || query.fields.contains(&field);
+
+    let mut hits: Vec<SearchHit> = Vec::new();
+    for var in env.vars {
+        if !query.origins.is_empty()
+            && !var
+                .sources
+                .iter()
+                .any(|source| query.origins.contains(&source.origin))
+        {
+            continue;
+        }
+        let mut issues: Vec<IssueKind> = Vec::new();
+        for issue in &env.diagnostics {
+            if issue.key.as_deref() == Some(var.key.as_str()) && !issues.contains(&issue.kind) {
+                issues.push(issue.kind);
+            }
+        }
+        let health = match issues.is_empty() {
+            true => Health::Healthy,
+            false => Health::Unhealthy,
+        };
+        if query.health.is_some_and(|wanted| wanted != health) {
+            continue;
+        }
+
+        let mut score = 0;
+        let mut matches: Vec<FieldMatch> = Vec::new();
+        if let Some(matcher) = &matcher {
+            if searches(Field::Key) {
+                score +=
+                    matcher.match_field(Field::Key, [(None, var.key.as_str())], &mut matches) * 3;
+            }
+            // a secret's mask isn't its value, so there's nothing to find in it
+            if searches(Field::Value) && !var.secret {
+                let entries = var
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .map(|(index, entry)| (Some(index), entry.as_str()));
+                score += matcher.match_field(Field::Value, entries, &mut matches) * 2;
+            }
+            if searches(Field::File) {
+                let files = var
+                    .sources
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, source)| {
+                        source.file.as_deref().map(|file| (Some(index), file))
+                    });
+                score += matcher.match_field(Field::File, files, &mut matches);
+            }
+            if matches.is_empty() {
+                continue;
+            }
+        }
+
+        hits.push(SearchHit {
+            key: var.key,
+            entries: var.entries,
+            sources: var.sources,
+            secret: var.secret,
+            health,
+            issues,
+            score,
+            matches,
+        });
+    }
+
+    hits.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.key.cmp(&b.key)));
+    let total = hits.len();
+    if let Some(limit) = query.limit {
+        hits.truncate(limit);
+    }
+    return Ok(SearchResults {
+        schema_version: SCHEMA_VERSION,
+        total,
+        hits,
+    });
+}
+
+/// The search text, ready to be matched
+enum Matcher {
+    Substring(Vec<char>, bool),
+    Regex(Regex),
+    Fuzzy(Vec<char>, bool),
+}
+
+impl Matcher {
+    /// Prepares the query's text
+    /// ### Returns
+    /// The matcher, None if there's no text, or an error if the regular expression is invalid
+    fn new(query: &SearchQuery) -> Result<Option<Matcher>, GunmetalError> {
+        if query.text.is_empty() {
+            return Ok(None);
+        }
+        let chars: Vec<char> = query.text.chars().collect();
+        return Ok(Some(match query.mode {
+            MatchMode::Substring => Matcher::Substring(chars, query.case_sensitive),
+            MatchMode::Fuzzy => Matcher::Fuzzy(chars, query.case_sensitive),
+            MatchMode::Regex => Matcher::Regex(
+                RegexBuilder::new(&query.text)
+                    .case_insensitive(!query.case_sensitive)
+                    .build()
+                    .map_err(|err| {
+                        GunmetalError::InvalidInput(
+                            ErrorInfo::new(search_regex_err!(&query.text)).details(err),
+                        )
+                    })?,
+            ),
+        }));
+    }
+
+    /// Matches every text of a field, adding the ones that match to `matches`
+    /// ### Arguments
+    /// - texts: the texts with their index, see `FieldMatch::index`
+    /// ### Returns
+    /// The score of the best match in the field, 0 if none matched
+    fn match_field<'a>(
+        &self,
+        field: Field,
+        texts: impl IntoIterator<Item = (Option<usize>, &'a str)>,
+        matches: &mut Vec<FieldMatch>,
+    ) -> u32 {
+        let mut best = 0;
+        for (index, text) in texts {
+            if let Some((score, ranges)) = self.find(text) {
+                best = best.max(score);
+                matches.push(FieldMatch {
+                    field,
+                    index,
+                    ranges,
+                });
+            }
+        }
+        return best;
+    }
+
+    /// Matches one text
+    /// ### Returns
+    /// The score and the parts that matched, or None if the text doesn't match
+    fn find(&self, text: &str) -> Option<(u32, Vec<Highlight>)> {
+        let chars: Vec<char> = text.chars().collect();
+        let ranges = match self {
+            Matcher::Substring(needle, case_sensitive) => {
+                find_substrings(&chars, needle, *case_sensitive)
+            }
+            Matcher::Regex(regex) => regex
+                .find_iter(text)
+                // an empty match (ex: `a*`) has nothing to show
+                .filter(|found| found.start() < found.end())
+                .map(|found| Highlight {
+                    start: text[..found.start()].chars().count(),
+                    end: text[..found.end()].chars().count(),
+                })
+                .collect(),
+            Matcher::Fuzzy(pattern, case_sensitive) => {
+                return fuzzy_match(&chars, pattern, *case_sensitive);
+            }
+        };
+        if ranges.is_empty() {
+            return None;
+        }
+        return Some((position_score(&chars, &ranges), ranges));
+    }
+}
+
+/// Finds every place a text appears, without overlaps
+fn find_substrings(text: &[char], needle: &[char], case_sensitive: bool) -> Vec<Highlight> {
+    let mut ranges: Vec<Highlight> = Vec::new();
+    let mut start = 0;
+    while start + needle.len() <= text.len() {
+        let found = needle
+            .iter()
+            .enumerate()
+            .all(|(offset, ch)| same_char(text[start + offset], *ch, case_sensitive));
+        if found {
+            ranges.push(Highlight {
+                start,
+                end: start + needle.len(),
+            });
+            start += needle.len();
+        } else {
+            start += 1;
+        }
+    }
+    return ranges;
+}
+
+/// Scores a substring or regex match: a match of the whole text beats one at its start,
+/// which beats one at the start of a word, which beats one anywhere else. Each extra match adds a little.
+fn position_score(text: &[char], ranges: &[Highlight]) -> u32 {
+    let first = ranges[0];
+    let mut score = 100;
+    if first.start == 0 && first.end == text.len() {
+        score += 100;
+    } else if first.start == 0 {
+        score += 40;
+    } else if is_word_start(text, first.start) {
+        score += 20;
+    }
+    return score + (ranges.len().min(5) as u32 - 1) * 5;
+}
+
+/// Matches the characters of a pattern in order, at the places that score best, so `pa` in "xpxxPATH" is the start of "PATH".
+/// Characters next to each other and at the start of words score higher, each skipped character and a late start score lower.
+/// ### Returns
+/// The score and the characters that matched, joined into ranges, or None if a character of the pattern is missing
+fn fuzzy_match(
+    text: &[char],
+    pattern: &[char],
+    case_sensitive: bool,
+) -> Option<(u32, Vec<Highlight>)> {
+    if pattern.is_empty() {
+        return None;
+    }
+    let char_score = |position: usize| match is_word_start(text, position) {
+        true => 20,
+        false => 10,
+    };
+
+    // best[i][j] is the best score of the first i+1 pattern characters with the last one at j,
+    // and from[i][j] is where the one before it is then
+    let mut best: Vec<Vec<Option<i64>>> = vec![vec![None; text.len()]; pattern.len()];
+    let mut from: Vec<Vec<usize>> = vec![vec![0; text.len()]; pattern.len()];
+    for (i, ch) in pattern.iter().enumerate() {
+        // best of best[i-1][k] + k over the places k left of j-1, so a gap costs its length without another loop
+        let mut before_gap: Option<(i64, usize)> = None;
+        for j in 0..text.len() {
+            if i > 0 && j >= 2 {
+                if let Some(score) = best[i - 1][j - 2] {
+                    let candidate = score + (j - 2) as i64;
+                    if before_gap.map_or(true, |(best_gap, _)| candidate > best_gap) {
+                        before_gap = Some((candidate, j - 2));
+                    }
+                }
+            }
+            if !same_char(text[j], *ch, case_sensitive) {
+                continue;
+            }
+            if i == 0 {
+                best[i][j] = Some(char_score(j) - j.min(10) as i64);
+                continue;
+            }
+            let next_to = match j {
+                0 => None,
+                _ => best[i - 1][j - 1].map(|score| (score + 15, j - 1)),
+            };
+            let after_gap = before_gap.map(|(score, k)| (score - (j as i64 - 1), k));
+            let previous = match (next_to, after_gap) {
+                (Some(a), Some(b)) if b.0 > a.0 => Some(b),
+                (Some(a), _) => Some(a),
+                (None, b) => b,
+            };
+            if let Some((score, k)) = previous {
+                best[i][j] = Some(score + char_score(j));
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let last = pattern.len() - 1;
+    let (score, end) = best[last]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| score.map(|score| (score, j)))
+        // the first of equal scores, so earlier matches win ties
+        .fold(
+            None,
+            |found: Option<(i64, usize)>, (score, j)| match found {
+                Some(found) if found.0 >= score => Some(found),
+                _ => Some((score, j)),
+            },
+        )?;
+
+    let mut positions: Vec<usize> = vec![end];
+    for i in (1..pattern.len()).rev() {
+        positions.push(from[i][positions[positions.len() - 1]]);
+    }
+    positions.reverse();
+    let mut ranges: Vec<Highlight> = Vec::new();
+    for position in positions {
+        match ranges.last_mut() {
+            Some(range) if range.end == position => range.end += 1,
+            _ => ranges.push(Highlight {
+                start: position,
+                end: position + 1,
+            }),
+        }
+    }
+    return Some((score.max(1) as u32, ranges));
+}
+
+/// Checks if a character starts a word: it's first, follows a separator, or is a capital after a lowercase letter
+fn is_word_start(text: &[char], index: usize) -> bool {
+    if index == 0 {
+        return true;
+    }
+    let before = text[index - 1];
+    return !before.is_alphanumeric() || (before.is_lowercase() && text[index].is_uppercase());
+}
+
+fn same_char(a: char, b: char, case_sensitive: bool) -> bool {
+    return a == b || (!case_sensitive && a.to_lowercase().eq(b.to_lowercase()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provenance::{Issue, ResolvedVar};
+
+    fn var(key: &str, entries: &[&str], origin: Origin, file: Option<&str>) -> ResolvedVar {
+        return ResolvedVar {
+            key: String::from(key),
+            entries: entries.iter().map(|entry| String::from(*entry)).collect(),
+            sources: vec![Source {
+                origin,
+                file: file.map(String::from),
+                line: file.map(|_| 1),
+            }],
+            secret: false,
+        };
+    }
+
+    fn env() -> ResolvedEnv {
+        return ResolvedEnv {
+            schema_version: SCHEMA_VERSION,
+            vars: vec![
+                var(
+                    "EDITOR",
+                    &["vim"],
+                    Origin::Profile,
+                    Some("/home/u/.profile"),
+                ),
+                var("HOME", &["/home/u"], Origin::Process, None),
+                var(
+                    "MYPATH",
+                    &["/opt/x"],
+                    Origin::System,
+                    Some("/etc/environment"),
+                ),
+                var(
+                    "PATH",
+                    &["/usr/bin", "/home/u/bin"],
+                    Origin::Profile,
+                    Some("/home/u/.profile"),
+                ),
+            ],
+            diagnostics: vec![Issue {
+                kind: IssueKind::Path,
+                problem: None,
+                key: Some(String::from("PATH")),
+                file: None,
+                line: None,
+                message: String::new(),
+            }],
+        };
+    }
+
+    fn keys(results: &SearchResults) -> Vec<&str> {
+        return results.hits.iter().map(|hit| hit.key.as_str()).collect();
+    }
+
+    fn range(start: usize, end: usize) -> Highlight {
+        return Highlight { start, end };
+    }
+
+    #[test]
+    fn ranks_exact_and_key_matches_first() {
+        let query = SearchQuery {
+            text: String::from("path"),
+            ..SearchQuery::default()
+        };
+        let results = search(env(), &query).unwrap();
+        assert_eq!(keys(&results), vec!["PATH", "MYPATH"]);
+        assert_eq!(results.total, 2);
+
+        // a key match counts more than a value match
+        let query = SearchQuery {
+            text: String::from("home"),
+            ..SearchQuery::default()
+        };
+        let results = search(env(), &query).unwrap();
+        assert_eq!(keys(&results), vec!["HOME", "PATH", "EDITOR"]);
+    }
+
+    #[test]
+    fn highlights_characters_not_bytes() {
+        let matcher = Matcher::new(&SearchQuery {
+            text: String::from("bin"),
+            ..SearchQuery::default()
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            matcher.find("/ü/bin:/é/bin").unwrap().1,
+            vec![range(3, 6), range(10, 13)]
+        );
+
+        let matcher = Matcher::new(&SearchQuery {
+            text: String::from("b.n"),
+            mode: MatchMode::Regex,
+            ..SearchQuery::default()
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(matcher.find("/ü/bin").unwrap().1, vec![range(3, 6)]);
+    }
+
+    #[test]
+    fn fuzzy_matches_the_best_places() {
+        let text: Vec<char> = "xpxxPATH".chars().collect();
+        let pattern: Vec<char> = "pa".chars().collect();
+        let (score, ranges) = fuzzy_match(&text, &pattern, false).unwrap();
+        assert_eq!(ranges, vec![range(4, 6)]);
+
+        // scattered characters score lower than the same ones next to each other
+        let scattered: Vec<char> = "xpxxaxxx".chars().collect();
+        let (scattered_score, ranges) = fuzzy_match(&scattered, &pattern, false).unwrap();
+        assert_eq!(ranges, vec![range(1, 2), range(4, 5)]);
+        assert!(score > scattered_score);
+
+        let camel: Vec<char> = "ldLibraryPath".chars().collect();
+        let pattern: Vec<char> = "lp".chars().collect();
+        assert_eq!(
+            fuzzy_match(&camel, &pattern, false).unwrap().1,
+            vec![range(0, 1), range(9, 10)]
+        );
+        assert!(fuzzy_match(&camel, &pattern, true).is_none());
+        let missing: Vec<char> = "px".chars().collect();
+        assert!(fuzzy_match(&camel, &missing, false).is_none());
+    }
+
+    #[test]
+    fn filters_by_origin_and_health() {
+        let query = SearchQuery {
+            origins: vec![Origin::Profile],
+            ..SearchQuery::default()
+        };
+        assert_eq!(
+            keys(&search(env(), &query).unwrap()),
+            vec!["EDITOR", "PATH"]
+        );
+
+        let query = SearchQuery {
+            health: Some(Health::Unhealthy),
+            ..SearchQuery::default()
+        };
+        let results = search(env(), &query).unwrap();
+        assert_eq!(keys(&results), vec!["PATH"]);
+        assert_eq!(results.hits[0].issues, vec![IssueKind::Path]);
+
+        let query = SearchQuery {
+            origins: vec![Origin::System, Origin::Process],
+            health: Some(Health::Healthy),
+            limit: Some(1),
+            ..SearchQuery::default()
+        };
+        let results = search(env(), &query).unwrap();
+        assert_eq!(keys(&results), vec!["HOME"]);
+        assert_eq!(results.total, 2);
+    }
+}
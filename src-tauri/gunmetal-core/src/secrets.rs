@@ -0,0 +1,576 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::backups::try_backup;
+use crate::consts_and_errors::*;
+use crate::diff_utils::unified_diff;
+use crate::env_backend::backend;
+use crate::file_guard::{check_unchanged, remember, write_atomically_with_mode, LockedFile};
+use crate::manage_settings::update_settings;
+use crate::profile_edit::remove_assignments;
+use crate::provenance::{ResolvedEnv, ResolvedVar};
+use crate::settings::{save_settings, validate_settings, Settings};
+use crate::settings_utils::{
+    check_writable, get_config_path, get_settings, get_shell_profile_path,
+};
+use crate::shell_quote::{set_line, ShellDialect};
+use crate::unix_backend::profile_read_err;
+
+/// Shown instead of the value of a secret variable
+pub const MASK: &str = "********";
+/// Version of the vault file's layout
+const VAULT_VERSION: u32 = 1;
+/// Length of the random salt the vault's key is derived with
+const SALT_LENGTH: usize = 16;
+
+/// The vault file as saved: the secrets, as JSON, encrypted with XChaCha20-Poly1305
+/// under a key derived from the passphrase with Argon2id. Binary fields are hex.
+#[derive(Deserialize, Serialize)]
+struct VaultFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Finds the vault file, `vault.json` in the config directory
+pub fn vault_path() -> Result<PathBuf, GunmetalError> {
+    return Ok(PathBuf::from(get_config_path()?).join("vault.json"));
+}
+
+/// Checks if a variable is marked secret in the settings
+pub fn is_secret(key: &str) -> bool {
+    return get_settings()
+        .map(|settings| settings.secrets.iter().any(|secret| secret == key))
+        .unwrap_or(false);
+}
+
+/// Refuses to write a secret variable anywhere but the vault
+/// ### Returns
+/// Nothing if the variable isn't secret, an InvalidInput error if it is
+pub fn check_not_secret(key: &str) -> Result<(), GunmetalError> {
+    if is_secret(key) {
+        return Err(GunmetalError::InvalidInput(ErrorInfo::new(
+            secret_write_err!(key),
+        )));
+    }
+    return Ok(());
+}
+
+/// Lists the variables marked secret
+pub fn list_secrets() -> Vec<String> {
+    return get_settings()
+        .map(|settings| settings.secrets)
+        .unwrap_or_default();
+}
+
+/// Hides the values of secret variables in a map of variables, as returned by `get_vars`.
+/// Secrets that aren't loaded (ex: in a shell started before they were added) are listed too.
+pub fn mask_vars(vars: &mut HashMap<String, Vec<String>>) {
+    for key in list_secrets() {
+        vars.insert(key, vec![String::from(MASK)]);
+    }
+}
+
+/// Hides the values of secret variables in resolved variables, and marks them as secret.
+/// Secrets that aren't loaded are listed too, with no sources.
+pub fn mask_env(env: &mut ResolvedEnv) {
+    for key in list_secrets() {
+        match env.vars.iter_mut().find(|var| var.key == key) {
+            Some(var) => {
+                var.entries = vec![String::from(MASK)];
+                var.secret = true;
+            }
+            None => env.vars.push(ResolvedVar {
+                key,
+                entries: vec![String::from(MASK)],
+                sources: Vec::new(),
+                secret: true,
+            }),
+        }
+    }
+    env.vars.sort_by(|a, b| a.key.cmp(&b.key));
+}
+
+/// The line added to a profile that loads the secrets when a shell starts, asking for the passphrase
+/// unless GUNMETAL_VAULT_PASSPHRASE is set
+pub fn loader_line(dialect: ShellDialect) -> &'static str {
+    match dialect {
+        ShellDialect::Posix => "eval \"$(gunmetal secret env)\"",
+        ShellDialect::Fish => "gunmetal secret env --shell fish | source",
+    }
+}
+
+/// Marks a variable secret: its value moves to the vault, and the profile gets the loader line instead of it
+/// ### Arguments
+/// - key: the variable
+/// - value (optional String): the secret value. Defaults to the variable's current value
+/// - passphrase: the vault's passphrase, which becomes the passphrase if there's no vault yet
+/// - dry_run (optional bool): if true, nothing is written and a unified diff of the profile is returned instead
+/// ### Returns
+/// A success message (or the diff when doing a dry run), or an error
+/// ### Types of Errors
+/// - ValueNotFound, when no value is given and the variable isn't set
+/// - InvalidInput, when the passphrase doesn't unlock the vault or is empty
+/// - ReadOnly, when read-only mode is on in the settings
+/// - Conflict, when the profile was changed by another program since `get_vars` read it
+/// - WriteFailed, when the vault, the profile or the settings can't be written
+pub fn mark_secret(
+    key: String,
+    value: Option<String>,
+    passphrase: String,
+    dry_run: Option<bool>,
+) -> Result<String, GunmetalError> {
+    let dry_run = dry_run.unwrap_or(false);
+    if !dry_run {
+        check_writable()?;
+    }
+    let value = match value {
+        Some(value) => value,
+        None => backend()
+            .read()?
+            .get(&key)
+            .map(|entries| entries.join(":"))
+            .ok_or_else(|| GunmetalError::ValueNotFound(ErrorInfo::new(var_not_set_err!(&key))))?,
+    };
+    let mut vault = open_vault(&passphrase)?;
+
+    let path = get_shell_profile_path()?;
+    let update = |content: &str| marked_profile(content, &key, &path);
+    if dry_run {
+        return edit_profile(&path, true, update);
+    }
+
+    // the new settings are checked before anything is written
+    let previous = Previous::save()?;
+    let mut settings = previous.settings.clone();
+    if !settings.secrets.contains(&key) {
+        settings.secrets.push(key.to_owned());
+    }
+    validate_settings(&settings)?;
+
+    // the value is safe in the vault before it leaves the profile, and the settings list it as secret
+    // before the profile is backed up, so the backup leaves it out. If a later step fails, the vault and
+    // the settings are put back, so the three never disagree
+    vault.insert(key.to_owned(), value);
+    seal_vault(&vault, &passphrase)?;
+    let result = update_settings(settings).and_then(|_| edit_profile(&path, false, update));
+    if let Err(err) = result {
+        previous.restore();
+        return Err(err);
+    }
+
+    log::info!("{} is now secret", key);
+    return Ok(secret_added!(&key));
+}
+
+/// Makes a secret variable plain again: its value goes back in the profile and leaves the vault.
+/// The loader line is removed with the last secret.
+/// ### Arguments
+/// - key: the variable
+/// - passphrase: the vault's passphrase
+/// - dry_run (optional bool): if true, nothing is written and a unified diff of the profile is returned instead
+/// ### Returns
+/// A success message (or the diff when doing a dry run), or an error
+pub fn unmark_secret(
+    key: String,
+    passphrase: String,
+    dry_run: Option<bool>,
+) -> Result<String, GunmetalError> {
+    let dry_run = dry_run.unwrap_or(false);
+    if !dry_run {
+        check_writable()?;
+    }
+    let mut vault = open_vault(&passphrase)?;
+    let value = vault
+        .remove(&key)
+        .ok_or_else(|| GunmetalError::ValueNotFound(ErrorInfo::new(no_secret_err!(&key))))?;
+
+    let path = get_shell_profile_path()?;
+    let dialect = ShellDialect::from_profile_path(&path);
+    let update = |content: &str| {
+        Ok(unmarked_profile(
+            content,
+            &key,
+            &value,
+            dialect,
+            !vault.is_empty(),
+        ))
+    };
+    if dry_run {
+        return edit_profile(&path, true, update);
+    }
+
+    // the new settings are checked before anything is written
+    let previous = Previous::save()?;
+    let mut settings = previous.settings.clone();
+    settings.secrets.retain(|secret| secret != &key);
+    validate_settings(&settings)?;
+
+    // the value is back in the profile before it leaves the vault. If a later step fails,
+    // the profile, the vault and the settings are all put back
+    let original = edit_profile(&path, false, update)?;
+    let result = seal_vault(&vault, &passphrase).and_then(|_| update_settings(settings));
+    if let Err(err) = result {
+        previous.restore();
+        if let Err(err) = edit_profile(&path, false, |_| Ok(original.clone())) {
+            log::error!("could not roll back {}: {}", path, err);
+        }
+        return Err(err);
+    }
+
+    log::info!("{} is no longer secret", key);
+    return Ok(secret_removed!(&key));
+}
+
+/// Gets the value of a secret variable from the vault
+/// ### Returns
+/// The value, or an error if the passphrase is wrong or the variable isn't in the vault
+pub fn reveal_secret(key: String, passphrase: String) -> Result<String, GunmetalError> {
+    return open_vault(&passphrase)?
+        .remove(&key)
+        .ok_or_else(|| GunmetalError::ValueNotFound(ErrorInfo::new(no_secret_err!(&key))));
+}
+
+/// Builds the commands that set every secret in the vault, for the loader line to run
+/// ### Arguments
+/// - passphrase: the vault's passphrase
+/// - dialect: the shell the commands are for
+/// ### Returns
+/// One `export KEY='value'` (or fish's `set -gx`) line per secret, or an error if the vault can't be opened
+pub fn secret_exports(passphrase: &str, dialect: ShellDialect) -> Result<String, GunmetalError> {
+    let mut exports = String::new();
+    for (key, value) in open_vault(passphrase)? {
+        exports.push_str(&set_line(&key, &value, dialect));
+        exports.push('\n');
+    }
+    return Ok(exports);
+}
+
+/// Decrypts the vault
+/// ### Returns
+/// The secrets by name (none if there's no vault yet), or an error if the passphrase is wrong or the file can't be read
+fn open_vault(passphrase: &str) -> Result<BTreeMap<String, String>, GunmetalError> {
+    return open_vault_at(&vault_path()?.to_string_lossy(), passphrase);
+}
+
+/// Decrypts the vault at a path, see `open_vault`
+fn open_vault_at(path: &str, passphrase: &str) -> Result<BTreeMap<String, String>, GunmetalError> {
+    if passphrase.is_empty() {
+        return Err(GunmetalError::InvalidInput(ErrorInfo::new(
+            vault_empty_passphrase_err!(),
+        )));
+    }
+    if !Path::new(path).exists() {
+        return Ok(BTreeMap::new());
+    }
+    let read_err = || GunmetalError::ParseError(ErrorInfo::new(vault_read_err!(path)).path(path));
+
+    let json = fs::read_to_string(path).map_err(|err| {
+        GunmetalError::from_io(
+            GunmetalError::ReadFailed,
+            GunmetalError::ReadFailed,
+            ErrorInfo::new(vault_read_err!(path)).path(path),
+            &err,
+        )
+    })?;
+    let file: VaultFile = serde_json::from_str(&json).map_err(|_| read_err())?;
+    let salt = from_hex(&file.salt).ok_or_else(read_err)?;
+    let nonce = from_hex(&file.nonce).ok_or_else(read_err)?;
+    let ciphertext = from_hex(&file.ciphertext).ok_or_else(read_err)?;
+    if file.version != VAULT_VERSION || nonce.len() != 24 {
+        return Err(read_err());
+    }
+
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    // a wrong passphrase gives a wrong key, which fails the authentication check
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_err(|_| {
+            GunmetalError::InvalidInput(ErrorInfo::new(vault_passphrase_err!(path)).path(path))
+        })?;
+    return serde_json::from_slice(&plaintext).map_err(|_| read_err());
+}
+
+/// Encrypts the secrets and saves them to the vault, with a new salt and nonce each time
+fn seal_vault(secrets: &BTreeMap<String, String>, passphrase: &str) -> Result<(), GunmetalError> {
+    return seal_vault_at(&vault_path()?.to_string_lossy(), secrets, passphrase);
+}
+
+/// Encrypts the secrets and saves them to the vault at a path, see `seal_vault`
+fn seal_vault_at(
+    path: &str,
+    secrets: &BTreeMap<String, String>,
+    passphrase: &str,
+) -> Result<(), GunmetalError> {
+    let write_err = |details: String| {
+        GunmetalError::WriteFailed(
+            ErrorInfo::new(make_file_err!(path))
+                .path(path)
+                .details(details),
+        )
+    };
+
+    let mut salt = [0u8; SALT_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let plaintext = serde_json::to_vec(secrets).map_err(|err| write_err(err.to_string()))?;
+    let ciphertext = XChaCha20Poly1305::new(&derive_key(passphrase, &salt)?)
+        .encrypt(&nonce, plaintext.as_ref())
+        .map_err(|err| write_err(err.to_string()))?;
+
+    let file = VaultFile {
+        version: VAULT_VERSION,
+        salt: to_hex(&salt),
+        nonce: to_hex(&nonce),
+        ciphertext: to_hex(&ciphertext),
+    };
+    let json = serde_json::to_string_pretty(&file).map_err(|err| write_err(err.to_string()))?;
+    // only the owner may read the vault, from the moment the file is made
+    return write_atomically_with_mode(path, &json, 0o600);
+}
+
+/// The vault and the settings as they were before a change, to put back if a later step of it fails
+struct Previous {
+    /// contents of the vault file, None if there was none
+    vault: Option<String>,
+    settings: Settings,
+}
+
+impl Previous {
+    fn save() -> Result<Previous, GunmetalError> {
+        let path = vault_path()?.to_string_lossy().to_string();
+        let vault = match fs::read_to_string(&path) {
+            Ok(vault) => Some(vault),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                return Err(GunmetalError::from_io(
+                    GunmetalError::ReadFailed,
+                    GunmetalError::ReadFailed,
+                    ErrorInfo::new(vault_read_err!(&path)).path(&path),
+                    &err,
+                ))
+            }
+        };
+        return Ok(Previous {
+            vault,
+            settings: get_settings()?,
+        });
+    }
+
+    /// Puts the vault and the settings back, noting in the log anything that can't be
+    fn restore(&self) {
+        let result = vault_path().and_then(|path| {
+            let path = path.to_string_lossy().to_string();
+            return match &self.vault {
+                Some(vault) => write_atomically_with_mode(&path, vault, 0o600),
+                None => fs::remove_file(&path).or_else(|err| match err.kind() {
+                    io::ErrorKind::NotFound => Ok(()),
+                    _ => Err(GunmetalError::WriteFailed(
+                        ErrorInfo::new(make_file_err!(&path)).path(&path).io(&err),
+                    )),
+                }),
+            };
+        });
+        if let Err(err) = result {
+            log::error!("could not roll back the vault: {}", err);
+        }
+        let result = get_config_path()
+            .and_then(|dir| save_settings(&format!("{}/settings.json", dir), &self.settings));
+        if let Err(err) = result {
+            log::error!("could not roll back the settings: {}", err);
+        }
+    }
+}
+
+/// Derives the vault's key from the passphrase with Argon2id
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key, GunmetalError> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| GunmetalError::Other(ErrorInfo::new(vault_key_err!()).details(err)))?;
+    return Ok(key);
+}
+
+/// Changes the shell profile, the same way `remove_var` does: under a lock, checked against what was loaded,
+/// and with a backup taken first
+/// ### Arguments
+/// - update: works out the new contents from the current ones
+/// ### Returns
+/// The diff of the change when doing a dry run, otherwise the profile's contents before the change
+fn edit_profile(
+    path: &str,
+    dry_run: bool,
+    update: impl Fn(&str) -> Result<String, GunmetalError>,
+) -> Result<String, GunmetalError> {
+    if dry_run {
+        let original = fs::read_to_string(path).map_err(|err| profile_read_err(path, &err))?;
+        return Ok(unified_diff(path, &original, &update(&original)?));
+    }
+
+    let mut file = LockedFile::open(path)?;
+    let original = file.read_to_string()?;
+    check_unchanged(path, &original)?;
+    let updated = update(&original)?;
+
+    try_backup(path, &original);
+    file.replace(&updated)?;
+    remember(path, &updated);
+    return Ok(original);
+}
+
+/// The profile once a variable is secret: its assignments are gone and the loader line loads it instead
+fn marked_profile(content: &str, key: &str, path: &str) -> Result<String, GunmetalError> {
+    let updated = remove_assignments(content, key, path)?;
+    return Ok(with_loader(
+        &updated,
+        ShellDialect::from_profile_path(path),
+        true,
+    ));
+}
+
+/// The profile once a variable is plain again: it's assigned its value, and the loader line stays only
+/// if other secrets are left
+fn unmarked_profile(
+    content: &str,
+    key: &str,
+    value: &str,
+    dialect: ShellDialect,
+    secrets_left: bool,
+) -> String {
+    let updated = with_loader(content, dialect, secrets_left);
+    return with_line(&updated, &set_line(key, value, dialect));
+}
+
+/// Adds the loader line to the end of a profile if it's wanted and not there yet, or removes it if it isn't wanted
+fn with_loader(content: &str, dialect: ShellDialect, wanted: bool) -> String {
+    let loader = loader_line(dialect);
+    let present = content.lines().any(|line| line.trim() == loader);
+    if wanted && !present {
+        return with_line(content, loader);
+    }
+    if !wanted && present {
+        return content
+            .split('\n')
+            .filter(|line| line.trim() != loader)
+            .collect::<Vec<&str>>()
+            .join("\n");
+    }
+    return String::from(content);
+}
+
+/// Adds a line to the end of a profile, keeping its trailing newline if it has one
+fn with_line(content: &str, line: &str) -> String {
+    return match content.strip_suffix('\n') {
+        Some(content) => format!("{}\n{}\n", content, line),
+        None if content.is_empty() => String::from(line),
+        None => format!("{}\n{}", content, line),
+    };
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    return bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 {
+        return None;
+    }
+    return (0..text.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes an empty directory for a test, named after it so tests running at the same time don't share one
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gunmetal-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        return dir;
+    }
+
+    #[test]
+    fn opens_what_it_sealed() {
+        let dir = test_dir("vault-round-trip");
+        let path = dir.join("vault.json").to_string_lossy().to_string();
+        let mut secrets = BTreeMap::new();
+        secrets.insert(
+            String::from("API_TOKEN"),
+            String::from("it's a \"secret\"\n"),
+        );
+        secrets.insert(String::from("DB_PASSWORD"), String::from("hunter2"));
+
+        seal_vault_at(&path, &secrets, "correct horse").unwrap();
+        assert_eq!(open_vault_at(&path, "correct horse").unwrap(), secrets);
+        // the values never reach the file in plain text
+        assert!(!fs::read_to_string(&path).unwrap().contains("hunter2"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_a_wrong_passphrase() {
+        let dir = test_dir("vault-passphrase");
+        let path = dir.join("vault.json").to_string_lossy().to_string();
+        let mut secrets = BTreeMap::new();
+        secrets.insert(String::from("API_TOKEN"), String::from("abc"));
+        seal_vault_at(&path, &secrets, "right").unwrap();
+
+        let err = open_vault_at(&path, "wrong").unwrap_err();
+        assert_eq!(err.code(), "invalid_input");
+        assert_eq!(err.info().message, vault_passphrase_err!(&path));
+        let err = open_vault_at(&path, "").unwrap_err();
+        assert_eq!(err.info().message, vault_empty_passphrase_err!());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn opens_an_empty_vault_when_there_is_none() {
+        let dir = test_dir("vault-missing");
+        let path = dir.join("vault.json").to_string_lossy().to_string();
+        assert!(open_vault_at(&path, "anything").unwrap().is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unmarking_puts_the_profile_line_back() {
+        let profile = "export EDITOR='vim'\nexport API_TOKEN='abc'\n";
+        let marked = marked_profile(profile, "API_TOKEN", "/home/me/.bashrc").unwrap();
+        assert_eq!(
+            marked,
+            format!(
+                "export EDITOR='vim'\n{}\n",
+                loader_line(ShellDialect::Posix)
+            )
+        );
+        let unmarked = unmarked_profile(&marked, "API_TOKEN", "abc", ShellDialect::Posix, false);
+        assert_eq!(unmarked, profile);
+    }
+
+    #[test]
+    fn keeps_the_loader_while_other_secrets_are_left() {
+        let marked = format!("export EDITOR='vim'\n{}\n", loader_line(ShellDialect::Fish));
+        let unmarked = unmarked_profile(&marked, "API_TOKEN", "abc", ShellDialect::Fish, true);
+        assert!(unmarked.contains(loader_line(ShellDialect::Fish)));
+        assert!(unmarked.ends_with("set -gx API_TOKEN 'abc'\n"));
+    }
+}
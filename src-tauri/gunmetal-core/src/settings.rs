@@ -0,0 +1,256 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{self};
+use std::path::Path;
+
+use crate::consts_and_errors::*;
+use crate::file_guard::write_atomically;
+use crate::fs_root::{open_root, resolve_in_root};
+use crate::i18n::available_locales;
+use crate::shell_quote::is_name;
+use crate::system_scope::Scope;
+
+/// Version of the settings written by this build. Bump it and add a step to `migrate` when the layout changes.
+pub const SETTINGS_VERSION: u64 = 2;
+
+/// Everything stored in settings.json. Missing fields are filled in with their defaults.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Settings {
+    /// layout version of the file, see `migrate`
+    pub version: u64,
+    /// shells the user has, ex: "/bin/zsh"
+    pub shells: Vec<String>,
+    /// profile that variables are read from and added to
+    pub shell_profile: String,
+    /// every profile the user has, including the active one
+    pub profiles: Vec<String>,
+    /// where `add_var` writes when it isn't given a scope
+    pub write_target: Scope,
+    /// how many backups of a file to keep before deleting the oldest
+    pub backup_retention: u32,
+    /// variables that are (true) or aren't (false) lists of ':' separated values, overriding the default of splitting everything
+    pub list_overrides: BTreeMap<String, bool>,
+    /// variables whose values are kept in the encrypted vault and masked everywhere, see `secrets`
+    pub secrets: Vec<String>,
+    /// if true, variables are only shown and nothing is written
+    pub read_only: bool,
+    /// language of the app's messages, ex: "de". Empty to use the system's language
+    pub locale: String,
+    /// directory to use as `/` for profiles, home directories and files under /etc, ex: a mounted disk image.
+    /// Empty for the real root. Paths in the other settings are inside this root.
+    pub root: String,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        return Settings {
+            version: SETTINGS_VERSION,
+            shells: Vec::new(),
+            shell_profile: String::new(),
+            profiles: Vec::new(),
+            write_target: Scope::User,
+            backup_retention: 10,
+            list_overrides: BTreeMap::new(),
+            secrets: Vec::new(),
+            read_only: false,
+            locale: String::new(),
+            root: String::new(),
+        };
+    }
+}
+
+impl Settings {
+    /// Checks if a variable's value should be split into entries
+    pub fn is_list(&self, key: &str) -> bool {
+        return *self.list_overrides.get(key).unwrap_or(&true);
+    }
+
+    /// Splits a variable's value into entries, unless it's overridden as a single value
+    pub fn split_entries(&self, key: &str, value: &str) -> Vec<String> {
+        if !self.is_list(key) {
+            return vec![String::from(value)];
+        }
+        return value.split(':').map(str::to_string).collect();
+    }
+}
+
+/// Reads the settings file, upgrading it first if it was written by an older version
+/// ### Arguments
+/// - settings_path: path to settings.json
+/// ### Returns
+/// The settings, or an error if the file can't be read, isn't valid JSON or is from a newer version
+pub fn load_settings(settings_path: &str) -> Result<Settings, GunmetalError> {
+    let settings_text: String = fs::read_to_string(settings_path).map_err(|err| {
+        GunmetalError::from_io(
+            GunmetalError::SettingsMissing,
+            GunmetalError::ReadFailed,
+            ErrorInfo::new(settings_read_error!(&settings_path)).path(settings_path),
+            &err,
+        )
+    })?;
+    let raw: Value = serde_json::from_str(&settings_text).map_err(|err| {
+        GunmetalError::ParseError(
+            ErrorInfo::new(json_parse_err!())
+                .path(settings_path)
+                .details(err),
+        )
+    })?;
+
+    let (migrated, upgraded) = migrate(raw).map_err(|err| match err {
+        // a parse error doesn't know which file it came from
+        GunmetalError::ParseError(info) => GunmetalError::ParseError(info.path(settings_path)),
+        err => err,
+    })?;
+    let settings: Settings = serde_json::from_value(migrated).map_err(|err| {
+        GunmetalError::ParseError(
+            ErrorInfo::new(json_parse_err!())
+                .path(settings_path)
+                .details(err),
+        )
+    })?;
+
+    // save the upgraded file, so the migration only runs once
+    if upgraded {
+        save_settings(settings_path, &settings)?;
+    }
+    return Ok(settings);
+}
+
+/// Writes the settings file. The old file is replaced in one step, so it's never left half-written.
+pub fn save_settings(settings_path: &str, settings: &Settings) -> Result<(), GunmetalError> {
+    let json_string = serde_json::to_string_pretty(settings).map_err(|err| {
+        GunmetalError::ParseError(
+            ErrorInfo::new(json_parse_err!())
+                .path(settings_path)
+                .details(err),
+        )
+    })?;
+    return write_atomically(settings_path, &json_string);
+}
+
+/// Checks settings sent from the app before they're saved
+/// ### Returns
+/// Nothing if every setting is usable, otherwise an error naming the first bad setting
+pub fn validate_settings(settings: &Settings) -> Result<(), GunmetalError> {
+    if settings.version > SETTINGS_VERSION {
+        return Err(GunmetalError::Unsupported(ErrorInfo::new(
+            settings_version_err!(settings.version, SETTINGS_VERSION),
+        )));
+    }
+    // the other paths are inside the root, so it's checked first
+    let root = open_root(&settings.root).map_err(|err| {
+        GunmetalError::InvalidInput(
+            ErrorInfo::new(invalid_setting_err!(
+                "root",
+                tr!("reason-root-not-dir", path = &settings.root)
+            ))
+            .path(&settings.root)
+            .details(err),
+        )
+    })?;
+    let in_root = |path: &str| -> Result<String, GunmetalError> {
+        return match &root {
+            Some(root) => resolve_in_root(root, path),
+            None => Ok(String::from(path)),
+        };
+    };
+    // a blank profile just means it hasn't been chosen yet
+    if !settings.shell_profile.is_empty() {
+        check_readable("shell_profile", &in_root(&settings.shell_profile)?)?;
+    }
+    for profile in &settings.profiles {
+        check_readable("profiles", &in_root(profile)?)?;
+    }
+    for shell in &settings.shells {
+        if !Path::new(&in_root(shell)?).is_file() {
+            return Err(GunmetalError::InvalidInput(
+                ErrorInfo::new(invalid_setting_err!(
+                    "shells",
+                    tr!("reason-shell-missing", path = shell)
+                ))
+                .path(shell),
+            ));
+        }
+    }
+    if !settings.locale.is_empty() && !available_locales().contains(&settings.locale.as_str()) {
+        return Err(GunmetalError::InvalidInput(ErrorInfo::new(
+            invalid_setting_err!(
+                "locale",
+                tr!("reason-unknown-locale", locale = &settings.locale)
+            ),
+        )));
+    }
+    for key in settings.list_overrides.keys() {
+        if !is_name(key) {
+            return Err(GunmetalError::InvalidInput(ErrorInfo::new(
+                invalid_setting_err!("list_overrides", tr!("reason-not-a-name", key = key)),
+            )));
+        }
+    }
+    for key in &settings.secrets {
+        if !is_name(key) {
+            return Err(GunmetalError::InvalidInput(ErrorInfo::new(
+                invalid_setting_err!("secrets", tr!("reason-not-a-name", key = key)),
+            )));
+        }
+    }
+    return Ok(());
+}
+
+/// Checks that a file in the settings exists and can be opened
+fn check_readable(field: &str, path: &str) -> Result<(), GunmetalError> {
+    if let Err(err) = fs::File::open(path) {
+        return Err(GunmetalError::InvalidInput(
+            ErrorInfo::new(invalid_setting_err!(
+                field,
+                tr!("reason-unreadable", path = path, error = err)
+            ))
+            .path(path)
+            .io(&err),
+        ));
+    }
+    return Ok(());
+}
+
+/// Upgrades the raw settings, one version at a time, to the current layout
+/// ### Returns
+/// The upgraded settings, and whether anything was changed
+fn migrate(mut raw: Value) -> Result<(Value, bool), GunmetalError> {
+    let fields = match raw.as_object_mut() {
+        Some(fields) => fields,
+        None => return Err(GunmetalError::ParseError(ErrorInfo::new(json_parse_err!()))),
+    };
+    // the first settings files only had "shell_profile", and no version
+    let mut version = fields.get("version").and_then(Value::as_u64).unwrap_or(1);
+    if version > SETTINGS_VERSION {
+        return Err(GunmetalError::Unsupported(ErrorInfo::new(
+            settings_version_err!(version, SETTINGS_VERSION),
+        )));
+    }
+    let upgraded = version < SETTINGS_VERSION;
+
+    while version < SETTINGS_VERSION {
+        // one arm per version, so it stays a match as versions are added
+        #[allow(clippy::single_match)]
+        match version {
+            // 1 -> 2: the profile is also added to the new profile list
+            1 => {
+                let profile = fields
+                    .get("shell_profile")
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string();
+                if !profile.is_empty() {
+                    fields.insert(String::from("profiles"), Value::from(vec![profile]));
+                }
+            }
+            _ => (),
+        }
+        version += 1;
+        fields.insert(String::from("version"), Value::from(version));
+    }
+
+    return Ok((raw, upgraded));
+}
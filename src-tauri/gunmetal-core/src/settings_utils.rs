@@ -0,0 +1,255 @@
+use serde_json::Value;
+use std::{
+    fs::{self},
+    path::PathBuf,
+};
+
+use crate::consts_and_errors::*;
+use crate::fs_root::rooted;
+use crate::settings::{load_settings, save_settings, Settings};
+use crate::shell_detect::detect_settings;
+
+/// Check if a file exists, like `path_exists`, given its full path
+pub fn path_exists_combined_path(path: &str, make_file: bool) -> Result<(), GunmetalError> {
+    let path_buf = PathBuf::from(path);
+    let path_to_dir = path_buf
+        .parent()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let filename = path_buf
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| String::from(path));
+    return path_exists(&path_to_dir, &filename, make_file);
+}
+
+/// Check if a file exists, and if not, make one
+/// ### Arguments:
+/// - path_to_dir: path to directory (do not include "/")
+/// - filename: name of file
+/// ### Returns
+/// Either nothing if the file exists or was successfully made, or an error
+pub fn path_exists(
+    path_to_dir: &str,
+    filename: &str,
+    make_file: bool,
+) -> Result<(), GunmetalError> {
+    // Add filename to make full string
+    let mut full_path = PathBuf::from(&path_to_dir);
+    full_path.push(filename);
+
+    log::debug!("checking for {}", full_path.to_string_lossy());
+
+    if full_path.exists() {
+        return Ok(());
+    } else if !make_file {
+        // if we don't wanna make file, return error saying file doesn't exist
+        // convert file name to string. if error, return error
+        let pathstr_option = &full_path.to_str();
+        let info = match pathstr_option {
+            Some(pathstr) => ErrorInfo::new(tr!("file-missing-err", path = pathstr)).path(*pathstr),
+            None => ErrorInfo::new(tr!("file-name-unreadable-err")),
+        };
+        return Err(GunmetalError::ReadFailed(ErrorInfo {
+            os_error: Some(std::io::ErrorKind::NotFound),
+            ..info
+        }));
+    }
+
+    // from here, we're making the file
+
+    // check if directory exists, if not make the directory
+    if !path_to_dir.is_empty() && !PathBuf::from(&path_to_dir).is_dir() {
+        fs::create_dir_all(path_to_dir).map_err(|err| {
+            GunmetalError::from_io(
+                GunmetalError::WriteFailed,
+                GunmetalError::WriteFailed,
+                ErrorInfo::new(mkdir_err!(path_to_dir)).path(path_to_dir),
+                &err,
+            )
+        })?;
+    }
+    // generate the JSON, and return error empty file if no shell profile could be found for it
+    match &full_path.to_str() {
+        Some(pathstr) => {
+            fs::File::create(&full_path).map_err(|err| {
+                GunmetalError::from_io(
+                    GunmetalError::WriteFailed,
+                    GunmetalError::WriteFailed,
+                    ErrorInfo::new(make_file_err!(pathstr)).path(*pathstr),
+                    &err,
+                )
+            })?;
+            let settings = generate_json(pathstr)?;
+            if settings.shell_profile.is_empty() {
+                return Err(GunmetalError::SettingsMissing(
+                    ErrorInfo::new(empty_settings_err!()).path(*pathstr),
+                ));
+            }
+            return Ok(());
+        }
+        None => {
+            return Err(GunmetalError::WriteFailed(ErrorInfo::new(tr!(
+                "file-path-unreadable-err"
+            ))))
+        }
+    }
+}
+
+/// Generates the JSON file on first run, filled in with the user's shells and profiles
+/// ### Returns
+/// The settings that were written. The shell profile is blank if no startup file was found for the login shell.
+fn generate_json(settings_path: &str) -> Result<Settings, GunmetalError> {
+    let settings = detect_settings();
+    save_settings(settings_path, &settings)?;
+    return Ok(settings);
+}
+
+/// Returns the path of the configuration directory, in order:
+/// - `$GUNMETAL_CONFIG`, if it's set
+/// - `$XDG_CONFIG_HOME/gunmetal`
+/// - `~/.config/gunmetal` (or the OS's config directory on Windows)
+///
+/// Settings from the directories older versions used are moved over the first time this is called.
+pub fn get_config_path() -> Result<String, GunmetalError> {
+    let config_path = match std::env::var("GUNMETAL_CONFIG") {
+        Ok(path) if !path.is_empty() => path,
+        _ => {
+            let config_home = match std::env::var("XDG_CONFIG_HOME") {
+                // relative paths are invalid in XDG_CONFIG_HOME and should be ignored
+                Ok(path) if path.starts_with('/') => PathBuf::from(path),
+                _ if cfg!(target_os = "windows") => dirs::config_dir().ok_or_else(|| {
+                    GunmetalError::SettingsMissing(ErrorInfo::new(no_config_dir_err!()))
+                })?,
+                _ => dirs::home_dir()
+                    .ok_or_else(|| {
+                        GunmetalError::SettingsMissing(ErrorInfo::new(no_config_dir_err!()))
+                    })?
+                    .join(".config"),
+            };
+            config_home
+                .join(config_dir_name!())
+                .to_string_lossy()
+                .to_string()
+        }
+    };
+
+    migrate_legacy_config(&config_path)?;
+    return Ok(config_path);
+}
+
+/// Moves settings.json from where older versions kept it into the config directory, if there isn't one there yet.
+/// Older versions used ~/.config on macOS, and ~/etc or /etc on Linux.
+fn migrate_legacy_config(config_path: &str) -> Result<(), GunmetalError> {
+    let settings_path = PathBuf::from(config_path).join("settings.json");
+    if settings_path.exists() {
+        return Ok(());
+    }
+
+    // without a home directory only /etc is left to look in
+    let mut legacy_dirs: Vec<PathBuf> = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        legacy_dirs.push(home.join(".config").join(legacy_config_dir_name!()));
+        legacy_dirs.push(home.join("etc").join(legacy_config_dir_name!()));
+    }
+    legacy_dirs.push(PathBuf::from("/etc").join(legacy_config_dir_name!()));
+    let legacy_path = match legacy_dirs
+        .iter()
+        .map(|dir| dir.join("settings.json"))
+        .find(|path| path.is_file())
+    {
+        Some(legacy_path) => legacy_path,
+        None => return Ok(()),
+    };
+
+    fs::create_dir_all(config_path).map_err(|err| {
+        GunmetalError::from_io(
+            GunmetalError::WriteFailed,
+            GunmetalError::WriteFailed,
+            ErrorInfo::new(mkdir_err!(config_path)).path(config_path),
+            &err,
+        )
+    })?;
+    // files under /etc can't be removed without root, so those are copied instead
+    if fs::rename(&legacy_path, &settings_path).is_err() {
+        fs::copy(&legacy_path, &settings_path).map_err(|err| {
+            let settings_path = settings_path.to_string_lossy().to_string();
+            GunmetalError::from_io(
+                GunmetalError::SettingsMissing,
+                GunmetalError::WriteFailed,
+                ErrorInfo::new(make_file_err!(&settings_path)).path(settings_path),
+                &err,
+            )
+        })?;
+    }
+    return Ok(());
+}
+
+/// Reads the settings from the config directory, making a new settings file if there isn't one
+/// ### Returns
+/// The settings, or an error if the settings file is missing or can't be read
+pub fn get_settings() -> Result<Settings, GunmetalError> {
+    // find settings file, return error if there isn't one
+    let config_path = get_config_path()?;
+    path_exists(config_path.as_str(), "settings.json", true)?;
+
+    return load_settings(format!("{}/settings.json", config_path).as_str());
+}
+
+/// Finds the shell profile set in the settings, and checks that it exists
+/// ### Returns
+/// The path to the shell profile (under the root, if one is set), or an error if the settings or the profile are missing
+pub fn get_shell_profile_path() -> Result<String, GunmetalError> {
+    // get the path to the shell profile
+    let shell_profile_path = get_settings()?.shell_profile;
+    if shell_profile_path.is_empty() {
+        return Err(GunmetalError::SettingsMissing(ErrorInfo::new(
+            empty_settings_err!(),
+        )));
+    }
+    let shell_profile_path = rooted(&shell_profile_path)?;
+    // check if shell profile path exists, if not return error
+    path_exists_combined_path(&shell_profile_path, false)
+        .map_err(|err| GunmetalError::ProfileNotFound(err.info().clone()))?;
+
+    return Ok(shell_profile_path);
+}
+
+/// Checks that the settings allow writing. Settings that can't be read don't block anything here,
+/// since the write will run into the same error when it looks for the profile.
+pub fn check_writable() -> Result<(), GunmetalError> {
+    match get_config_path().and_then(|path| load_settings(&format!("{}/settings.json", path))) {
+        Ok(settings) if settings.read_only => {
+            return Err(GunmetalError::ReadOnly(ErrorInfo::new(read_only_err!())))
+        }
+        _ => return Ok(()),
+    }
+}
+
+/// Reads the JSON settings file, finds the value for a setting, and returns it
+/// This program re-reads the JSON file every time a setting is needed in case the file is edited during runtime
+/// ### Arguments
+/// - settings_path: path to JSON file
+/// - key: the setting that should be gathered
+/// ### Returns:
+/// Either a String with the setting (settings that aren't strings are returned as JSON) or an error
+pub fn gather_setting(settings_path: &str, key: &str) -> Result<String, GunmetalError> {
+    let settings = load_settings(settings_path)?;
+    let fields = serde_json::to_value(&settings).map_err(|err| {
+        GunmetalError::ParseError(
+            ErrorInfo::new(json_parse_err!())
+                .path(settings_path)
+                .details(err),
+        )
+    })?;
+
+    match fields.get(key) {
+        Some(Value::String(setting)) => return Ok(setting.to_owned()),
+        Some(setting) => return Ok(setting.to_string()),
+        None => {
+            return Err(GunmetalError::SettingsMissing(
+                ErrorInfo::new(json_parse_err!()).path(settings_path),
+            ))
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use serde::Serialize;
+use std::fs::{self};
+use std::path::Path;
+
+use crate::fs_root::{root, rooted};
+use crate::settings::Settings;
+
+/// A shell installed on the system, with the startup files the user has for it
+#[derive(Serialize, Clone, Debug)]
+pub struct DetectedShell {
+    /// path of the shell, ex: "/bin/zsh"
+    pub path: String,
+    /// if this is the user's login shell
+    pub login: bool,
+    /// startup files for this shell that exist, most preferred for variables first
+    pub startup_files: Vec<String>,
+}
+
+/// Lists the shells installed on the system and the startup files found for each,
+/// so the settings page can suggest profiles
+pub fn detect_shells() -> Vec<DetectedShell> {
+    let login = login_shell();
+    let mut shells = installed_shells();
+    // the login shell might not be in /etc/shells (ex: installed with a package manager)
+    if let Some(login) = &login {
+        if !shells.contains(login) {
+            shells.insert(0, login.to_owned());
+        }
+    }
+
+    return shells
+        .into_iter()
+        .map(|path| DetectedShell {
+            login: login.as_ref() == Some(&path),
+            startup_files: startup_files(&path),
+            path,
+        })
+        .collect();
+}
+
+/// Builds the settings for a first run from the detected shells, with the login shell's main startup file as the profile
+pub fn detect_settings() -> Settings {
+    let shells = detect_shells();
+    let mut settings = Settings::default();
+
+    for shell in &shells {
+        for file in &shell.startup_files {
+            if !settings.profiles.contains(file) {
+                settings.profiles.push(file.to_owned());
+            }
+        }
+    }
+    if let Some(login) = shells.iter().find(|shell| shell.login) {
+        if let Some(file) = login.startup_files.first() {
+            settings.shell_profile = file.to_owned();
+        }
+    }
+    settings.shells = shells.into_iter().map(|shell| shell.path).collect();
+
+    return settings;
+}
+
+/// Finds the user's login shell, from $SHELL or else the user's entry in /etc/passwd.
+/// Under a root, $SHELL is this machine's, so only /etc/passwd is used.
+fn login_shell() -> Option<String> {
+    if let Ok(shell) = std::env::var("SHELL") {
+        if !shell.is_empty() && matches!(root(), Ok(None)) {
+            return Some(shell);
+        }
+    }
+    return user_entry()
+        .map(|entry| entry.shell)
+        .filter(|shell| !shell.is_empty());
+}
+
+/// Finds the user's home directory, as seen from inside the root if one is set.
+/// Under a root, this machine's home may not exist there, so the user's entry in the root's /etc/passwd is used.
+fn home_dir() -> Option<String> {
+    if matches!(root(), Ok(None)) {
+        return dirs::home_dir().map(|home| home.to_string_lossy().to_string());
+    }
+    return user_entry()
+        .map(|entry| entry.home)
+        .filter(|home| !home.is_empty());
+}
+
+/// The parts of a user's /etc/passwd entry that shell detection needs
+struct PasswdEntry {
+    home: String,
+    shell: String,
+}
+
+/// Reads the user's entry in /etc/passwd (under the root if one is set), found by name,
+/// or without a root also by home directory
+fn user_entry() -> Option<PasswdEntry> {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("LOGNAME"))
+        .unwrap_or_default();
+    // this machine's home says nothing about the users inside a root
+    let home = match root() {
+        Ok(None) => dirs::home_dir().map(|home| home.to_string_lossy().to_string()),
+        _ => None,
+    };
+    let passwd = fs::read_to_string(rooted("/etc/passwd").ok()?).ok()?;
+    return find_passwd_entry(&passwd, &user, home.as_deref());
+}
+
+/// Finds a user's entry in the contents of /etc/passwd, by name or home directory
+fn find_passwd_entry(passwd: &str, user: &str, home: Option<&str>) -> Option<PasswdEntry> {
+    // entries look like "name:password:uid:gid:info:home:shell"
+    for line in passwd.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields.len() < 7 {
+            continue;
+        }
+        if (!user.is_empty() && fields[0] == user) || home == Some(fields[5]) {
+            return Some(PasswdEntry {
+                home: String::from(fields[5]),
+                shell: String::from(fields[6]),
+            });
+        }
+    }
+    return None;
+}
+
+/// Lists the shells in /etc/shells that are installed, skipping comments and duplicates
+fn installed_shells() -> Vec<String> {
+    let mut shells: Vec<String> = Vec::new();
+    let content = rooted("/etc/shells")
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .unwrap_or_default();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || shells.iter().any(|shell| shell == line) {
+            continue;
+        }
+        if is_file_in_root(line) {
+            shells.push(String::from(line));
+        }
+    }
+    return shells;
+}
+
+/// Lists the startup files of a shell that exist in the user's home, the file best suited for variables first
+fn startup_files(shell: &str) -> Vec<String> {
+    let home = match home_dir() {
+        Some(home) => home,
+        None => return Vec::new(),
+    };
+    // under a root, these are set for this machine's shells, not the ones inside the root
+    let env_dir = |name: &str| match root() {
+        Ok(None) => std::env::var(name).ok().filter(|dir| !dir.is_empty()),
+        _ => None,
+    };
+    return startup_candidates(shell, &home, env_dir("ZDOTDIR"), env_dir("XDG_CONFIG_HOME"))
+        .into_iter()
+        .filter(|file| is_file_in_root(file))
+        .collect();
+}
+
+/// Lists the startup files a shell may read, the file best suited for variables first
+/// ### Arguments
+/// - shell: path of the shell
+/// - home: the user's home directory
+/// - zdotdir, config_home: $ZDOTDIR and $XDG_CONFIG_HOME, if they're set
+fn startup_candidates(
+    shell: &str,
+    home: &str,
+    zdotdir: Option<String>,
+    config_home: Option<String>,
+) -> Vec<String> {
+    let name = shell.rsplit('/').next().unwrap_or(shell);
+    return match name {
+        "zsh" => {
+            // zsh reads its startup files from $ZDOTDIR if it's set
+            let dir = zdotdir.unwrap_or_else(|| String::from(home));
+            [".zshrc", ".zshenv", ".zprofile", ".zlogin"]
+                .iter()
+                .map(|file| format!("{}/{}", dir, file))
+                .collect()
+        }
+        "bash" => [".bashrc", ".bash_profile", ".bash_login", ".profile"]
+            .iter()
+            .map(|file| format!("{}/{}", home, file))
+            .collect(),
+        "fish" => {
+            let config_dir = config_home.unwrap_or_else(|| format!("{}/.config", home));
+            vec![format!("{}/fish/config.fish", config_dir)]
+        }
+        "ksh" | "mksh" => [".kshrc", ".profile"]
+            .iter()
+            .map(|file| format!("{}/{}", home, file))
+            .collect(),
+        // sh, dash and other POSIX shells only read ~/.profile as a login shell
+        _ => vec![format!("{}/.profile", home)],
+    };
+}
+
+/// Checks if a file exists, under the root if one is set. Paths in the settings stay as seen from inside the root.
+fn is_file_in_root(path: &str) -> bool {
+    return rooted(path)
+        .map(|path| Path::new(&path).is_file())
+        .unwrap_or(false);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWD: &str =
+        "root:x:0:0:root:/root:/bin/bash\nbad line\nana:x:1000:1000::/home/ana:/usr/bin/zsh\n";
+
+    #[test]
+    fn finds_the_user_in_passwd() {
+        let entry = find_passwd_entry(PASSWD, "ana", None).unwrap();
+        assert_eq!(entry.home, "/home/ana");
+        assert_eq!(entry.shell, "/usr/bin/zsh");
+        let entry = find_passwd_entry(PASSWD, "", Some("/root")).unwrap();
+        assert_eq!(entry.shell, "/bin/bash");
+        assert!(find_passwd_entry(PASSWD, "", None).is_none());
+        assert!(find_passwd_entry(PASSWD, "bob", Some("/home/bob")).is_none());
+    }
+
+    #[test]
+    fn lists_startup_files_from_the_home_given() {
+        assert_eq!(
+            startup_candidates("/usr/bin/zsh", "/home/ana", None, None)[0],
+            "/home/ana/.zshrc"
+        );
+        assert_eq!(
+            startup_candidates("/usr/bin/zsh", "/home/ana", Some(String::from("/z")), None)[0],
+            "/z/.zshrc"
+        );
+        assert_eq!(
+            startup_candidates("fish", "/home/ana", None, None),
+            vec!["/home/ana/.config/fish/config.fish"]
+        );
+        assert_eq!(
+            startup_candidates("/bin/dash", "/home/ana", None, Some(String::from("/c"))),
+            vec!["/home/ana/.profile"]
+        );
+    }
+}
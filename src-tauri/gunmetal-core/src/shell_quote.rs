@@ -0,0 +1,767 @@
+use crate::env_backend::Position;
+
+/// Shell syntax used by a profile file, which decides how values are quoted and read back
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ShellDialect {
+    /// sh, bash, zsh, ksh and friends
+    Posix,
+    /// fish, which has its own quoting rules and `set` instead of `export`
+    Fish,
+}
+
+impl ShellDialect {
+    /// Guesses the dialect of a shell profile from its file name
+    pub fn from_profile_path(path: &str) -> ShellDialect {
+        if path.ends_with(".fish") {
+            return ShellDialect::Fish;
+        }
+        return ShellDialect::Posix;
+    }
+}
+
+/// A variable assignment read from a shell profile, with all quoting removed
+pub struct Assignment {
+    pub key: String,
+    /// the values being assigned, in order
+    pub entries: Vec<String>,
+    /// true if the assignment keeps the variable's existing value (ex: `export PATH=a:$PATH`)
+    pub keeps_existing: bool,
+    /// true if every value goes after the existing value (fish's `set --append`)
+    pub appends: bool,
+    /// source text before the first value (ex: `export PATH=`), kept as is when the line is rewritten
+    pub prefix: String,
+    /// every value as written, including references to the variable itself
+    pub parts: Vec<Part>,
+    /// source text after the last value (ex: a trailing comment)
+    pub suffix: String,
+}
+
+/// One value of an assignment, as written in the profile
+pub struct Part {
+    /// the value with quoting removed
+    pub value: String,
+    /// the value exactly as written, quotes included
+    pub raw: String,
+    /// true if the part is only a reference to the variable being assigned (`$PATH` in `PATH=a:$PATH`)
+    pub self_reference: bool,
+}
+
+impl Assignment {
+    fn new(key: &str, prefix: &str, parts: Vec<Part>, suffix: &str) -> Assignment {
+        let mut assignment = Assignment {
+            key: String::from(key),
+            entries: Vec::new(),
+            keeps_existing: false,
+            appends: false,
+            prefix: String::from(prefix),
+            parts,
+            suffix: String::from(suffix),
+        };
+        assignment.update_entries();
+        return assignment;
+    }
+
+    /// Recomputes `entries` and `keeps_existing` after the parts changed
+    fn update_entries(&mut self) {
+        // a bare reference to the variable itself means the old value is kept
+        self.keeps_existing =
+            self.keeps_existing || self.parts.iter().any(|part| part.self_reference);
+        self.entries = self
+            .parts
+            .iter()
+            .filter(|part| !part.self_reference)
+            .map(|part| part.value.clone())
+            .collect();
+    }
+
+    /// Splits the values into the ones that go before the variable's existing value and the ones that go after it,
+    /// ex: `export PATH=a:$PATH:b` puts "a" before and "b" after
+    pub fn split_around_existing(&self) -> (Vec<String>, Vec<String>) {
+        if self.appends {
+            return (Vec::new(), self.entries.clone());
+        }
+        let mut before: Vec<String> = Vec::new();
+        let mut after: Vec<String> = Vec::new();
+        let mut past_existing = false;
+        for part in &self.parts {
+            if part.self_reference {
+                past_existing = true;
+            } else if past_existing {
+                after.push(part.value.clone());
+            } else {
+                before.push(part.value.clone());
+            }
+        }
+        return (before, after);
+    }
+
+    /// Replaces the first part with the value `old` by the quoted `new`
+    /// ### Returns
+    /// false if no part has the value `old`
+    pub fn replace_value(&mut self, old: &str, new: &str, dialect: ShellDialect) -> bool {
+        match self
+            .parts
+            .iter_mut()
+            .find(|part| !part.self_reference && part.value == old)
+        {
+            Some(part) => {
+                part.value = String::from(new);
+                part.raw = quote(new, dialect);
+            }
+            None => return false,
+        }
+        self.update_entries();
+        return true;
+    }
+
+    /// Removes the first part with the given value
+    /// ### Returns
+    /// false if no part has the value
+    pub fn remove_value(&mut self, value: &str) -> bool {
+        match self
+            .parts
+            .iter()
+            .position(|part| !part.self_reference && part.value == value)
+        {
+            Some(index) => self.parts.remove(index),
+            None => return false,
+        };
+        self.update_entries();
+        return true;
+    }
+
+    /// Writes the assignment back out, untouched values keep their original quoting
+    /// ### Returns
+    /// The line, or None if the assignment has no values left and should be removed
+    pub fn render(&self, dialect: ShellDialect) -> Option<String> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let separator = match dialect {
+            ShellDialect::Posix => ":",
+            ShellDialect::Fish => " ",
+        };
+        let values: Vec<&str> = self.parts.iter().map(|part| part.raw.as_str()).collect();
+        return Some(format!(
+            "{}{}{}",
+            &self.prefix,
+            values.join(separator),
+            &self.suffix
+        ));
+    }
+}
+
+/// An assignment together with the lines of the profile it was read from
+pub struct ProfileAssignment {
+    /// index of the first line of the statement
+    pub first_line: usize,
+    /// index of the last line, only different from `first_line` if a quoted value spans lines
+    pub last_line: usize,
+    pub assignment: Assignment,
+}
+
+/// Everything read from a profile
+pub struct ParsedProfile {
+    pub assignments: Vec<ProfileAssignment>,
+    /// text of a statement that was still open (unclosed quote) when the file ended
+    pub incomplete: Option<String>,
+}
+
+/// Reasons a line could not be read as an assignment
+#[derive(Debug, PartialEq)]
+pub enum LexError {
+    /// a quote, substitution or line continuation is not closed, the assignment probably continues on the next line
+    Incomplete,
+}
+
+/// Quotes a value so the shell reads it back as exactly the same string, no matter which characters it contains
+/// ### Arguments
+/// - value: the raw value
+/// - dialect: shell syntax of the file the value goes into
+/// ### Returns
+/// The quoted value, safe to paste into a profile
+pub fn quote(value: &str, dialect: ShellDialect) -> String {
+    match dialect {
+        // nothing is special inside single quotes except the closing quote, so close, escape it and reopen
+        ShellDialect::Posix => format!("'{}'", value.replace('\'', "'\\''")),
+        // fish single quotes only treat \\ and \' as escapes
+        ShellDialect::Fish => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'")),
+    }
+}
+
+/// Builds the profile line that sets a variable to a value, replacing whatever it was
+/// ### Arguments
+/// - key: variable name
+/// - value: raw value, quoted here
+/// - dialect: shell syntax of the profile
+pub fn set_line(key: &str, value: &str, dialect: ShellDialect) -> String {
+    match dialect {
+        ShellDialect::Posix => format!("export {}={}", key, quote(value, dialect)),
+        ShellDialect::Fish => format!("set -gx {} {}", key, quote(value, dialect)),
+    }
+}
+
+/// Builds the profile line that adds a value to the front of a variable
+/// ### Arguments
+/// - key: variable name
+/// - value: raw value to add, quoted here
+/// - dialect: shell syntax of the profile
+pub fn prepend_line(key: &str, value: &str, dialect: ShellDialect) -> String {
+    match dialect {
+        ShellDialect::Posix => format!("export {}={}:${}", key, quote(value, dialect), key),
+        ShellDialect::Fish => format!("set -gx --prepend {} {}", key, quote(value, dialect)),
+    }
+}
+
+/// Builds the profile line that adds a value to the end of a variable
+/// ### Arguments
+/// - key: variable name
+/// - value: raw value to add, quoted here
+/// - dialect: shell syntax of the profile
+pub fn append_line(key: &str, value: &str, dialect: ShellDialect) -> String {
+    match dialect {
+        ShellDialect::Posix => format!("export {}=${}:{}", key, key, quote(value, dialect)),
+        ShellDialect::Fish => format!("set -gx --append {} {}", key, quote(value, dialect)),
+    }
+}
+
+/// Builds the profile line that adds a value to either end of a variable
+pub fn add_line(key: &str, value: &str, position: Position, dialect: ShellDialect) -> String {
+    match position {
+        Position::Prepend => prepend_line(key, value, dialect),
+        Position::Append => append_line(key, value, dialect),
+    }
+}
+
+/// Reads an assignment (`export KEY=...` or fish's `set -gx KEY ...`) back from a profile
+/// ### Arguments
+/// - statement: the line, or several lines joined with `\n` if a quoted value spans lines
+/// - dialect: shell syntax of the profile
+/// ### Returns
+/// - Ok(Some(assignment)) if the line assigns a variable
+/// - Ok(None) if the line is something else (comments, other commands, `export` without a value, etc.)
+/// - Err if the assignment continues past the end of the statement
+pub fn parse_assignment(
+    statement: &str,
+    dialect: ShellDialect,
+) -> Result<Option<Assignment>, LexError> {
+    match dialect {
+        ShellDialect::Posix => parse_posix_assignment(statement),
+        ShellDialect::Fish => parse_fish_assignment(statement),
+    }
+}
+
+/// Reads every assignment in a profile, joining lines while a quoted value is still open
+/// ### Arguments
+/// - content: the whole profile
+/// - dialect: shell syntax of the profile
+pub fn parse_profile(content: &str, dialect: ShellDialect) -> ParsedProfile {
+    let mut assignments: Vec<ProfileAssignment> = Vec::new();
+    // holds the start of an assignment whose quoted value spans multiple lines
+    let mut pending = String::new();
+    let mut first_line = 0;
+
+    for (index, line) in content.lines().enumerate() {
+        let statement = if pending.is_empty() {
+            first_line = index;
+            String::from(line)
+        } else {
+            format!("{}\n{}", pending, line)
+        };
+        pending.clear();
+
+        match parse_assignment(&statement, dialect) {
+            Ok(Some(assignment)) => assignments.push(ProfileAssignment {
+                first_line,
+                last_line: index,
+                assignment,
+            }),
+            Ok(None) => continue,
+            Err(LexError::Incomplete) => pending = statement,
+        }
+    }
+
+    return ParsedProfile {
+        assignments,
+        incomplete: if pending.is_empty() {
+            None
+        } else {
+            Some(pending)
+        },
+    };
+}
+
+/// Reads `export KEY=value` lines
+fn parse_posix_assignment(statement: &str) -> Result<Option<Assignment>, LexError> {
+    let trimmed = statement.trim_start();
+    let rest = match trimmed.strip_prefix("export") {
+        Some(rest) if rest.starts_with([' ', '\t']) => rest.trim_start(),
+        _ => return Ok(None),
+    };
+    // `export KEY` without a value, or flags like `export -p`
+    let (key, rhs) = match rest.split_once('=') {
+        Some((key, rhs)) if is_name(key) => (key, rhs),
+        _ => return Ok(None),
+    };
+
+    let (values, end) = lex_posix_value(rhs)?;
+    let parts = values
+        .into_iter()
+        .map(|(value, raw)| Part {
+            self_reference: is_self_reference(&raw, key),
+            value,
+            raw,
+        })
+        .collect();
+    let prefix = &statement[..statement.len() - rhs.len()];
+
+    return Ok(Some(Assignment::new(key, prefix, parts, &rhs[end..])));
+}
+
+/// Splits the right-hand side of a POSIX assignment on unquoted `:` and removes quoting
+/// ### Returns
+/// A list of (value, raw source text) pairs, one per entry, and the byte offset where the value ends
+fn lex_posix_value(rhs: &str) -> Result<(Vec<(String, String)>, usize), LexError> {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    let mut value = String::new();
+    let mut raw = String::new();
+    let mut chars = rhs.chars();
+    let mut end = rhs.len();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            // unquoted whitespace ends the value, anything after is another word or a comment
+            ' ' | '\t' | ';' | '(' | ')' | '|' | '&' | '<' | '>' => {
+                end = rhs.len() - chars.as_str().len() - ch.len_utf8();
+                break;
+            }
+            ':' => {
+                entries.push((value, raw));
+                value = String::new();
+                raw = String::new();
+                continue;
+            }
+            '\'' => {
+                raw.push(ch);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(quoted) => {
+                            raw.push(quoted);
+                            value.push(quoted);
+                        }
+                        None => return Err(LexError::Incomplete),
+                    }
+                }
+                raw.push('\'');
+            }
+            '"' => {
+                raw.push(ch);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            raw.push('\\');
+                            match chars.next() {
+                                // inside double quotes, a backslash only escapes these characters
+                                Some(escaped) if "$`\"\\".contains(escaped) => {
+                                    raw.push(escaped);
+                                    value.push(escaped);
+                                }
+                                Some('\n') => raw.push('\n'),
+                                Some(other) => {
+                                    raw.push(other);
+                                    value.push('\\');
+                                    value.push(other);
+                                }
+                                None => return Err(LexError::Incomplete),
+                            }
+                        }
+                        Some(quoted) => {
+                            raw.push(quoted);
+                            value.push(quoted);
+                        }
+                        None => return Err(LexError::Incomplete),
+                    }
+                }
+                raw.push('"');
+            }
+            '\\' => {
+                raw.push(ch);
+                match chars.next() {
+                    Some('\n') => raw.push('\n'),
+                    Some(escaped) => {
+                        raw.push(escaped);
+                        value.push(escaped);
+                    }
+                    // line continuation
+                    None => return Err(LexError::Incomplete),
+                }
+            }
+            // command substitutions can't be resolved here, so they're kept as written
+            '$' if chars.clone().next() == Some('(') => {
+                let substitution = take_substitution(&mut chars, ')')?;
+                raw.push_str(&substitution);
+                value.push_str(&substitution);
+            }
+            '`' => {
+                let substitution = take_substitution(&mut chars, '`')?;
+                raw.push_str(&substitution);
+                value.push_str(&substitution);
+            }
+            _ => {
+                raw.push(ch);
+                value.push(ch);
+            }
+        }
+    }
+    entries.push((value, raw));
+
+    return Ok((entries, end));
+}
+
+/// Copies a command substitution (`$(...)` or `` `...` ``) verbatim, the opening characters already being consumed
+/// ### Arguments
+/// - chars: iterator positioned right after `$` or the opening backtick
+/// - close: `)` for `$(...)`, or the backtick
+fn take_substitution(chars: &mut std::str::Chars, close: char) -> Result<String, LexError> {
+    let mut substitution = String::from(if close == ')' { "$" } else { "`" });
+    let mut depth = 0;
+    for ch in chars.by_ref() {
+        substitution.push(ch);
+        if close == ')' && ch == '(' {
+            depth += 1;
+        } else if ch == close {
+            depth -= 1;
+            if depth <= 0 {
+                return Ok(substitution);
+            }
+        }
+    }
+    return Err(LexError::Incomplete);
+}
+
+/// Reads fish's `set -gx KEY value...` lines
+fn parse_fish_assignment(statement: &str) -> Result<Option<Assignment>, LexError> {
+    let (words, end) = lex_fish_words(statement)?;
+    if words.first().map(|word| word.value.as_str()) != Some("set") {
+        return Ok(None);
+    }
+
+    let mut exported = false;
+    let mut keeps_existing = false;
+    let mut appends = false;
+    // index of the variable name, the values come after it
+    let mut key_index: Option<usize> = None;
+    for (index, word) in words.iter().enumerate().skip(1) {
+        let flag = word.value.as_str();
+        // flags come before the variable name
+        if !flag.starts_with('-') {
+            key_index = Some(index);
+            break;
+        }
+        if flag == "--export" || (!flag.starts_with("--") && flag.contains('x')) {
+            exported = true;
+        }
+        if flag == "--prepend" || flag == "--append" {
+            keeps_existing = true;
+            appends = flag == "--append";
+        }
+        if flag == "--erase" || flag == "-e" || flag == "--query" || flag == "-q" {
+            return Ok(None);
+        }
+    }
+
+    let key_index = match key_index {
+        Some(index) if exported && is_name(&words[index].value) => index,
+        _ => return Ok(None),
+    };
+    let key = words[key_index].value.as_str();
+    let values = &words[key_index + 1..];
+    let parts = values
+        .iter()
+        .map(|word| Part {
+            value: word.value.clone(),
+            raw: word.raw.clone(),
+            self_reference: is_self_reference(&word.raw, key),
+        })
+        .collect();
+    // an empty `set -gx KEY` still gets a space so values can be added back
+    let (prefix, suffix) = match (values.first(), values.last()) {
+        (Some(first), Some(last)) => (
+            String::from(&statement[..first.start]),
+            &statement[last.end..],
+        ),
+        _ => (
+            format!("{} ", &statement[..words[key_index].end]),
+            &statement[end..],
+        ),
+    };
+
+    let mut assignment = Assignment::new(key, &prefix, parts, suffix);
+    assignment.keeps_existing = assignment.keeps_existing || keeps_existing;
+    assignment.appends = appends;
+    return Ok(Some(assignment));
+}
+
+/// A word of a fish command line
+struct Word {
+    /// the word with quoting removed
+    value: String,
+    /// the word exactly as written
+    raw: String,
+    /// byte offsets of the word in the line
+    start: usize,
+    end: usize,
+}
+
+/// Splits a fish command line into words and removes quoting
+/// ### Returns
+/// The words, and the byte offset where the command ends (before any comment)
+fn lex_fish_words(line: &str) -> Result<(Vec<Word>, usize), LexError> {
+    let mut words: Vec<Word> = Vec::new();
+    let mut value = String::new();
+    let mut raw = String::new();
+    let mut chars = line.chars();
+    let mut start = 0;
+    let mut end = line.len();
+
+    while let Some(ch) = chars.next() {
+        let position = line.len() - chars.as_str().len() - ch.len_utf8();
+        if raw.is_empty() {
+            start = position;
+        }
+        match ch {
+            ' ' | '\t' => {
+                if !raw.is_empty() {
+                    words.push(Word {
+                        value,
+                        raw,
+                        start,
+                        end: position,
+                    });
+                    value = String::new();
+                    raw = String::new();
+                }
+                continue;
+            }
+            // comment or end of command
+            '#' | ';' if raw.is_empty() => {
+                end = position;
+                break;
+            }
+            ';' => {
+                end = position;
+                break;
+            }
+            '\'' => {
+                raw.push(ch);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => {
+                            raw.push('\\');
+                            match chars.next() {
+                                Some(escaped) if escaped == '\\' || escaped == '\'' => {
+                                    raw.push(escaped);
+                                    value.push(escaped);
+                                }
+                                Some(other) => {
+                                    raw.push(other);
+                                    value.push('\\');
+                                    value.push(other);
+                                }
+                                None => return Err(LexError::Incomplete),
+                            }
+                        }
+                        Some(quoted) => {
+                            raw.push(quoted);
+                            value.push(quoted);
+                        }
+                        None => return Err(LexError::Incomplete),
+                    }
+                }
+                raw.push('\'');
+            }
+            '"' => {
+                raw.push(ch);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            raw.push('\\');
+                            match chars.next() {
+                                Some(escaped) if "\"$\\".contains(escaped) => {
+                                    raw.push(escaped);
+                                    value.push(escaped);
+                                }
+                                Some('\n') => raw.push('\n'),
+                                Some(other) => {
+                                    raw.push(other);
+                                    value.push('\\');
+                                    value.push(other);
+                                }
+                                None => return Err(LexError::Incomplete),
+                            }
+                        }
+                        Some(quoted) => {
+                            raw.push(quoted);
+                            value.push(quoted);
+                        }
+                        None => return Err(LexError::Incomplete),
+                    }
+                }
+                raw.push('"');
+            }
+            '\\' => {
+                raw.push(ch);
+                match chars.next() {
+                    Some('\n') => raw.push('\n'),
+                    Some(escaped) => {
+                        raw.push(escaped);
+                        value.push(escaped);
+                    }
+                    // line continuation
+                    None => return Err(LexError::Incomplete),
+                }
+            }
+            _ => {
+                raw.push(ch);
+                value.push(ch);
+            }
+        }
+    }
+    if !raw.is_empty() {
+        words.push(Word {
+            value,
+            raw,
+            start,
+            end: end.min(line.len() - chars.as_str().len()),
+        });
+    }
+
+    return Ok((words, end));
+}
+
+/// Checks if the raw text of an entry is only a reference to the given variable (`$KEY`, `${KEY}`, `"$KEY"`)
+fn is_self_reference(raw: &str, key: &str) -> bool {
+    let unquoted = raw
+        .strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+        .unwrap_or(raw);
+    return unquoted == format!("${}", key) || unquoted == format!("${{{}}}", key);
+}
+
+/// Checks if a string is a shell variable name (letters, digits and underscores, not starting with a digit)
+pub fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Values with every character the quoting has to get right
+    const VALUES: [&str; 10] = [
+        "plain",
+        "two words",
+        "it's",
+        "'quoted'",
+        "say \"hi\"",
+        "$HOME and ${USER}",
+        "`whoami` and $(id)",
+        "back\\slash\\",
+        "first\nsecond",
+        "\\'mixed'\\\\",
+    ];
+
+    /// Quotes a value into a profile line and reads the value back
+    fn round_trip(value: &str, dialect: ShellDialect) -> Vec<String> {
+        let line = set_line("KEY", value, dialect);
+        let assignment = parse_assignment(&line, dialect)
+            .unwrap_or_else(|_| panic!("incomplete line: {}", line))
+            .unwrap_or_else(|| panic!("not an assignment: {}", line));
+        assert_eq!(assignment.key, "KEY");
+        return assignment.entries;
+    }
+
+    #[test]
+    fn quotes_posix_values_in_single_quotes() {
+        assert_eq!(quote("plain", ShellDialect::Posix), "'plain'");
+        assert_eq!(quote("$HOME", ShellDialect::Posix), "'$HOME'");
+        assert_eq!(quote("`id`", ShellDialect::Posix), "'`id`'");
+        assert_eq!(quote("a\\b", ShellDialect::Posix), "'a\\b'");
+        assert_eq!(quote("it's", ShellDialect::Posix), "'it'\\''s'");
+        assert_eq!(quote("a\nb", ShellDialect::Posix), "'a\nb'");
+    }
+
+    #[test]
+    fn escapes_fish_quotes_and_backslashes() {
+        assert_eq!(quote("plain", ShellDialect::Fish), "'plain'");
+        assert_eq!(quote("$HOME", ShellDialect::Fish), "'$HOME'");
+        assert_eq!(quote("it's", ShellDialect::Fish), "'it\\'s'");
+        assert_eq!(quote("a\\b", ShellDialect::Fish), "'a\\\\b'");
+        assert_eq!(quote("\\'", ShellDialect::Fish), "'\\\\\\''");
+    }
+
+    #[test]
+    fn reads_back_every_quoted_value_in_bash_and_zsh() {
+        // bash and zsh profiles are both read as POSIX
+        assert_eq!(
+            ShellDialect::from_profile_path("/home/user/.bashrc"),
+            ShellDialect::Posix
+        );
+        assert_eq!(
+            ShellDialect::from_profile_path("/home/user/.zshrc"),
+            ShellDialect::Posix
+        );
+        for value in VALUES {
+            assert_eq!(round_trip(value, ShellDialect::Posix), vec![value]);
+        }
+    }
+
+    #[test]
+    fn reads_back_every_quoted_value_in_fish() {
+        assert_eq!(
+            ShellDialect::from_profile_path("/home/user/.config/fish/config.fish"),
+            ShellDialect::Fish
+        );
+        for value in VALUES {
+            assert_eq!(round_trip(value, ShellDialect::Fish), vec![value]);
+        }
+    }
+
+    #[test]
+    fn reads_back_a_value_spanning_lines_in_a_profile() {
+        for dialect in [ShellDialect::Posix, ShellDialect::Fish] {
+            let content = format!("# start\n{}\n", set_line("KEY", "first\nsecond", dialect));
+            let parsed = parse_profile(&content, dialect);
+            assert!(parsed.incomplete.is_none());
+            assert_eq!(parsed.assignments.len(), 1);
+            assert_eq!(parsed.assignments[0].first_line, 1);
+            assert_eq!(parsed.assignments[0].last_line, 2);
+            assert_eq!(
+                parsed.assignments[0].assignment.entries,
+                vec!["first\nsecond"]
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_the_existing_value_when_adding() {
+        for dialect in [ShellDialect::Posix, ShellDialect::Fish] {
+            for position in [Position::Prepend, Position::Append] {
+                let line = add_line("PATH", "/opt/it's bin", position, dialect);
+                let assignment = parse_assignment(&line, dialect).unwrap().unwrap();
+                assert!(assignment.keeps_existing, "{}", line);
+                assert_eq!(assignment.entries, vec!["/opt/it's bin"]);
+            }
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::consts_and_errors::*;
+use crate::diff_utils::{diff_entries, EntryDiff};
+use crate::provenance::{resolve, ResolvedEnv, ResolvedVar, Source};
+use crate::settings_utils::get_config_path;
+
+/// Name `diff_snapshots` uses for the variables as they are now
+pub const LIVE: &str = "live";
+
+/// Every variable as it was at one point, saved to compare against later
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct Snapshot {
+    pub name: String,
+    /// when the snapshot was taken, RFC 3339
+    pub taken_at: String,
+    /// the variables, with the same fields as `resolve` (schema_version, vars, diagnostics)
+    #[serde(flatten)]
+    pub env: ResolvedEnv,
+}
+
+/// A saved snapshot, without its variables
+#[derive(Serialize, Clone, Debug)]
+pub struct SnapshotInfo {
+    pub name: String,
+    pub taken_at: String,
+    /// file the snapshot is saved in
+    pub path: String,
+}
+
+/// A variable whose entries are different in the two snapshots
+#[derive(Serialize, Clone, Debug)]
+pub struct VarChange {
+    pub key: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+    /// where the variable is set in the newer snapshot
+    pub sources: Vec<Source>,
+    /// entry by entry changes, for lists (ex: PATH). Empty if both versions are a single value
+    pub entries: Vec<EntryDiff>,
+}
+
+/// What changed between two snapshots, each list sorted by name
+#[derive(Serialize, Clone, Debug)]
+pub struct SnapshotDiff {
+    /// the older snapshot
+    pub from: String,
+    /// the newer snapshot, "live" for the variables as they are now
+    pub to: String,
+    pub added: Vec<ResolvedVar>,
+    pub removed: Vec<ResolvedVar>,
+    pub changed: Vec<VarChange>,
+}
+
+/// Finds the directory snapshots are kept in, `snapshots` in the config directory
+pub fn snapshots_dir() -> Result<PathBuf, GunmetalError> {
+    return Ok(PathBuf::from(get_config_path()?).join("snapshots"));
+}
+
+/// Saves every variable as it is now, with where it's set, to a file in the snapshots directory
+/// ### Arguments
+/// - name (optional String): name to save it under, ex: "before-upgrade". Defaults to the time it's taken
+/// ### Returns
+/// The saved snapshot, or an error if the variables can't be read, the name is taken or the file can't be written
+pub fn save_snapshot(name: Option<String>) -> Result<SnapshotInfo, GunmetalError> {
+    let now = chrono::Local::now();
+    let dir = snapshots_dir()?;
+    let named = name.is_some();
+    let mut name = match name {
+        Some(name) => {
+            check_name(&name)?;
+            name
+        }
+        None => now.format("%Y%m%d-%H%M%S").to_string(),
+    };
+    let env = resolve()?;
+
+    let dir_str = dir.to_string_lossy().to_string();
+    fs::create_dir_all(&dir).map_err(|err| {
+        GunmetalError::from_io(
+            GunmetalError::WriteFailed,
+            GunmetalError::WriteFailed,
+            ErrorInfo::new(mkdir_err!(&dir_str)).path(&dir_str),
+            &err,
+        )
+    })?;
+    // the file is only made if it doesn't exist yet, so a snapshot saved at the same time is never overwritten
+    let (path, mut file) = loop {
+        let path = dir
+            .join(format!("{}.json", name))
+            .to_string_lossy()
+            .to_string();
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        // a snapshot holds every variable, so only the owner may read it
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        match options.open(&path) {
+            Ok(file) => break (path, file),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && named => {
+                return Err(GunmetalError::Conflict(
+                    ErrorInfo::new(snapshot_exists_err!(&name)).path(&path),
+                ));
+            }
+            // two snapshots in the same second still get separate files
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => name.push('x'),
+            Err(err) => {
+                return Err(GunmetalError::from_io(
+                    GunmetalError::WriteFailed,
+                    GunmetalError::WriteFailed,
+                    ErrorInfo::new(make_file_err!(&path)).path(&path),
+                    &err,
+                ))
+            }
+        }
+    };
+
+    let snapshot = Snapshot {
+        name: name.to_owned(),
+        taken_at: now.to_rfc3339(),
+        env,
+    };
+    let written = serde_json::to_string_pretty(&snapshot)
+        .map_err(io::Error::from)
+        .and_then(|json| file.write_all(json.as_bytes()));
+    if let Err(err) = written {
+        // a half written snapshot can't be read back, don't leave it in the list
+        let _ = fs::remove_file(&path);
+        return Err(GunmetalError::from_io(
+            GunmetalError::WriteFailed,
+            GunmetalError::WriteFailed,
+            ErrorInfo::new(make_file_err!(&path)).path(&path),
+            &err,
+        ));
+    }
+
+    log::info!("saved snapshot {}", name);
+    return Ok(SnapshotInfo {
+        name,
+        taken_at: snapshot.taken_at,
+        path,
+    });
+}
+
+/// Lists the saved snapshots, newest first. Files that can't be read as snapshots are skipped.
+pub fn list_snapshots() -> Vec<SnapshotInfo> {
+    let entries = match snapshots_dir().map(fs::read_dir) {
+        Ok(Ok(entries)) => entries,
+        _ => return Vec::new(),
+    };
+    let mut snapshots: Vec<SnapshotInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path().to_string_lossy().to_string();
+            let snapshot: Snapshot = serde_json::from_str(&fs::read_to_string(&path).ok()?).ok()?;
+            Some(SnapshotInfo {
+                name: snapshot.name,
+                taken_at: snapshot.taken_at,
+                path,
+            })
+        })
+        .collect();
+    sort_newest_first(&mut snapshots);
+    return snapshots;
+}
+
+/// Sorts snapshots by when they were taken, newest first. Times taken in different time zones
+/// don't sort as text, so they're compared as times. Ones with an unreadable time go last
+fn sort_newest_first(snapshots: &mut [SnapshotInfo]) {
+    snapshots.sort_by_key(|info| {
+        std::cmp::Reverse(chrono::DateTime::parse_from_rfc3339(&info.taken_at).ok())
+    });
+}
+
+/// Reads a saved snapshot
+/// ### Returns
+/// The snapshot, or an error if there's none with that name or it can't be read
+pub fn load_snapshot(name: &str) -> Result<Snapshot, GunmetalError> {
+    check_name(name)?;
+    let path = snapshots_dir()?
+        .join(format!("{}.json", name))
+        .to_string_lossy()
+        .to_string();
+    let json = fs::read_to_string(&path).map_err(|err| {
+        GunmetalError::from_io(
+            GunmetalError::ValueNotFound,
+            GunmetalError::ReadFailed,
+            ErrorInfo::new(no_snapshot_err!(name)).path(&path),
+            &err,
+        )
+    })?;
+    return serde_json::from_str(&json).map_err(|err| {
+        GunmetalError::ParseError(
+            ErrorInfo::new(snapshot_read_err!(&path))
+                .path(&path)
+                .details(err),
+        )
+    });
+}
+
+/// Compares two snapshots, or a snapshot with the variables as they are now
+/// ### Arguments
+/// - a: name of the older snapshot
+/// - b (optional String): name of the newer snapshot. Without it (or with "live"), the variables as they are now
+/// ### Returns
+/// The variables added, removed and changed from `a` to `b`, or an error if a snapshot can't be read
+pub fn diff_snapshots(a: &str, b: Option<&str>) -> Result<SnapshotDiff, GunmetalError> {
+    let before = load_snapshot(a)?.env;
+    let (to, after) = match b {
+        Some(b) if b != LIVE => (String::from(b), load_snapshot(b)?.env),
+        _ => (String::from(LIVE), resolve()?),
+    };
+    return Ok(diff_envs(a, &to, before, after));
+}
+
+/// Compares two sets of resolved variables
+pub fn diff_envs(from: &str, to: &str, before: ResolvedEnv, after: ResolvedEnv) -> SnapshotDiff {
+    let mut old: HashMap<String, ResolvedVar> = before
+        .vars
+        .into_iter()
+        .map(|var| (var.key.to_owned(), var))
+        .collect();
+    let mut diff = SnapshotDiff {
+        from: String::from(from),
+        to: String::from(to),
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+    };
+
+    // both lists are sorted by name, so the results are too
+    for var in after.vars {
+        match old.remove(&var.key) {
+            None => diff.added.push(var),
+            Some(old_var) if old_var.entries != var.entries => {
+                let is_list = old_var.entries.len() > 1 || var.entries.len() > 1;
+                diff.changed.push(VarChange {
+                    entries: match is_list {
+                        true => diff_entries(&old_var.entries, &var.entries),
+                        false => Vec::new(),
+                    },
+                    key: var.key,
+                    before: old_var.entries,
+                    after: var.entries,
+                    sources: var.sources,
+                });
+            }
+            Some(_) => (),
+        }
+    }
+    diff.removed = old.into_values().collect();
+    diff.removed.sort_by(|a, b| a.key.cmp(&b.key));
+    return diff;
+}
+
+/// Checks that a snapshot name is safe to use as a file name
+fn check_name(name: &str) -> Result<(), GunmetalError> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_';
+    if name.is_empty() || name.starts_with('.') || !name.chars().all(allowed) || name == LIVE {
+        return Err(GunmetalError::InvalidInput(ErrorInfo::new(
+            snapshot_name_err!(name),
+        )));
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(name: &str, taken_at: &str) -> SnapshotInfo {
+        return SnapshotInfo {
+            name: String::from(name),
+            taken_at: String::from(taken_at),
+            path: format!("/snapshots/{}.json", name),
+        };
+    }
+
+    #[test]
+    fn sorts_by_time_across_time_zones() {
+        let mut snapshots = vec![
+            // 09:30 UTC, sorts after "2026-10-19T10:00:00+00:00" as text
+            info("tokyo", "2026-10-19T18:30:00+09:00"),
+            info("broken", "yesterday"),
+            info("london", "2026-10-19T10:00:00+00:00"),
+            info("new-york", "2026-10-19T07:00:00-04:00"),
+        ];
+        sort_newest_first(&mut snapshots);
+        let names: Vec<&str> = snapshots.iter().map(|info| info.name.as_str()).collect();
+        assert_eq!(names, vec!["new-york", "london", "tokyo", "broken"]);
+    }
+}
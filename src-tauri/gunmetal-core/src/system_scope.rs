@@ -0,0 +1,335 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{ErrorKind, Read};
+use std::path::Path;
+
+use crate::consts_and_errors::*;
+use crate::env_backend::Position;
+use crate::file_guard::LockedFile;
+use crate::fs_root::rooted;
+use crate::shell_quote::{add_line, is_name, ShellDialect};
+use crate::target_format::TargetFormat;
+use crate::validation::validate;
+
+/// Who a change applies to
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// only the current user, written to their own shell profile
+    User,
+    /// every user on the machine, written to a file under /etc by the privileged helper
+    System,
+}
+
+/// The system-wide files the privileged helper may write. The helper never takes a path, only one of these.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemTarget {
+    /// a script in /etc/profile.d, sourced by login shells
+    ProfileD,
+    /// /etc/environment, read by pam_env at login
+    Environment,
+    /// a systemd environment.d drop-in, read by the user manager
+    EnvironmentD,
+}
+
+impl SystemTarget {
+    /// Path of the file this target writes to
+    pub fn path(&self) -> &'static str {
+        match self {
+            SystemTarget::ProfileD => "/etc/profile.d/gunmetal.sh",
+            SystemTarget::Environment => "/etc/environment",
+            SystemTarget::EnvironmentD => "/etc/environment.d/60-gunmetal.conf",
+        }
+    }
+
+    /// Where the target file is on this machine: `path` under the root, if one is set
+    pub fn location(&self) -> Result<String, GunmetalError> {
+        return rooted(self.path());
+    }
+
+    /// Format of the target file, which decides how lines are written and what is allowed in them
+    pub fn format(&self) -> TargetFormat {
+        match self {
+            SystemTarget::ProfileD => TargetFormat::Shell(ShellDialect::Posix),
+            SystemTarget::Environment => TargetFormat::PamEnvironment,
+            SystemTarget::EnvironmentD => TargetFormat::EnvironmentD,
+        }
+    }
+}
+
+/// A change for the privileged helper, sent to it as JSON on stdin.
+/// It only describes what to add, the helper decides where and how to write it.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct HelperRequest {
+    pub target: SystemTarget,
+    pub key: String,
+    pub value: String,
+    /// requests from older versions don't have a position, and always prepended
+    #[serde(default)]
+    pub position: Position,
+}
+
+impl HelperRequest {
+    /// Validates the request and works out the new contents of the target file
+    /// ### Arguments
+    /// - content: current contents of the target file (empty if it doesn't exist yet)
+    /// ### Returns
+    /// The contents the file should have after the change, or the validation error
+    pub fn apply_to(&self, content: &str) -> Result<String, GunmetalError> {
+        let format = self.target.format();
+        validate(&self.key, &self.value, format)?;
+
+        let line = match format {
+            // pam_env can't expand variables, so an existing assignment is extended in place
+            TargetFormat::PamEnvironment => {
+                if let Some(updated) = add_in_place(content, &self.key, &self.value, self.position)
+                {
+                    return Ok(updated);
+                }
+                format!("{}={}", &self.key, &self.value)
+            }
+            // the separator is only added if the variable is already set, since an empty entry
+            // (ex: in PATH, where it means the current directory) is never what's wanted
+            TargetFormat::EnvironmentD => match self.position {
+                Position::Prepend => format!(
+                    "{}={}${{{}:+:${}}}",
+                    &self.key, &self.value, &self.key, &self.key
+                ),
+                Position::Append => format!(
+                    "{}=${{{}:+${}:}}{}",
+                    &self.key, &self.key, &self.key, &self.value
+                ),
+            },
+            _ => add_line(&self.key, &self.value, self.position, ShellDialect::Posix),
+        };
+
+        // keep the file ending in a newline
+        let mut updated = String::from(content);
+        if !updated.is_empty() && !updated.ends_with('\n') {
+            updated.push('\n');
+        }
+        updated.push_str(&line);
+        updated.push('\n');
+        return Ok(updated);
+    }
+}
+
+/// Reads the current contents of a system target, a missing file counts as empty
+pub fn read_target(target: SystemTarget) -> Result<String, GunmetalError> {
+    return read_system_file(&target.location()?);
+}
+
+/// Runs one request for the privileged helper: reads it, checks it, and writes the target file.
+/// Only the helper calls this, since the files need root to write.
+/// ### Arguments
+/// - input: where the JSON `HelperRequest` is read from, stdin for the helper
+/// ### Returns
+/// A success message, or an error if the request is invalid or the file can't be written
+pub fn handle_helper_request(mut input: impl Read) -> Result<String, GunmetalError> {
+    let mut request_json = String::new();
+    input.read_to_string(&mut request_json).map_err(|err| {
+        GunmetalError::InvalidInput(ErrorInfo::new(helper_request_err!()).io(&err))
+    })?;
+    let request: HelperRequest = serde_json::from_str(&request_json).map_err(|err| {
+        GunmetalError::InvalidInput(ErrorInfo::new(helper_request_err!()).details(err))
+    })?;
+
+    // the helper only ever writes the real files, never under a root
+    return apply_request(&request, request.target.path());
+}
+
+/// Makes the change in a request to the target file at `path`, for the helper or (under a root) the app itself
+/// ### Returns
+/// A success message, or an error if the change is invalid or the file can't be written
+pub fn apply_request(request: &HelperRequest, path: &str) -> Result<String, GunmetalError> {
+    // checked before anything is made, so an invalid request leaves nothing behind
+    validate(&request.key, &request.value, request.target.format())?;
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            let parent = parent.to_string_lossy().to_string();
+            GunmetalError::from_io(
+                GunmetalError::WriteFailed,
+                GunmetalError::WriteFailed,
+                ErrorInfo::new(mkdir_err!(&parent)).path(&parent),
+                &err,
+            )
+        })?;
+    }
+    create_system_file(path)?;
+
+    // the lock is on the target itself, so no lock file is left next to it in /etc
+    let mut file = LockedFile::open(path)?;
+    let content = file.read_to_string()?;
+    let updated = request.apply_to(&content)?;
+    // files under /etc are world readable, whatever the helper's umask is
+    file.replace_with_mode(&updated, Some(0o644))?;
+
+    return Ok(add_var_success!());
+}
+
+/// Makes an empty system file if there isn't one yet, so it can be locked
+fn create_system_file(path: &str) -> Result<(), GunmetalError> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o644);
+    }
+    return match options.open(path) {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => Ok(()),
+        Err(err) => Err(GunmetalError::from_io(
+            GunmetalError::WriteFailed,
+            GunmetalError::WriteFailed,
+            ErrorInfo::new(make_file_err!(path)).path(path),
+            &err,
+        )),
+    };
+}
+
+/// Reads the variables assigned in a `KEY=VALUE` file like /etc/environment, skipping comments and other lines
+/// ### Returns
+/// The index of each assignment's line, its key and its value with surrounding quotes removed
+pub fn environment_assignments(content: &str) -> Vec<(usize, String, String)> {
+    let mut assignments: Vec<(usize, String, String)> = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim_start();
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        if let Some((key, value)) = line.split_once('=') {
+            if is_name(key) {
+                let value = value.trim_end();
+                let value = ['"', '\'']
+                    .iter()
+                    .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+                    .unwrap_or(value);
+                assignments.push((index, String::from(key), String::from(value)));
+            }
+        }
+    }
+    return assignments;
+}
+
+/// Reads a system file, a missing file counts as empty
+fn read_system_file(path: &str) -> Result<String, GunmetalError> {
+    match fs::read_to_string(path) {
+        Ok(content) => return Ok(content),
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(String::new()),
+        Err(err) => {
+            return Err(GunmetalError::from_io(
+                GunmetalError::ReadFailed,
+                GunmetalError::ReadFailed,
+                ErrorInfo::new(profile_err!(path)).path(path),
+                &err,
+            ))
+        }
+    }
+}
+
+/// Adds a value to either end of an existing `KEY=...` line in /etc/environment
+/// ### Returns
+/// The updated file contents, or None if the key isn't assigned in the file
+fn add_in_place(content: &str, key: &str, value: &str, position: Position) -> Option<String> {
+    let prefix = format!("{}=", key);
+    let mut found = false;
+    let lines: Vec<String> = content
+        .lines()
+        .map(|line| match line.trim_start().strip_prefix(&prefix) {
+            Some(old) if !found => {
+                found = true;
+                // keep the old value's quotes around the whole list
+                let (quote, old) = match old.strip_prefix('"').and_then(|old| old.strip_suffix('"'))
+                {
+                    Some(unquoted) => ("\"", unquoted),
+                    None => ("", old),
+                };
+                match position {
+                    Position::Prepend => format!("{}{}{}:{}{}", &prefix, quote, value, old, quote),
+                    Position::Append => format!("{}{}{}:{}{}", &prefix, quote, old, value, quote),
+                }
+            }
+            _ => String::from(line),
+        })
+        .collect();
+
+    if !found {
+        return None;
+    }
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    return Some(updated);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(target: SystemTarget, position: Position) -> HelperRequest {
+        return HelperRequest {
+            target,
+            key: String::from("PATH"),
+            value: String::from("/opt/bin"),
+            position,
+        };
+    }
+
+    #[test]
+    fn adds_to_environment_d_without_an_empty_entry() {
+        let prepend = request(SystemTarget::EnvironmentD, Position::Prepend);
+        assert_eq!(
+            prepend.apply_to("").unwrap(),
+            "PATH=/opt/bin${PATH:+:$PATH}\n"
+        );
+        let append = request(SystemTarget::EnvironmentD, Position::Append);
+        assert_eq!(
+            append.apply_to("A=1").unwrap(),
+            "A=1\nPATH=${PATH:+$PATH:}/opt/bin\n"
+        );
+    }
+
+    #[test]
+    fn writes_the_target_without_leaving_other_files() {
+        let dir = std::env::temp_dir().join(format!("gunmetal-system-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("environment.d").join("60-gunmetal.conf");
+        let path = path.to_string_lossy().to_string();
+
+        apply_request(
+            &request(SystemTarget::EnvironmentD, Position::Prepend),
+            &path,
+        )
+        .unwrap();
+        apply_request(
+            &request(SystemTarget::EnvironmentD, Position::Append),
+            &path,
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "PATH=/opt/bin${PATH:+:$PATH}\nPATH=${PATH:+$PATH:}/opt/bin\n"
+        );
+        // no lock or temporary file is left next to it
+        let names: Vec<String> = fs::read_dir(dir.join("environment.d"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["60-gunmetal.conf"]);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o644);
+        }
+
+        // an invalid request doesn't make the file
+        let missing = dir.join("profile.d").join("gunmetal.sh");
+        let mut invalid = request(SystemTarget::ProfileD, Position::Prepend);
+        invalid.key = String::from("1PATH");
+        apply_request(&invalid, &missing.to_string_lossy()).unwrap_err();
+        assert!(!missing.exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
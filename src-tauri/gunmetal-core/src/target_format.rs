@@ -0,0 +1,51 @@
+use crate::consts_and_errors::*;
+use crate::shell_quote::ShellDialect;
+
+/// Kind of file (or store) a variable gets written to, which decides what keys and values are allowed
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TargetFormat {
+    /// a shell startup file like ~/.bashrc or config.fish
+    Shell(ShellDialect),
+    /// a systemd `environment.d` drop-in (`KEY=VALUE` lines)
+    EnvironmentD,
+    /// `/etc/environment`, read by pam_env (`KEY=VALUE` lines)
+    PamEnvironment,
+    /// the Windows user environment, written with `SetX`
+    #[allow(dead_code)] // only constructed on Windows
+    Windows,
+}
+
+impl TargetFormat {
+    /// Works out the format of a file from its path
+    pub fn from_path(path: &str) -> TargetFormat {
+        if path == "/etc/environment" {
+            return TargetFormat::PamEnvironment;
+        }
+        if path.contains("/environment.d/") && path.ends_with(".conf") {
+            return TargetFormat::EnvironmentD;
+        }
+        return TargetFormat::Shell(ShellDialect::from_profile_path(path));
+    }
+
+    /// Whether each variable has to fit on one line (no quoting that could span lines)
+    pub fn is_single_line(&self) -> bool {
+        match self {
+            TargetFormat::Shell(_) => false,
+            TargetFormat::EnvironmentD | TargetFormat::PamEnvironment | TargetFormat::Windows => {
+                true
+            }
+        }
+    }
+
+    /// Name used in messages, ex: "names in {} may only contain...", in the user's language
+    pub fn name(&self) -> String {
+        let id = match self {
+            TargetFormat::Shell(ShellDialect::Posix) => "format-posix",
+            TargetFormat::Shell(ShellDialect::Fish) => "format-fish",
+            TargetFormat::EnvironmentD => "format-environment-d",
+            TargetFormat::PamEnvironment => "format-pam-environment",
+            TargetFormat::Windows => "format-windows",
+        };
+        return tr!(id);
+    }
+}
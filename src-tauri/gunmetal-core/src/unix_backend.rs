@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fs::{self};
+use std::path::Path;
+
+use crate::backups::try_backup;
+use crate::consts_and_errors::*;
+use crate::diff_utils::unified_diff;
+use crate::env_backend::{EnvBackend, Position, Target};
+use crate::file_guard::{check_unchanged, remember, LockedFile};
+use crate::fs_root::root;
+use crate::profile_edit::remove_value;
+use crate::provenance::{process_sources, profile_sources, system_sources, Origin, Provenance};
+use crate::settings_utils::*;
+use crate::shell_quote::{add_line, parse_profile, Assignment, ShellDialect};
+use crate::system_scope::{environment_assignments, read_target, Scope, SystemTarget};
+use crate::target_format::TargetFormat;
+use crate::validation::validate;
+
+/// Backend for macOS and Linux: the process environment plus the shell profile set in the settings
+pub struct UnixProfileBackend;
+
+impl EnvBackend for UnixProfileBackend {
+    /// Reads the process environment and the shell profile into a map of variables and their entries.
+    /// The shell profile's current state is remembered, so a later write can tell if the file was changed in the meantime.
+    fn read(&self) -> Result<HashMap<String, Vec<String>>, GunmetalError> {
+        // find the shell profile from the settings, return error if no settings file or profile
+        let shell_profile_path = get_shell_profile_path()?;
+
+        // read the whole file, then go through it line by line
+        let content = fs::read_to_string(&shell_profile_path)
+            .map_err(|err| profile_read_err(&shell_profile_path, &err))?;
+        remember(&shell_profile_path, &content);
+
+        return Ok(vars_with_profile(&shell_profile_path, &content));
+    }
+
+    /// Appends an export command to the shell profile
+    fn write(
+        &self,
+        key: &str,
+        value: &str,
+        position: Position,
+        dry_run: bool,
+    ) -> Result<String, GunmetalError> {
+        let shell_path = get_shell_profile_path()?;
+        return append_assignment(&shell_path, key, value, position, dry_run, |content| {
+            is_duplicate(&shell_path, content, key, value)
+        });
+    }
+
+    /// Removes a value from the assignment in the shell profile that sets it
+    fn remove(&self, key: &str, value: &str, dry_run: bool) -> Result<String, GunmetalError> {
+        let shell_path = get_shell_profile_path()?;
+
+        if dry_run {
+            let original = fs::read_to_string(&shell_path)
+                .map_err(|err| profile_read_err(&shell_path, &err))?;
+            let updated = remove_value(&original, key, value, &shell_path)?;
+            return Ok(unified_diff(&shell_path, &original, &updated));
+        }
+
+        let mut file = LockedFile::open(&shell_path)?;
+        let original = file.read_to_string()?;
+        check_unchanged(&shell_path, &original)?;
+        let updated = remove_value(&original, key, value, &shell_path)?;
+
+        try_backup(&shell_path, &original);
+        file.replace(&updated)?;
+        remember(&shell_path, &updated);
+
+        return Ok(remove_var_success!());
+    }
+
+    fn list_targets(&self) -> Result<Vec<Target>, GunmetalError> {
+        let shell_path = get_shell_profile_path()?;
+        let mut targets = vec![Target {
+            format: TargetFormat::from_path(&shell_path).name(),
+            location: shell_path,
+            scope: Scope::User,
+        }];
+
+        // system-wide files are written by the privileged helper, only list the ones in use
+        if cfg!(target_os = "linux") {
+            for system_target in [
+                SystemTarget::ProfileD,
+                SystemTarget::Environment,
+                SystemTarget::EnvironmentD,
+            ] {
+                let location = system_target.location()?;
+                if Path::new(&location).exists() {
+                    targets.push(Target {
+                        location,
+                        format: system_target.format().name(),
+                        scope: Scope::System,
+                    });
+                }
+            }
+        }
+
+        return Ok(targets);
+    }
+
+    /// Finds where each variable is set: the system-wide files (on Linux), then the process environment
+    /// (not under a root, see `base_environment`), then the shell profile
+    fn provenance(&self) -> Result<Provenance, GunmetalError> {
+        let shell_profile_path = get_shell_profile_path()?;
+        let content = fs::read_to_string(&shell_profile_path)
+            .map_err(|err| profile_read_err(&shell_profile_path, &err))?;
+
+        let mut provenance = Provenance::default();
+        if cfg!(target_os = "linux") {
+            system_sources(&mut provenance);
+        }
+        if root()?.is_none() {
+            process_sources(&mut provenance);
+        }
+        profile_sources(
+            &mut provenance,
+            &shell_profile_path,
+            &content,
+            Origin::Profile,
+        );
+        return Ok(provenance);
+    }
+}
+
+/// Builds the map of variables from the process environment, updated by the assignments in the shell profile
+/// ### Arguments
+/// - shell_profile_path: path of the profile, which decides its dialect
+/// - content: contents of the profile
+fn vars_with_profile(shell_profile_path: &str, content: &str) -> HashMap<String, Vec<String>> {
+    let mut map: HashMap<String, Vec<String>> = HashMap::new();
+
+    // variables can be marked as single values in the settings, so they aren't split on ':'
+    let settings = get_settings().unwrap_or_default();
+    for (key, vals) in base_environment() {
+        let entries: Vec<String> = settings.split_entries(&key, &vals);
+        map.insert(key, entries);
+    }
+
+    // modify map by adding every assignment in the shell profile
+    let parsed = parse_profile(content, ShellDialect::from_profile_path(shell_profile_path));
+    for profile_assignment in parsed.assignments {
+        append_cmd_to_map(profile_assignment.assignment, &mut map);
+    }
+    // file ended in the middle of a quote, show the line that couldn't be read
+    if let Some(pending) = parsed.incomplete {
+        map.insert(String::from("Error:"), vec![pending]);
+    }
+
+    return map;
+}
+
+/// The variables a login shell starts with, before the profile: the process environment,
+/// or under a root (where this process's environment means nothing) the root's /etc/environment
+fn base_environment() -> Vec<(String, String)> {
+    if let Ok(Some(_)) = root() {
+        let content = read_target(SystemTarget::Environment).unwrap_or_default();
+        return environment_assignments(&content)
+            .into_iter()
+            .map(|(_, key, value)| (key, value))
+            .collect();
+    }
+    return std::env::vars().collect();
+}
+
+/// Appends the line that adds a value to a variable to a shell file, the way `add_var` does:
+/// validated, quoted for the file's shell, and written under a lock with a backup taken first
+/// ### Arguments
+/// - path: the file, ex: the shell profile or a project's .envrc
+/// - key, value: the variable and the value to add
+/// - position: which end of the variable the value goes on
+/// - dry_run: if true, nothing is written and the diff of the change is returned instead
+/// - is_set: checks if the value is already set, given the file's current contents. Nothing is written if it is
+/// ### Returns
+/// A success message (or the diff), or an error if the file was changed since it was loaded or can't be written
+pub(crate) fn append_assignment(
+    path: &str,
+    key: &str,
+    value: &str,
+    position: Position,
+    dry_run: bool,
+    is_set: impl Fn(&str) -> bool,
+) -> Result<String, GunmetalError> {
+    validate(key, value, TargetFormat::from_path(path))?;
+
+    // make string to add to end of file, quoted for the file's shell so the value is stored literally
+    let dialect = ShellDialect::from_profile_path(path);
+    let export_cmd: String = format!("\n{}", add_line(key, value, position, dialect));
+
+    if dry_run {
+        let original = fs::read_to_string(path).map_err(|err| profile_read_err(path, &err))?;
+        // nothing would be written, so a dry run has an empty diff
+        if is_set(&original) {
+            return Ok(String::new());
+        }
+        let updated = format!("{}{}", &original, &export_cmd);
+        return Ok(unified_diff(path, &original, &updated));
+    }
+
+    // lock the file while it's checked and written, then make sure nobody changed it since it was loaded
+    let mut file = LockedFile::open(path)?;
+    let original = file.read_to_string()?;
+    check_unchanged(path, &original)?;
+    // check if variable is already there, if so, return
+    if is_set(&original) {
+        return Ok(var_added_already!());
+    }
+
+    try_backup(path, &original);
+    file.append(&export_cmd)?;
+    // our own change isn't a conflict for the next write
+    remember(path, &format!("{}{}", &original, &export_cmd));
+
+    // if this point is reached, return success string
+    return Ok(add_var_success!());
+}
+
+/// Error for a shell profile that can't be read
+pub fn profile_read_err(path: &str, err: &std::io::Error) -> GunmetalError {
+    return GunmetalError::from_io(
+        GunmetalError::ProfileNotFound,
+        GunmetalError::ReadFailed,
+        ErrorInfo::new(profile_err!(path)).path(path),
+        err,
+    );
+}
+
+/// Checks if a value is already set for a variable, either in the process environment or the shell profile
+fn is_duplicate(shell_profile_path: &str, content: &str, key: &str, value: &str) -> bool {
+    return match vars_with_profile(shell_profile_path, content).get(key) {
+        Some(entries) => entries.iter().any(|entry| entry == value),
+        None => false,
+    };
+}
+
+/// Adds the values of an assignment to the map, either around the variable's existing values or replacing them
+pub(crate) fn append_cmd_to_map(assignment: Assignment, map: &mut HashMap<String, Vec<String>>) {
+    let mut values_to_add = assignment.entries.clone();
+
+    if let Some(existing_values) = map.get(&assignment.key) {
+        if assignment.keeps_existing {
+            // ex: `PATH=a:$PATH` puts "a" in front of the existing entries
+            let (before, after) = assignment.split_around_existing();
+            values_to_add = before;
+            values_to_add.extend(existing_values.iter().cloned());
+            values_to_add.extend(after);
+        } else {
+            log::debug!(
+                "{} is assigned again, overriding its earlier value",
+                &assignment.key
+            );
+        }
+    }
+
+    map.insert(assignment.key, values_to_add);
+}
@@ -0,0 +1,247 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::consts_and_errors::*;
+use crate::shell_quote::is_name;
+use crate::target_format::TargetFormat;
+
+/// Longest variable name accepted, most shells cope with more but nobody needs it
+const MAX_KEY_LENGTH: usize = 255;
+/// Longest value accepted, the size limit of a single Windows environment variable
+const MAX_VALUE_LENGTH: usize = 32767;
+/// `SetX` truncates anything longer than this
+const MAX_SETX_VALUE_LENGTH: usize = 1024;
+
+/// The individual checks a key or value has to pass before it gets written
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationRule {
+    KeyEmpty,
+    KeyTooLong,
+    /// shells and environment.d need names made of letters, digits and `_`, not starting with a digit
+    KeyNotIdentifier,
+    /// Windows allows most characters in names, but not `=`
+    KeyContainsEquals,
+    ValueEmpty,
+    ValueTooLong,
+    ValueContainsNul,
+    /// single-line formats can't hold a value with a line break
+    ValueContainsNewline,
+    /// a character the format has no way to escape
+    ValueUnsupportedCharacter,
+}
+
+impl ValidationRule {
+    /// Stable name of the rule, also what it serializes to
+    pub fn code(&self) -> &'static str {
+        match self {
+            ValidationRule::KeyEmpty => "key_empty",
+            ValidationRule::KeyTooLong => "key_too_long",
+            ValidationRule::KeyNotIdentifier => "key_not_identifier",
+            ValidationRule::KeyContainsEquals => "key_contains_equals",
+            ValidationRule::ValueEmpty => "value_empty",
+            ValidationRule::ValueTooLong => "value_too_long",
+            ValidationRule::ValueContainsNul => "value_contains_nul",
+            ValidationRule::ValueContainsNewline => "value_contains_newline",
+            ValidationRule::ValueUnsupportedCharacter => "value_unsupported_character",
+        }
+    }
+
+    /// Which input the rule checks, either "key" or "value"
+    pub fn field(&self) -> &'static str {
+        match self {
+            ValidationRule::KeyEmpty
+            | ValidationRule::KeyTooLong
+            | ValidationRule::KeyNotIdentifier
+            | ValidationRule::KeyContainsEquals => "key",
+            ValidationRule::ValueEmpty
+            | ValidationRule::ValueTooLong
+            | ValidationRule::ValueContainsNul
+            | ValidationRule::ValueContainsNewline
+            | ValidationRule::ValueUnsupportedCharacter => "value",
+        }
+    }
+}
+
+/// A key or value that failed validation, and which rule it broke
+#[derive(Debug, Serialize)]
+pub struct ValidationError {
+    pub rule: ValidationRule,
+    /// either "key" or "value"
+    pub field: &'static str,
+    /// the rejected input
+    pub input: String,
+    /// human readable explanation
+    pub reason: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            validation_err!(
+                self.field,
+                self.input.escape_debug(),
+                self.rule.code(),
+                &self.reason
+            )
+        )
+    }
+}
+
+/// Invalid keys and values can be fixed by the user, so they're sent as `InvalidInput`
+impl From<ValidationError> for GunmetalError {
+    fn from(err: ValidationError) -> GunmetalError {
+        return GunmetalError::InvalidInput(ErrorInfo::new(err.to_string()).rule(err.rule));
+    }
+}
+
+/// Checks a key and value against the rules of the format they'll be written in
+/// ### Arguments
+/// - key: variable name
+/// - value: value being added
+/// - format: format of the file the variable is written to
+/// ### Returns
+/// Nothing if both are fine, otherwise the first rule that failed
+pub fn validate(key: &str, value: &str, format: TargetFormat) -> Result<(), ValidationError> {
+    validate_key(key, format)?;
+    validate_value(value, format)?;
+    return Ok(());
+}
+
+/// Checks a variable name against the rules of a format
+pub fn validate_key(key: &str, format: TargetFormat) -> Result<(), ValidationError> {
+    let fail = |rule: ValidationRule, reason: String| {
+        Err(ValidationError {
+            rule,
+            field: "key",
+            input: String::from(key),
+            reason,
+        })
+    };
+
+    if key.is_empty() {
+        return fail(ValidationRule::KeyEmpty, tr!("reason-key-empty"));
+    }
+    if key.len() > MAX_KEY_LENGTH {
+        return fail(
+            ValidationRule::KeyTooLong,
+            tr!("reason-key-too-long", max = MAX_KEY_LENGTH),
+        );
+    }
+
+    match format {
+        TargetFormat::Windows => {
+            if key.contains('=') || key.contains('\0') {
+                return fail(
+                    ValidationRule::KeyContainsEquals,
+                    tr!("reason-windows-key-chars"),
+                );
+            }
+        }
+        _ => {
+            if !is_name(key) {
+                return fail(
+                    ValidationRule::KeyNotIdentifier,
+                    tr!("reason-key-not-identifier", format = format.name()),
+                );
+            }
+        }
+    }
+
+    return Ok(());
+}
+
+/// Checks a value against the rules of a format
+pub fn validate_value(value: &str, format: TargetFormat) -> Result<(), ValidationError> {
+    let fail = |rule: ValidationRule, reason: String| {
+        Err(ValidationError {
+            rule,
+            field: "value",
+            input: String::from(value),
+            reason,
+        })
+    };
+
+    if value.is_empty() {
+        return fail(ValidationRule::ValueEmpty, tr!("reason-value-empty"));
+    }
+    if value.contains('\0') {
+        return fail(ValidationRule::ValueContainsNul, tr!("reason-value-nul"));
+    }
+    let max_length = match format {
+        TargetFormat::Windows => MAX_SETX_VALUE_LENGTH,
+        _ => MAX_VALUE_LENGTH,
+    };
+    if value.len() > max_length {
+        return fail(
+            ValidationRule::ValueTooLong,
+            tr!(
+                "reason-value-too-long",
+                format = format.name(),
+                max = max_length
+            ),
+        );
+    }
+    if format.is_single_line() && (value.contains('\n') || value.contains('\r')) {
+        return fail(
+            ValidationRule::ValueContainsNewline,
+            tr!("reason-value-multiline", format = format.name()),
+        );
+    }
+
+    // these files have no reliable quoting, so characters with special meaning are refused
+    let unsupported: &[char] = match format {
+        TargetFormat::EnvironmentD => &['"', '\'', '\\', '$', '`'],
+        TargetFormat::PamEnvironment => &['"', '\'', '\\', '$', '@', '#'],
+        _ => &[],
+    };
+    if let Some(ch) = value.chars().find(|ch| {
+        unsupported.contains(ch) || (format.is_single_line() && ch.is_control() && *ch != '\t')
+    }) {
+        return fail(
+            ValidationRule::ValueUnsupportedCharacter,
+            tr!(
+                "reason-value-character",
+                format = format.name(),
+                character = format!("{:?}", ch)
+            ),
+        );
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shell_quote::ShellDialect;
+
+    #[test]
+    fn keeps_the_rule_and_field_in_the_error() {
+        let err: GunmetalError =
+            validate("1KEY", "value", TargetFormat::Shell(ShellDialect::Posix))
+                .unwrap_err()
+                .into();
+        assert_eq!(err.code(), "invalid_input");
+        assert_eq!(err.info().rule, Some(ValidationRule::KeyNotIdentifier));
+        assert_eq!(err.info().rule.map(|rule| rule.field()), Some("key"));
+
+        let err: GunmetalError = validate("KEY", "a\nb", TargetFormat::Windows)
+            .unwrap_err()
+            .into();
+        assert_eq!(err.info().rule, Some(ValidationRule::ValueContainsNewline));
+        assert_eq!(err.info().rule.map(|rule| rule.field()), Some("value"));
+    }
+
+    #[test]
+    fn keeps_the_rule_under_context() {
+        let err: GunmetalError = validate("", "value", TargetFormat::Windows)
+            .unwrap_err()
+            .into();
+        let err = err.context("change #1 failed");
+        assert_eq!(err.info().rule, Some(ValidationRule::KeyEmpty));
+        assert_eq!(err.info().rule.map(|rule| rule.field()), Some("key"));
+    }
+}
@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+use std::process::Command;
+
+use crate::consts_and_errors::*;
+use crate::diff_utils::unified_diff;
+use crate::env_backend::{EnvBackend, Position, Target};
+use crate::system_scope::Scope;
+use crate::target_format::TargetFormat;
+use crate::validation::{validate, validate_value};
+
+/// Registry key holding the current user's environment variables
+const USER_ENVIRONMENT_KEY: &str = "HKCU\\Environment";
+/// Registry type of values with `%NAME%` references that are expanded when read, like the default PATH
+const EXPAND_TYPE: &str = "REG_EXPAND_SZ";
+
+/// Runs external programs. The Windows backend goes through this so it can be run on any OS with a fake runner.
+pub trait CommandRunner: Sync {
+    /// Runs a program and waits for it
+    /// ### Returns
+    /// The program's stdout, or an error with its output if it couldn't start or exited with a failure
+    fn run(&self, program: &str, args: &[&str]) -> Result<String, GunmetalError>;
+}
+
+/// Runs programs for real
+pub struct SystemCommandRunner;
+
+impl CommandRunner for SystemCommandRunner {
+    fn run(&self, program: &str, args: &[&str]) -> Result<String, GunmetalError> {
+        let output = Command::new(program).args(args).output().map_err(|err| {
+            GunmetalError::from_io(
+                GunmetalError::CommandFailed,
+                GunmetalError::CommandFailed,
+                ErrorInfo::new(cmd_fail_start!()),
+                &err,
+            )
+        })?;
+
+        if !output.status.success() {
+            return Err(GunmetalError::CommandFailed(
+                ErrorInfo::new(cmd_failed_err!(program, output.status)).details(format!(
+                    "{}{}",
+                    String::from_utf8_lossy(&output.stdout),
+                    String::from_utf8_lossy(&output.stderr)
+                )),
+            ));
+        }
+        return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+    }
+}
+
+/// A variable in the user's registry environment
+struct RegValue {
+    /// the registry type, ex: REG_SZ or REG_EXPAND_SZ
+    kind: String,
+    entries: Vec<String>,
+}
+
+/// Backend for the Windows user environment, stored in the registry and written with `SetX`
+pub struct WindowsBackend<R: CommandRunner> {
+    runner: R,
+}
+
+impl<R: CommandRunner> WindowsBackend<R> {
+    pub const fn new(runner: R) -> WindowsBackend<R> {
+        return WindowsBackend { runner };
+    }
+
+    /// Reads a variable from the user's registry environment (not the process environment,
+    /// which also has the system-wide values mixed in)
+    /// ### Returns
+    /// The variable's registry type and entries, None if it isn't set
+    fn user_value(&self, key: &str) -> Option<RegValue> {
+        // `reg query` fails if the value doesn't exist, which just means there are no entries yet
+        let output = self
+            .runner
+            .run("reg", &["query", USER_ENVIRONMENT_KEY, "/v", key])
+            .ok()?;
+        let (kind, value) = parse_reg_query(&output, key)?;
+        return Some(RegValue {
+            kind,
+            entries: split_entries(&value),
+        });
+    }
+
+    /// Saves the new entries of a variable, or deletes it if there are none left
+    /// ### Arguments
+    /// - kind: the variable's registry type before the change, None if it wasn't set
+    fn set_user_entries(
+        &self,
+        key: &str,
+        entries: &[String],
+        kind: Option<&str>,
+    ) -> Result<(), GunmetalError> {
+        if entries.is_empty() {
+            self.runner
+                .run("reg", &["delete", USER_ENVIRONMENT_KEY, "/v", key, "/f"])?;
+            return Ok(());
+        }
+
+        let value = entries.join(";");
+        // SetX silently truncates long values, so check the whole new value and not just the added entry
+        validate_value(&value, TargetFormat::Windows)?;
+        // SetX tells open programs about the change, but always saves a plain REG_SZ, which would stop
+        // references like %USERPROFILE% from being expanded. Those values are saved with their type instead
+        if kind == Some(EXPAND_TYPE) || value.contains('%') {
+            self.runner.run(
+                "reg",
+                &[
+                    "add",
+                    USER_ENVIRONMENT_KEY,
+                    "/v",
+                    key,
+                    "/t",
+                    EXPAND_TYPE,
+                    "/d",
+                    &value,
+                    "/f",
+                ],
+            )?;
+        } else {
+            self.runner.run("SetX", &[key, &value])?;
+        }
+        return Ok(());
+    }
+}
+
+impl<R: CommandRunner> EnvBackend for WindowsBackend<R> {
+    fn read(&self) -> Result<HashMap<String, Vec<String>>, GunmetalError> {
+        // create map for variables and entries, values are split on ';' which separates lists on Windows
+        let names_and_vars: HashMap<String, Vec<String>> = std::env::vars()
+            .map(|(key, vals)| (key, split_entries(&vals)))
+            .collect();
+        return Ok(names_and_vars);
+    }
+
+    fn write(
+        &self,
+        key: &str,
+        value: &str,
+        position: Position,
+        dry_run: bool,
+    ) -> Result<String, GunmetalError> {
+        validate(key, value, TargetFormat::Windows)?;
+
+        let current = self.user_value(key);
+        let kind = current.as_ref().map(|current| current.kind.as_str());
+        let entries = current
+            .as_ref()
+            .map(|current| current.entries.clone())
+            .unwrap_or_default();
+        if entries.iter().any(|entry| entry == value) {
+            return Ok(match dry_run {
+                true => String::new(),
+                false => var_added_already!(),
+            });
+        }
+
+        let mut updated = entries.clone();
+        match position {
+            Position::Prepend => updated.insert(0, String::from(value)),
+            Position::Append => updated.push(String::from(value)),
+        }
+        if dry_run {
+            return Ok(unified_diff(
+                &format!("{}\\{}", USER_ENVIRONMENT_KEY, key),
+                &entries.join("\n"),
+                &updated.join("\n"),
+            ));
+        }
+
+        self.set_user_entries(key, &updated, kind)?;
+        return Ok(add_var_success!());
+    }
+
+    fn remove(&self, key: &str, value: &str, dry_run: bool) -> Result<String, GunmetalError> {
+        let current = self.user_value(key);
+        let kind = current.as_ref().map(|current| current.kind.as_str());
+        let entries = current
+            .as_ref()
+            .map(|current| current.entries.clone())
+            .unwrap_or_default();
+        if !entries.iter().any(|entry| entry == value) {
+            return Err(GunmetalError::ValueNotFound(
+                ErrorInfo::new(value_not_found_err!(value, key, USER_ENVIRONMENT_KEY))
+                    .path(USER_ENVIRONMENT_KEY),
+            ));
+        }
+
+        let updated: Vec<String> = entries
+            .iter()
+            .filter(|entry| entry.as_str() != value)
+            .cloned()
+            .collect();
+        if dry_run {
+            return Ok(unified_diff(
+                &format!("{}\\{}", USER_ENVIRONMENT_KEY, key),
+                &entries.join("\n"),
+                &updated.join("\n"),
+            ));
+        }
+
+        self.set_user_entries(key, &updated, kind)?;
+        return Ok(remove_var_success!());
+    }
+
+    fn list_targets(&self) -> Result<Vec<Target>, GunmetalError> {
+        return Ok(vec![Target {
+            location: String::from(USER_ENVIRONMENT_KEY),
+            format: TargetFormat::Windows.name(),
+            scope: Scope::User,
+        }]);
+    }
+}
+
+/// Splits a Windows list value into its entries, skipping empty ones (ex: from a trailing ';')
+fn split_entries(value: &str) -> Vec<String> {
+    return value
+        .split(';')
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect();
+}
+
+/// Gets a value and its type out of the output of `reg query`, which looks like
+/// `    PATH    REG_EXPAND_SZ    C:\a;C:\b`
+fn parse_reg_query(output: &str, key: &str) -> Option<(String, String)> {
+    for line in output.lines() {
+        let line = line.trim_start();
+        // registry value names aren't case sensitive
+        let name_matches = line
+            .get(..key.len())
+            .is_some_and(|name| name.eq_ignore_ascii_case(key));
+        let type_start = match line.find("REG_") {
+            Some(type_start) if name_matches => type_start,
+            _ => continue,
+        };
+        return match line[type_start..].split_once("    ") {
+            Some((kind, value)) => Some((String::from(kind), String::from(value))),
+            None => Some((String::from(line[type_start..].trim_end()), String::new())),
+        };
+    }
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Runs `reg` and `SetX` against a registry kept in memory: value name => (type, data)
+    struct FakeRunner {
+        registry: Mutex<HashMap<String, (String, String)>>,
+    }
+
+    impl FakeRunner {
+        fn with(values: &[(&str, &str, &str)]) -> FakeRunner {
+            let registry = values
+                .iter()
+                .map(|(name, kind, data)| {
+                    (
+                        String::from(*name),
+                        (String::from(*kind), String::from(*data)),
+                    )
+                })
+                .collect();
+            return FakeRunner {
+                registry: Mutex::new(registry),
+            };
+        }
+
+        fn value(&self, name: &str) -> Option<(String, String)> {
+            return self.registry.lock().unwrap().get(name).cloned();
+        }
+    }
+
+    impl CommandRunner for FakeRunner {
+        fn run(&self, program: &str, args: &[&str]) -> Result<String, GunmetalError> {
+            let mut registry = self.registry.lock().unwrap();
+            let not_found =
+                || GunmetalError::CommandFailed(ErrorInfo::new(String::from("not found")));
+            match (program, args) {
+                ("reg", ["query", _, "/v", name]) => {
+                    let (kind, data) = registry.get(*name).ok_or_else(not_found)?;
+                    return Ok(format!(
+                        "\r\nHKEY_CURRENT_USER\\Environment\r\n    {}    {}    {}\r\n\r\n",
+                        name, kind, data
+                    ));
+                }
+                ("reg", ["add", _, "/v", name, "/t", kind, "/d", data, "/f"]) => {
+                    registry.insert(
+                        String::from(*name),
+                        (String::from(*kind), String::from(*data)),
+                    );
+                }
+                ("reg", ["delete", _, "/v", name, "/f"]) => {
+                    registry.remove(*name).ok_or_else(not_found)?;
+                }
+                ("SetX", [name, data]) => {
+                    registry.insert(
+                        String::from(*name),
+                        (String::from("REG_SZ"), String::from(*data)),
+                    );
+                }
+                _ => panic!("unexpected command: {} {:?}", program, args),
+            }
+            return Ok(String::new());
+        }
+    }
+
+    #[test]
+    fn keeps_an_expandable_path_expandable() {
+        let backend = WindowsBackend::new(FakeRunner::with(&[(
+            "Path",
+            "REG_EXPAND_SZ",
+            "%USERPROFILE%\\bin;C:\\tools",
+        )]));
+        backend
+            .write("Path", "C:\\new", Position::Append, false)
+            .unwrap();
+        assert_eq!(
+            backend.runner.value("Path"),
+            Some((
+                String::from("REG_EXPAND_SZ"),
+                String::from("%USERPROFILE%\\bin;C:\\tools;C:\\new")
+            ))
+        );
+
+        backend.remove("Path", "C:\\tools", false).unwrap();
+        assert_eq!(
+            backend.runner.value("Path"),
+            Some((
+                String::from("REG_EXPAND_SZ"),
+                String::from("%USERPROFILE%\\bin;C:\\new")
+            ))
+        );
+    }
+
+    #[test]
+    fn saves_new_references_as_expandable() {
+        let backend = WindowsBackend::new(FakeRunner::with(&[]));
+        backend
+            .write("TOOLS", "%LOCALAPPDATA%\\tools", Position::Prepend, false)
+            .unwrap();
+        assert_eq!(
+            backend.runner.value("TOOLS"),
+            Some((
+                String::from("REG_EXPAND_SZ"),
+                String::from("%LOCALAPPDATA%\\tools")
+            ))
+        );
+    }
+
+    #[test]
+    fn keeps_plain_values_plain() {
+        let backend = WindowsBackend::new(FakeRunner::with(&[("LIB", "REG_SZ", "C:\\a")]));
+        backend
+            .write("LIB", "C:\\b", Position::Prepend, false)
+            .unwrap();
+        assert_eq!(
+            backend.runner.value("LIB"),
+            Some((String::from("REG_SZ"), String::from("C:\\b;C:\\a")))
+        );
+    }
+
+    #[test]
+    fn deletes_a_variable_with_no_entries_left() {
+        let backend = WindowsBackend::new(FakeRunner::with(&[("LIB", "REG_EXPAND_SZ", "C:\\a")]));
+        backend.remove("LIB", "C:\\a", false).unwrap();
+        assert_eq!(backend.runner.value("LIB"), None);
+    }
+
+    #[test]
+    fn writes_nothing_on_a_dry_run() {
+        let backend = WindowsBackend::new(FakeRunner::with(&[("LIB", "REG_EXPAND_SZ", "C:\\a")]));
+        let diff = backend
+            .write("LIB", "C:\\b", Position::Append, true)
+            .unwrap();
+        assert!(diff.contains("+C:\\b"), "{}", diff);
+        assert_eq!(
+            backend.runner.value("LIB"),
+            Some((String::from("REG_EXPAND_SZ"), String::from("C:\\a")))
+        );
+    }
+
+    #[test]
+    fn reads_the_type_from_reg_query() {
+        let output =
+            "\r\nHKEY_CURRENT_USER\\Environment\r\n    path    REG_EXPAND_SZ    C:\\a;C:\\b\r\n";
+        assert_eq!(
+            parse_reg_query(output, "PATH"),
+            Some((String::from("REG_EXPAND_SZ"), String::from("C:\\a;C:\\b")))
+        );
+        assert_eq!(parse_reg_query(output, "OTHER"), None);
+    }
+}
@@ -0,0 +1,20 @@
+//! Privileged helper for system-wide changes.
+//!
+//! The GUI never runs as root. For system scope writes it launches this binary through `pkexec` (or `sudo`)
+//! and sends it a single JSON `HelperRequest` on stdin. The request only names one of the fixed system
+//! targets plus a key and value, so the helper can't be used to write anywhere else.
+
+use std::io;
+use std::process::exit;
+
+use gunmetal_core::system_scope::handle_helper_request;
+
+fn main() {
+    match handle_helper_request(io::stdin()) {
+        Ok(message) => println!("{}", message),
+        Err(err) => {
+            eprintln!("{}", err);
+            exit(1);
+        }
+    }
+}
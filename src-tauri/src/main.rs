@@ -1,25 +1,237 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::env;
+//! The Tauri app. Every command here only forwards to `gunmetal_core`, which does the actual work.
 
-use settings_utils::{gather_setting, get_config_path};
-mod add_vars;
-mod consts_and_errors;
-mod get_vars;
-mod settings_utils;
+// explicit `return` is the style of this codebase
+#![allow(clippy::needless_return)]
 
+use std::collections::HashMap;
+
+use gunmetal_core::changes::Change;
+use gunmetal_core::env_backend::{Position, Target};
+use gunmetal_core::env_export::ExportFormat;
+use gunmetal_core::file_watcher::VarsChanged;
+use gunmetal_core::project_env::ProjectEnv;
+use gunmetal_core::search::{SearchQuery, SearchResults};
+use gunmetal_core::settings::{load_settings, Settings};
+use gunmetal_core::settings_utils::{gather_setting, get_config_path};
+use gunmetal_core::shell_detect::DetectedShell;
+use gunmetal_core::snapshots::{SnapshotDiff, SnapshotInfo};
+use gunmetal_core::system_scope::{Scope, SystemTarget};
+use gunmetal_core::{
+    add_vars, changes, diagnostics, dotenv, env_export, file_watcher, get_vars, i18n, logging,
+    manage_settings, project_env, remove_vars, search, secrets, shell_detect, snapshots,
+    GunmetalError,
+};
+use tauri::Manager;
+
+/// Event sent to the frontend when the variables change
+const VARS_CHANGED_EVENT: &str = "vars-changed";
+
+// the code tauri's macros expand to trips these lints, not the code here
+#[allow(clippy::unit_arg, clippy::unused_unit)]
 fn main() {
     tauri::Builder::default()
+        .setup(|app| {
+            // the app still works without a log file, it's only missing from bug reports
+            if let Err(err) = logging::init_logging() {
+                eprintln!("{}", err);
+            }
+            // show messages in the language chosen in the settings, if there is one
+            if let Ok(settings) =
+                get_config_path().and_then(|path| load_settings(&format!("{}/settings.json", path)))
+            {
+                i18n::set_locale(&settings.locale);
+            }
+            // keep the window up to date when profiles are edited, in Gunmetal or anywhere else
+            let handle = app.handle();
+            file_watcher::start_watching(move |changed: VarsChanged| {
+                if let Err(err) = handle.emit_all(VARS_CHANGED_EVENT, changed) {
+                    log::error!("{}", err);
+                }
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
-            get_vars::get_vars,
-            add_vars::add_var,
+            get_vars,
+            list_targets,
+            add_var,
+            remove_var,
+            apply_changes,
+            get_settings,
+            update_settings,
+            reset_settings,
+            detect_shells,
+            export_diagnostics,
+            save_snapshot,
+            list_snapshots,
+            diff_snapshots,
+            import_dotenv,
+            export_dotenv,
+            export_env,
+            open_project,
+            add_project_var,
+            list_secrets,
+            mark_secret,
+            unmark_secret,
+            reveal_secret,
+            search_vars,
             get_shell_location
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
+#[tauri::command]
+fn get_vars() -> Result<HashMap<String, Vec<String>>, GunmetalError> {
+    return get_vars::get_vars();
+}
+
+#[tauri::command]
+fn list_targets() -> Result<Vec<Target>, GunmetalError> {
+    return get_vars::list_targets();
+}
+
+#[tauri::command]
+fn add_var(
+    key: String,
+    var_submission: String,
+    dry_run: Option<bool>,
+    scope: Option<Scope>,
+    position: Option<Position>,
+    system_target: Option<SystemTarget>,
+) -> Result<String, GunmetalError> {
+    return add_vars::add_var(key, var_submission, dry_run, scope, position, system_target);
+}
+
+#[tauri::command]
+fn remove_var(key: String, value: String, dry_run: Option<bool>) -> Result<String, GunmetalError> {
+    return remove_vars::remove_var(key, value, dry_run);
+}
+
+#[tauri::command]
+fn apply_changes(changes: Vec<Change>, dry_run: Option<bool>) -> Result<String, GunmetalError> {
+    return changes::apply_changes(changes, dry_run);
+}
+
+#[tauri::command]
+fn get_settings() -> Result<Settings, GunmetalError> {
+    return manage_settings::get_settings();
+}
+
+#[tauri::command]
+fn update_settings(settings: Settings) -> Result<Settings, GunmetalError> {
+    return manage_settings::update_settings(settings);
+}
+
+#[tauri::command]
+fn reset_settings() -> Result<Settings, GunmetalError> {
+    return manage_settings::reset_settings();
+}
+
+#[tauri::command]
+fn detect_shells() -> Vec<DetectedShell> {
+    return shell_detect::detect_shells();
+}
+
+#[tauri::command]
+fn export_diagnostics(path: Option<String>) -> Result<String, GunmetalError> {
+    return diagnostics::export_diagnostics(path);
+}
+
+#[tauri::command]
+fn save_snapshot(name: Option<String>) -> Result<SnapshotInfo, GunmetalError> {
+    return snapshots::save_snapshot(name);
+}
+
+#[tauri::command]
+fn list_snapshots() -> Vec<SnapshotInfo> {
+    return snapshots::list_snapshots();
+}
+
+#[tauri::command]
+fn diff_snapshots(a: String, b: Option<String>) -> Result<SnapshotDiff, GunmetalError> {
+    return snapshots::diff_snapshots(&a, b.as_deref());
+}
+
+#[tauri::command]
+fn import_dotenv(
+    path: String,
+    target: Option<Scope>,
+    dry_run: Option<bool>,
+) -> Result<String, GunmetalError> {
+    return dotenv::import_dotenv(path, target, dry_run);
+}
+
+#[tauri::command]
+fn export_dotenv(
+    keys: Vec<String>,
+    path: String,
+    dry_run: Option<bool>,
+) -> Result<String, GunmetalError> {
+    return dotenv::export_dotenv(keys, path, dry_run);
+}
+
+#[tauri::command]
+fn export_env(
+    path: String,
+    format: Option<ExportFormat>,
+    redact: Option<bool>,
+) -> Result<String, GunmetalError> {
+    return env_export::save_export(path, format, redact);
+}
+
+#[tauri::command]
+fn open_project(dir: String) -> Result<ProjectEnv, GunmetalError> {
+    return project_env::open_project(&dir);
+}
+
+#[tauri::command]
+fn add_project_var(
+    dir: String,
+    key: String,
+    value: String,
+    dry_run: Option<bool>,
+    position: Option<Position>,
+) -> Result<String, GunmetalError> {
+    return project_env::add_project_var(&dir, &key, &value, dry_run, position);
+}
+
+#[tauri::command]
+fn list_secrets() -> Vec<String> {
+    return secrets::list_secrets();
+}
+
+#[tauri::command]
+fn mark_secret(
+    key: String,
+    value: Option<String>,
+    passphrase: String,
+    dry_run: Option<bool>,
+) -> Result<String, GunmetalError> {
+    return secrets::mark_secret(key, value, passphrase, dry_run);
+}
+
+#[tauri::command]
+fn unmark_secret(
+    key: String,
+    passphrase: String,
+    dry_run: Option<bool>,
+) -> Result<String, GunmetalError> {
+    return secrets::unmark_secret(key, passphrase, dry_run);
+}
+
+#[tauri::command]
+fn reveal_secret(key: String, passphrase: String) -> Result<String, GunmetalError> {
+    return secrets::reveal_secret(key, passphrase);
+}
+
+#[tauri::command]
+fn search_vars(query: SearchQuery) -> Result<SearchResults, GunmetalError> {
+    return search::search_vars(query);
+}
+
 #[tauri::command]
 fn get_shell_location() -> Result<String, String> {
     let config_path = get_config_path();
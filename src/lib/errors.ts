@@ -0,0 +1,31 @@
+// errors sent by the backend, see GunmetalError in consts_and_errors.rs
+export interface GunmetalError {
+	code: string; // ex: "conflict", "profile_not_found", "permission_denied"
+	message: string;
+	path: string | null;
+	os_error: string | null;
+	suggestion: string;
+	details: string | null;
+	rule: string | null; // validation rule an invalid key or value broke, ex: "key_not_identifier"
+	field: string | null; // "key" or "value", with rule
+}
+
+export function isGunmetalError(err: unknown): err is GunmetalError {
+	return typeof err == "object" && err != null && "code" in err && "message" in err;
+}
+
+// text to show the user for an error from invoke()
+export function errorText(err: unknown): string {
+	if (!isGunmetalError(err)) {
+		return String(err);
+	}
+	let text = `${err.message}\n\n${err.suggestion}`;
+	if (err.details) {
+		text += `\n\nFull Error:\n${err.details}`;
+	}
+	return text;
+}
+
+export function hasCode(err: unknown, code: string): boolean {
+	return isGunmetalError(err) && err.code == code;
+}